@@ -629,6 +629,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn verify_fork_single_unit() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let forker_index = NodeIndex(6);
+        let own_keychain = Keychain::new(n_members, own_index);
+        let forker_keychain = Keychain::new(n_members, forker_index);
+        let this = Handler::new(own_keychain, 0);
+        let unit = full_unit(n_members, forker_index, 0, Some(0));
+        let signed_unit = Signed::sign(unit, &forker_keychain).into_unchecked();
+        let alert = Alert::new(own_index, (signed_unit.clone(), signed_unit), vec![]);
+        assert_eq!(this.verify_fork(&alert), Err(Error::SingleUnit(own_index)));
+    }
+
     #[test]
     fn alert_confirmed_out_of_the_blue() {
         alert_confirmed(false, true);
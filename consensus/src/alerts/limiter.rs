@@ -0,0 +1,62 @@
+use crate::{NodeIndex, Round};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    time::{Duration, Instant},
+};
+
+/// Limits the alerts we send, so that there is at most one alert about a creator equivocating
+/// in a given round within the cooldown.
+pub struct AlertLimiter {
+    cooldown: Duration,
+    last_sent: HashMap<(NodeIndex, Round), Instant>,
+}
+
+impl AlertLimiter {
+    pub fn new(cooldown: Duration) -> Self {
+        AlertLimiter {
+            cooldown,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    /// Whether an alert about the creator equivocating in the round can be sent at the given time.
+    /// If so, the alert is assumed to be sent.
+    pub fn allow(&mut self, creator: NodeIndex, round: Round, now: Instant) -> bool {
+        let cooldown = self.cooldown;
+        self.last_sent
+            .retain(|_, sent| now.saturating_duration_since(*sent) < cooldown);
+        match self.last_sent.entry((creator, round)) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{alerts::limiter::AlertLimiter, NodeIndex};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn allows_one_alert_per_creator_and_round() {
+        let mut limiter = AlertLimiter::new(Duration::from_secs(10));
+        let now = Instant::now();
+        assert!(limiter.allow(NodeIndex(1), 3, now));
+        assert!(!limiter.allow(NodeIndex(1), 3, now));
+        assert!(limiter.allow(NodeIndex(1), 4, now));
+        assert!(limiter.allow(NodeIndex(2), 3, now));
+    }
+
+    #[test]
+    fn allows_alert_again_after_cooldown() {
+        let cooldown = Duration::from_secs(10);
+        let mut limiter = AlertLimiter::new(cooldown);
+        let now = Instant::now();
+        assert!(limiter.allow(NodeIndex(1), 3, now));
+        assert!(!limiter.allow(NodeIndex(1), 3, now + cooldown / 2));
+        assert!(limiter.allow(NodeIndex(1), 3, now + cooldown));
+    }
+}
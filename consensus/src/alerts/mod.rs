@@ -1,7 +1,7 @@
 use crate::{
     units::{UncheckedSignedUnit, Unit},
     Data, Hasher, Index, Keychain, MultiKeychain, Multisigned, NodeIndex, PartialMultisignature,
-    Signable, Signature, UncheckedSigned,
+    Round, Signable, Signature, UncheckedSigned,
 };
 use aleph_bft_rmc::Message as RmcMessage;
 use codec::{Decode, Encode};
@@ -10,6 +10,7 @@ use parking_lot::RwLock;
 use std::ops::Deref;
 
 mod handler;
+mod limiter;
 mod service;
 
 pub use handler::Handler;
@@ -79,6 +80,11 @@ impl<H: Hasher, D: Data, S: Signature> Alert<H, D, S> {
         self.proof.0.as_signable().creator()
     }
 
+    /// The round of the fork, with the same caveat as `forker`.
+    pub fn fork_round(&self) -> Round {
+        self.proof.0.as_signable().round()
+    }
+
     pub fn included_data(&self) -> Vec<D> {
         // Only legit units might end up in the DAG, we can ignore the fork proof.
         self.legit_units
@@ -1,6 +1,7 @@
 use crate::{
     alerts::{
         handler::{Handler, RmcResponse},
+        limiter::AlertLimiter,
         Alert, AlertMessage, ForkingNotification, NetworkMessage,
     },
     Data, Hasher, MultiKeychain, Multisigned, NodeIndex, Receiver, Recipient, Sender, Terminator,
@@ -8,7 +9,7 @@ use crate::{
 use aleph_bft_rmc::{DoublingDelayScheduler, Message as RmcMessage};
 use futures::{FutureExt, StreamExt};
 use log::{debug, error, trace, warn};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const LOG_TARGET: &str = "AlephBFT-alerter";
 type RmcService<H, MK, S, M> =
@@ -22,6 +23,7 @@ pub struct Service<H: Hasher, D: Data, MK: MultiKeychain> {
     node_index: NodeIndex,
     exiting: bool,
     handler: Handler<H, D, MK>,
+    limiter: AlertLimiter,
     rmc_service: RmcService<H::Hash, MK, MK::Signature, MK::PartialMultisignature>,
 }
 
@@ -33,7 +35,12 @@ pub struct IO<H: Hasher, D: Data, MK: MultiKeychain> {
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
-    pub fn new(keychain: MK, io: IO<H, D, MK>, handler: Handler<H, D, MK>) -> Service<H, D, MK> {
+    pub fn new(
+        keychain: MK,
+        io: IO<H, D, MK>,
+        handler: Handler<H, D, MK>,
+        alert_cooldown: Duration,
+    ) -> Service<H, D, MK> {
         let IO {
             messages_for_network,
             messages_from_network,
@@ -56,6 +63,7 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
             node_index,
             exiting: false,
             handler,
+            limiter: AlertLimiter::new(alert_cooldown),
             rmc_service,
         }
     }
@@ -148,6 +156,11 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
 
     fn handle_alert_from_runway(&mut self, alert: Alert<H, D, MK::Signature>) {
         trace!(target: LOG_TARGET, "Handling alert {:?}.", alert);
+        let (forker, round) = (alert.forker(), alert.fork_round());
+        if !self.limiter.allow(forker, round, Instant::now()) {
+            debug!(target: LOG_TARGET, "Already alerted about {:?} forking at round {}, skipping.", forker, round);
+            return;
+        }
         let (message, recipient, hash) = self.handler.on_own_alert(alert.clone());
         self.send_message_for_network(message, recipient);
         if let Some(multisigned) = self.rmc_service.start_rmc(hash) {
@@ -197,3 +210,99 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Service<H, D, MK> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        alerts::{service::Service, Alert, AlertMessage, Handler, IO},
+        units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit},
+        NodeCount, NodeIndex, NodeMap, Recipient, Round, Signed,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use aleph_bft_rmc::Message as RmcMessage;
+    use aleph_bft_types::{Index, Signable};
+    use futures::channel::mpsc;
+    use std::time::Duration;
+
+    fn signed_unit(
+        keychain: &Keychain,
+        n_members: NodeCount,
+        round: Round,
+        variant: u32,
+    ) -> UncheckedSignedUnit<Hasher64, Data, Signature> {
+        let unit = FullUnit::new(
+            PreUnit::new(
+                keychain.index(),
+                round,
+                ControlHash::new(&NodeMap::with_size(n_members)),
+            ),
+            Some(variant),
+            0,
+        );
+        Signed::sign(unit, keychain).into_unchecked()
+    }
+
+    #[tokio::test]
+    async fn alerts_once_about_forks_in_the_same_round() {
+        let n_members = NodeCount(7);
+        let own_index = NodeIndex(0);
+        let forker_keychain = Keychain::new(n_members, NodeIndex(6));
+        let own_keychain = Keychain::new(n_members, own_index);
+        let (messages_for_network, mut messages_from_service) = mpsc::unbounded();
+        let (_messages_for_service, messages_from_network) = mpsc::unbounded();
+        let (notifications_for_units, _notifications_from_service) = mpsc::unbounded();
+        let (_alerts_for_service, alerts_from_units) = mpsc::unbounded();
+        let mut service: Service<Hasher64, Data, Keychain> = Service::new(
+            own_keychain,
+            IO {
+                messages_for_network,
+                messages_from_network,
+                notifications_for_units,
+                alerts_from_units,
+            },
+            Handler::new(own_keychain, 0),
+            Duration::from_secs(60),
+        );
+
+        let round = 3;
+        let alerts: Vec<_> = [(0, 1), (0, 2)]
+            .into_iter()
+            .map(|(first, second)| {
+                let proof = (
+                    signed_unit(&forker_keychain, n_members, round, first),
+                    signed_unit(&forker_keychain, n_members, round, second),
+                );
+                Alert::new(own_index, proof, vec![])
+            })
+            .collect();
+        assert_ne!(Signable::hash(&alerts[0]), Signable::hash(&alerts[1]));
+        for alert in alerts.iter().cloned() {
+            service.handle_alert_from_runway(alert);
+        }
+
+        let (message, recipient) = messages_from_service
+            .try_next()
+            .expect("channel is open")
+            .expect("the alert was sent");
+        assert!(matches!(message, AlertMessage::ForkAlert(_)));
+        assert_eq!(recipient, Recipient::Everyone);
+        assert!(messages_from_service.try_next().is_err());
+
+        match service.rmc_service.next_message().await {
+            RmcMessage::SignedHash(signed_hash) => {
+                assert_eq!(
+                    signed_hash.as_signable().as_signable(),
+                    &Signable::hash(&alerts[0])
+                )
+            }
+            message => panic!("unexpected rmc message {:?}", message),
+        }
+        // Only the rebroadcast of the started rmc could come, and not before the initial delay.
+        assert!(tokio::time::timeout(
+            Duration::from_millis(100),
+            service.rmc_service.next_message()
+        )
+        .await
+        .is_err());
+    }
+}
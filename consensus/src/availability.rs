@@ -0,0 +1,150 @@
+use crate::{Data, DataAvailability, FinalizationHandler, NodeIndex, Receiver, Round, Sender};
+use futures::{channel::mpsc, Future, StreamExt};
+use futures_timer::Delay;
+use log::{debug, warn};
+use std::time::Duration;
+
+const LOG_TARGET: &str = "AlephBFT-availability";
+
+// How long to wait before checking again whether data reported as unavailable became available.
+const RECHECK_DELAY: Duration = Duration::from_millis(500);
+
+enum Finalization<D> {
+    Data(D),
+    Unit(NodeIndex, Round, Option<D>),
+    SessionFinished,
+}
+
+/// A finalization handler passing everything finalized on, but only once the data is available.
+/// Created by [`check_availability`].
+pub struct AvailabilityCheckingHandler<D: Data> {
+    finalizations: Sender<Finalization<D>>,
+}
+
+impl<D: Data> AvailabilityCheckingHandler<D> {
+    fn send(&self, finalization: Finalization<D>) {
+        if self.finalizations.unbounded_send(finalization).is_err() {
+            warn!(target: LOG_TARGET, "Availability checks stopped, dropping a finalization.");
+        }
+    }
+}
+
+impl<D: Data> FinalizationHandler<D> for AvailabilityCheckingHandler<D> {
+    fn data_finalized(&mut self, data: D) {
+        self.send(Finalization::Data(data));
+    }
+
+    fn unit_finalized(&mut self, creator: NodeIndex, round: Round, data: Option<D>) {
+        self.send(Finalization::Unit(creator, round, data));
+    }
+
+    fn session_finished(&mut self) {
+        self.send(Finalization::SessionFinished);
+    }
+}
+
+async fn wait_until_available<D: Data, DA: DataAvailability<D>>(data: &D, availability: &DA) {
+    while !availability.is_available(data).await {
+        debug!(target: LOG_TARGET, "Finalized data not available yet, checking again later.");
+        Delay::new(RECHECK_DELAY).await;
+    }
+}
+
+async fn pass_available<D: Data, FH: FinalizationHandler<D>, DA: DataAvailability<D>>(
+    mut finalizations: Receiver<Finalization<D>>,
+    mut finalization_handler: FH,
+    availability: DA,
+) {
+    while let Some(finalization) = finalizations.next().await {
+        match finalization {
+            Finalization::Data(data) => {
+                wait_until_available(&data, &availability).await;
+                finalization_handler.data_finalized(data);
+            }
+            Finalization::Unit(creator, round, data) => {
+                if let Some(data) = &data {
+                    wait_until_available(data, &availability).await;
+                }
+                finalization_handler.unit_finalized(creator, round, data);
+            }
+            Finalization::SessionFinished => finalization_handler.session_finished(),
+        }
+    }
+}
+
+/// Holds back the calls to the finalization handler until the finalized data is confirmed to be available,
+/// preserving their order. The ordering itself proceeds regardless, only the reporting waits.
+/// Pass the returned handler to the session and run the returned future alongside it, it finishes
+/// once the handler is dropped and everything got passed on.
+pub fn check_availability<D: Data, FH: FinalizationHandler<D>, DA: DataAvailability<D>>(
+    finalization_handler: FH,
+    availability: DA,
+) -> (
+    AvailabilityCheckingHandler<D>,
+    impl Future<Output = ()> + Send,
+) {
+    let (finalizations, finalizations_rx) = mpsc::unbounded();
+    (
+        AvailabilityCheckingHandler { finalizations },
+        pass_available(finalizations_rx, finalization_handler, availability),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check_availability, DataAvailability, FinalizationHandler, NodeIndex};
+    use aleph_bft_mock::{Data, FinalizationHandler as MockFinalizationHandler};
+    use futures::{
+        channel::oneshot,
+        future::{BoxFuture, FutureExt, Shared},
+        StreamExt,
+    };
+    use std::time::Duration;
+
+    // All data is available right away, except for one item, which becomes available once released.
+    struct DelayedAvailability {
+        delayed: Data,
+        released: Shared<BoxFuture<'static, ()>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DataAvailability<Data> for DelayedAvailability {
+        async fn is_available(&self, data: &Data) -> bool {
+            if *data == self.delayed {
+                self.released.clone().await;
+            }
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn defers_finalization_until_available() {
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let availability = DelayedAvailability {
+            delayed: 2,
+            released: release_rx.map(|_| ()).boxed().shared(),
+        };
+        let (finalization_handler, mut finalized_rx) = MockFinalizationHandler::new();
+        let (mut handler, checks) = check_availability(finalization_handler, availability);
+        let checks = tokio::spawn(checks);
+
+        for (round, data) in [1, 2, 3].into_iter().enumerate() {
+            handler.unit_finalized(NodeIndex(0), round as u16, Some(data));
+        }
+        assert_eq!(finalized_rx.next().await, Some(1));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Neither the delayed data nor anything after it was passed on.
+        assert!(finalized_rx.try_next().is_err());
+
+        release_tx
+            .send(())
+            .expect("the availability check is waiting");
+        assert_eq!(finalized_rx.next().await, Some(2));
+        assert_eq!(finalized_rx.next().await, Some(3));
+
+        drop(handler);
+        checks
+            .await
+            .expect("the checks finish once the handler is dropped");
+    }
+}
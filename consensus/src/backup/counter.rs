@@ -0,0 +1,40 @@
+use crate::{Round, Sender};
+use futures::channel::oneshot;
+use thiserror::Error;
+
+/// A round to persist, together with the sender announcing that it is durable.
+pub type RoundAdvance = (Round, oneshot::Sender<()>);
+
+/// Keeps the round of every unit we are about to create in the backup, made durable before the unit
+/// gets created, so that after a restart we never create another unit of a round we might have already
+/// created one of, even if that unit itself did not make it into the backup.
+/// A crash between persisting a round and creating its unit leaves us without a unit of that round,
+/// so we cannot create any more units in the session unless some other node got it.
+#[derive(Clone)]
+pub struct RoundCounter {
+    rounds_for_saver: Sender<RoundAdvance>,
+}
+
+/// The round could not be persisted.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum RoundCounterError {
+    #[error("Backup saver stopped before the round was persisted.")]
+    SaverStopped,
+}
+
+impl RoundCounter {
+    pub fn new(rounds_for_saver: Sender<RoundAdvance>) -> Self {
+        RoundCounter { rounds_for_saver }
+    }
+
+    /// Persist the given round, returns once it is durable.
+    pub async fn advance(&self, round: Round) -> Result<(), RoundCounterError> {
+        let (persisted_tx, persisted_rx) = oneshot::channel();
+        self.rounds_for_saver
+            .unbounded_send((round, persisted_tx))
+            .map_err(|_| RoundCounterError::SaverStopped)?;
+        persisted_rx
+            .await
+            .map_err(|_| RoundCounterError::SaverStopped)
+    }
+}
@@ -5,11 +5,12 @@ use std::{
     pin::Pin,
 };
 
-use codec::{Decode, Error as CodecError};
+use codec::Error as CodecError;
 use futures::{channel::oneshot, AsyncRead, AsyncReadExt};
 use log::{error, info, warn};
 
 use crate::{
+    backup::{decode_items, BackupItem},
     units::{UncheckedSignedUnit, Unit, UnitCoord},
     Data, Hasher, NodeIndex, Round, SessionId, Signature,
 };
@@ -72,6 +73,7 @@ pub struct BackupLoader<H: Hasher, D: Data, S: Signature, R: AsyncRead> {
     backup: Pin<Box<R>>,
     index: NodeIndex,
     session_id: SessionId,
+    starting_round: Option<Round>,
     _phantom: PhantomData<(H, D, S)>,
 }
 
@@ -81,19 +83,37 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
             backup: Box::pin(backup),
             index,
             session_id,
+            starting_round: None,
             _phantom: PhantomData,
         }
     }
 
-    async fn load(&mut self) -> Result<Vec<UncheckedSignedUnit<H, D, S>>, LoaderError> {
+    /// Start creating at the given round instead of the one inferred from the backup and collection,
+    /// as long as it does not conflict with them.
+    pub fn with_starting_round(self, starting_round: Option<Round>) -> Self {
+        BackupLoader {
+            starting_round,
+            ..self
+        }
+    }
+
+    /// The units in the backup, and the highest persisted round of the round counter.
+    async fn load(
+        &mut self,
+    ) -> Result<(Vec<UncheckedSignedUnit<H, D, S>>, Option<Round>), LoaderError> {
         let mut buf = Vec::new();
         self.backup.read_to_end(&mut buf).await?;
-        let input = &mut &buf[..];
-        let mut result = Vec::new();
-        while !input.is_empty() {
-            result.push(<UncheckedSignedUnit<H, D, S>>::decode(input)?);
+        let mut units = Vec::new();
+        let mut persisted_round = None;
+        for item in decode_items::<H, D, S>(&buf)? {
+            match item {
+                BackupItem::Unit(unit) => units.push(unit),
+                BackupItem::RoundCounter(round) => {
+                    persisted_round = persisted_round.max(Some(round))
+                }
+            }
         }
-        Ok(result)
+        Ok((units, persisted_round))
     }
 
     fn verify_units(&self, units: &Vec<UncheckedSignedUnit<H, D, S>>) -> Result<(), LoaderError> {
@@ -140,11 +160,24 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
     ) -> Option<Round> {
         if next_round_backup < next_round_collection {
             // Our newest unit doesn't appear in the backup. This indicates a serious issue, for example
-            // a different node running with the same pair of keys. It's safer not to continue.
+            // a lost backup or a different node running with the same pair of keys. It's safer not to continue,
+            // unless the operator explicitly configured a starting round above the units we know of.
+            if let Some(configured_round) = self.starting_round {
+                if configured_round >= next_round_collection {
+                    warn!(
+                        target: LOG_TARGET, "Backup state behind unit collection state, continuing from the configured starting round {:?}. Next round inferred from: collection: {:?}, backup: {:?}",
+                        configured_round,
+                        next_round_collection,
+                        next_round_backup,
+                    );
+                    return Some(next_round_collection);
+                }
+            }
             error!(
-                target: LOG_TARGET, "Backup state behind unit collection state. Next round inferred from: collection: {:?}, backup: {:?}",
+                target: LOG_TARGET, "Backup state behind unit collection state. Next round inferred from: collection: {:?}, backup: {:?}. Configure a starting round of at least {:?} to start anyway.",
                 next_round_collection,
                 next_round_backup,
+                next_round_collection,
             );
             return None;
         };
@@ -168,7 +201,7 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
         starting_round: oneshot::Sender<Option<Round>>,
         next_round_collection: oneshot::Receiver<Round>,
     ) {
-        let units = match self.load().await {
+        let (units, persisted_round) = match self.load().await {
             Ok(items) => items,
             Err(e) => {
                 error!(target: LOG_TARGET, "unable to load backup data: {}", e);
@@ -187,6 +220,8 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
             .filter(|u| u.as_signable().creator() == self.index)
             .map(|u| u.as_signable().round())
             .max()
+            // We might have created a unit of the persisted round that did not make it into the backup.
+            .max(persisted_round)
             .map(|round| round + 1)
             .unwrap_or(0);
 
@@ -229,6 +264,21 @@ impl<H: Hasher, D: Data, S: Signature, R: AsyncRead> BackupLoader<H, D, S, R> {
                 return;
             }
         };
+        let next_round = match self.starting_round {
+            Some(configured_round) if configured_round < next_round => {
+                // Units of the configured round or above were already created, starting there would produce forks.
+                error!(
+                    target: LOG_TARGET,
+                    "Configured starting round {:?} conflicts with already created units. Next round inferred: {:?}",
+                    configured_round,
+                    next_round,
+                );
+                self.on_shutdown(starting_round);
+                return;
+            }
+            Some(configured_round) => configured_round,
+            None => next_round,
+        };
 
         if let Err(e) = starting_round.send(Some(next_round)) {
             error!(target: LOG_TARGET, "Could not send starting round: {:?}", e);
@@ -244,7 +294,7 @@ mod tests {
     use aleph_bft_mock::{Data, Hasher64, Keychain, Loader, Signature};
 
     use crate::{
-        backup::BackupLoader,
+        backup::{backup_header, BackupItem, BackupLoader},
         units::{
             create_preunits, creator_set, preunit_to_full_unit, preunit_to_unchecked_signed_unit,
             UncheckedSignedUnit as GenericUncheckedSignedUnit,
@@ -308,17 +358,36 @@ mod tests {
     }
 
     fn encode_all(items: Vec<UncheckedSignedUnit>) -> Vec<Vec<u8>> {
-        items.iter().map(|u| u.encode()).collect()
+        items
+            .into_iter()
+            .map(|u| BackupItem::Unit(u).encode())
+            .collect()
     }
 
     fn prepare_test(encoded_items: Vec<u8>) -> PrepareTestResponse<impl futures::Future> {
+        prepare_test_with_starting_round(encoded_items, None)
+    }
+
+    fn prepare_test_with_starting_round(
+        encoded_items: Vec<u8>,
+        configured_starting_round: Option<Round>,
+    ) -> PrepareTestResponse<impl futures::Future> {
+        let mut backup = backup_header();
+        backup.extend(encoded_items);
+        prepare_test_with_backup(backup, configured_starting_round)
+    }
+
+    fn prepare_test_with_backup(
+        backup: Vec<u8>,
+        configured_starting_round: Option<Round>,
+    ) -> PrepareTestResponse<impl futures::Future> {
         let (loaded_data_tx, loaded_data_rx) = oneshot::channel();
         let (starting_round_tx, starting_round_rx) = oneshot::channel();
         let (highest_response_tx, highest_response_rx) = oneshot::channel();
 
         let task = {
-            let mut backup_loader =
-                BackupLoader::new(Loader::new(encoded_items), NODE_ID, SESSION_ID);
+            let mut backup_loader = BackupLoader::new(Loader::new(backup), NODE_ID, SESSION_ID)
+                .with_starting_round(configured_starting_round);
 
             async move {
                 backup_loader
@@ -378,6 +447,52 @@ mod tests {
         assert_eq!(loaded_data_rx.await, Ok(items));
     }
 
+    #[tokio::test]
+    async fn configured_starting_round_is_used() {
+        let items: Vec<_> = produce_units(5, SESSION_ID).into_iter().flatten().collect();
+        let encoded_items = encode_all(items.clone()).into_iter().flatten().collect();
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test_with_starting_round(encoded_items, Some(7));
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(5).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(Some(7)));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
+    #[tokio::test]
+    async fn configured_starting_round_below_loaded_fails() {
+        let items: Vec<_> = produce_units(5, SESSION_ID).into_iter().flatten().collect();
+        let encoded_items = encode_all(items.clone()).into_iter().flatten().collect();
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test_with_starting_round(encoded_items, Some(3));
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(0).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(None));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
     #[tokio::test]
     async fn something_loaded_something_collected_succeeds() {
         let items: Vec<_> = produce_units(5, SESSION_ID).into_iter().flatten().collect();
@@ -421,6 +536,127 @@ mod tests {
         assert_eq!(loaded_data_rx.await, Ok(Vec::new()));
     }
 
+    #[tokio::test]
+    async fn nothing_loaded_something_collected_with_configured_round_succeeds() {
+        for (configured_round, expected_round) in [(0, None), (1, Some(1)), (3, Some(3))] {
+            let PrepareTestResponse {
+                task,
+                loaded_data_rx,
+                highest_response_tx,
+                starting_round_rx,
+            } = prepare_test_with_starting_round(Vec::new(), Some(configured_round));
+
+            let handle = tokio::spawn(async {
+                task.await;
+            });
+
+            highest_response_tx.send(1).unwrap();
+            handle.await.unwrap();
+
+            assert_eq!(starting_round_rx.await, Ok(expected_round));
+            assert_eq!(loaded_data_rx.await, Ok(Vec::new()));
+        }
+    }
+
+    #[tokio::test]
+    async fn persisted_round_is_not_reused_after_restart() {
+        // Our unit of round 3 got created, but the node went down before it was saved or reached anyone.
+        let items: Vec<_> = produce_units(3, SESSION_ID).into_iter().flatten().collect();
+        let mut encoded_items: Vec<u8> = encode_all(items.clone()).into_iter().flatten().collect();
+        BackupItem::<Hasher64, Data, Signature>::RoundCounter(3).encode_to(&mut encoded_items);
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test(encoded_items);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(3).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(Some(4)));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
+    #[tokio::test]
+    async fn loads_legacy_backup() {
+        let items: Vec<_> = produce_units(3, SESSION_ID).into_iter().flatten().collect();
+        let backup = items.iter().flat_map(|unit| unit.encode()).collect();
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test_with_backup(backup, None);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(2).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(Some(3)));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
+    #[tokio::test]
+    async fn loads_legacy_backup_continued_in_current_format() {
+        let items: Vec<_> = produce_units(4, SESSION_ID).into_iter().flatten().collect();
+        let (legacy_items, current_items) = items.split_at(2 * N_MEMBERS.0);
+        let mut backup: Vec<u8> = legacy_items.iter().flat_map(|unit| unit.encode()).collect();
+        backup.extend(backup_header());
+        backup.extend(encode_all(current_items.to_vec()).into_iter().flatten());
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx,
+            starting_round_rx,
+        } = prepare_test_with_backup(backup, None);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        highest_response_tx.send(3).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(Some(4)));
+        assert_eq!(loaded_data_rx.await, Ok(items));
+    }
+
+    #[tokio::test]
+    async fn unsupported_backup_version_fails() {
+        let items: Vec<_> = produce_units(3, SESSION_ID).into_iter().flatten().collect();
+        let mut backup = backup_header();
+        let version_at = backup.len() - 2;
+        backup[version_at] += 1;
+        backup.extend(encode_all(items).into_iter().flatten());
+
+        let PrepareTestResponse {
+            task,
+            loaded_data_rx,
+            highest_response_tx: _highest_response_tx,
+            starting_round_rx,
+        } = prepare_test_with_backup(backup, None);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        handle.await.unwrap();
+
+        assert_eq!(starting_round_rx.await, Ok(None));
+        assert!(loaded_data_rx.await.is_err());
+    }
+
     #[tokio::test]
     async fn loaded_smaller_then_collected_fails() {
         let items: Vec<_> = produce_units(3, SESSION_ID).into_iter().flatten().collect();
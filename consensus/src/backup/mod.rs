@@ -1,5 +1,72 @@
+use crate::{units::UncheckedSignedUnit, Data, Hasher, Round, Signature};
+use codec::{Decode, Encode, Error as CodecError};
+
+pub use counter::{RoundAdvance, RoundCounter, RoundCounterError};
 pub use loader::BackupLoader;
 pub use saver::BackupSaver;
 
+mod counter;
 mod loader;
 mod saver;
+
+/// An entry of the backup, which consists of encoded entries one after another.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub enum BackupItem<H: Hasher, D: Data, S: Signature> {
+    /// A unit that got added to the Dag, ours or someone else's.
+    Unit(UncheckedSignedUnit<H, D, S>),
+    /// The round of a unit we were about to create, see [`RoundCounter`].
+    RoundCounter(Round),
+}
+
+/// The version of the backup format, written in the header preceding the entries.
+const BACKUP_VERSION: u16 = 1;
+
+// Backups of older versions are bare encoded units, the first of which always has round 0.
+// The header starts like a unit of the highest possible round, so it cannot be mistaken for one.
+const BACKUP_MAGIC: [u8; 6] = [0xff, 0xff, b'A', b'B', b'F', b'T'];
+
+/// The header starting the entries saved by a run of the [`BackupSaver`].
+pub(crate) fn backup_header() -> Vec<u8> {
+    let mut header = BACKUP_MAGIC.to_vec();
+    BACKUP_VERSION.encode_to(&mut header);
+    header
+}
+
+/// Decode all the entries of a backup. Units saved before the first header are in the
+/// legacy format, without a version.
+pub(crate) fn decode_items<H: Hasher, D: Data, S: Signature>(
+    mut backup: &[u8],
+) -> Result<Vec<BackupItem<H, D, S>>, CodecError> {
+    let input = &mut backup;
+    let mut items = Vec::new();
+    let mut versioned = false;
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix(&BACKUP_MAGIC[..]) {
+            *input = rest;
+            if u16::decode(input)? != BACKUP_VERSION {
+                return Err("unsupported backup version".into());
+            }
+            versioned = true;
+            continue;
+        }
+        items.push(match versioned {
+            true => BackupItem::decode(input)?,
+            false => BackupItem::Unit(UncheckedSignedUnit::decode(input)?),
+        });
+    }
+    Ok(items)
+}
+
+/// The units in an encoded backup, skipping the other entries.
+#[cfg(test)]
+pub fn decode_units<H: Hasher, D: Data, S: Signature>(
+    backup: &[u8],
+) -> Result<Vec<UncheckedSignedUnit<H, D, S>>, CodecError> {
+    Ok(decode_items(backup)?
+        .into_iter()
+        .filter_map(|item| match item {
+            BackupItem::Unit(unit) => Some(unit),
+            BackupItem::RoundCounter(_) => None,
+        })
+        .collect())
+}
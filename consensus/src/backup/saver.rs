@@ -1,12 +1,14 @@
 use std::pin::Pin;
 
 use crate::{
+    backup::{backup_header, BackupItem, RoundAdvance},
     dag::DagUnit,
     units::{UncheckedSignedUnit, WrappedUnit},
-    Data, Hasher, MultiKeychain, Receiver, Sender, Terminator,
+    Data, Hasher, MultiKeychain, Receiver, Round, Sender, SyncMode, Terminator,
 };
 use codec::Encode;
-use futures::{AsyncWrite, AsyncWriteExt, FutureExt, StreamExt};
+use futures::{future::pending, AsyncWrite, AsyncWriteExt, FutureExt, StreamExt};
+use futures_timer::Delay;
 use log::{debug, error};
 
 const LOG_TARGET: &str = "AlephBFT-backup-saver";
@@ -14,34 +16,99 @@ const LOG_TARGET: &str = "AlephBFT-backup-saver";
 /// Component responsible for saving units into backup.
 /// It waits for items to appear on its receivers, and writes them to backup.
 /// It announces a successful write through an appropriate response sender.
+/// Depending on the [`SyncMode`], the write is flushed before the announcement or periodically.
+/// Rounds from the [`RoundCounter`](crate::backup::RoundCounter) are always flushed before they are announced.
 pub struct BackupSaver<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> {
     units_from_runway: Receiver<DagUnit<H, D, MK>>,
     responses_for_runway: Sender<DagUnit<H, D, MK>>,
+    // `None` once the creator is gone.
+    rounds_from_creator: Option<Receiver<RoundAdvance>>,
     backup: Pin<Box<W>>,
+    sync_mode: SyncMode,
+    unflushed: bool,
+    header_written: bool,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK, W> {
     pub fn new(
         units_from_runway: Receiver<DagUnit<H, D, MK>>,
         responses_for_runway: Sender<DagUnit<H, D, MK>>,
+        rounds_from_creator: Receiver<RoundAdvance>,
         backup: W,
+        sync_mode: SyncMode,
     ) -> BackupSaver<H, D, MK, W> {
         BackupSaver {
             units_from_runway,
             responses_for_runway,
+            rounds_from_creator: Some(rounds_from_creator),
             backup: Box::pin(backup),
+            sync_mode,
+            unflushed: false,
+            header_written: false,
         }
     }
 
+    async fn write_item(
+        &mut self,
+        item: BackupItem<H, D, MK::Signature>,
+    ) -> Result<(), std::io::Error> {
+        // The backup might contain entries of an older version, so every run starts with a header.
+        let mut encoded = match self.header_written {
+            true => Vec::new(),
+            false => backup_header(),
+        };
+        item.encode_to(&mut encoded);
+        self.backup.write_all(&encoded).await?;
+        self.header_written = true;
+        Ok(())
+    }
+
     pub async fn save_unit(&mut self, unit: &DagUnit<H, D, MK>) -> Result<(), std::io::Error> {
         let unit: UncheckedSignedUnit<_, _, _> = unit.clone().unpack().into();
-        self.backup.write_all(&unit.encode()).await?;
-        self.backup.flush().await
+        self.write_item(BackupItem::Unit(unit)).await?;
+        match self.sync_mode {
+            SyncMode::PerUnit => self.backup.flush().await,
+            SyncMode::Periodic(_) => {
+                self.unflushed = true;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn save_round(&mut self, round: Round) -> Result<(), std::io::Error> {
+        self.write_item(BackupItem::RoundCounter(round)).await?;
+        self.backup.flush().await?;
+        self.unflushed = false;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), std::io::Error> {
+        if self.unflushed {
+            self.backup.flush().await?;
+            self.unflushed = false;
+        }
+        Ok(())
     }
 
     pub async fn run(&mut self, mut terminator: Terminator) {
         let mut terminator_exit = false;
+        let mut flush_timer = match self.sync_mode {
+            SyncMode::PerUnit => None,
+            SyncMode::Periodic(period) => Some((Delay::new(period), period)),
+        };
         loop {
+            let flush_due = async {
+                match flush_timer.as_mut() {
+                    Some((timer, _)) => timer.await,
+                    None => pending().await,
+                }
+            };
+            let round_to_save = async {
+                match self.rounds_from_creator.as_mut() {
+                    Some(rounds) => rounds.next().await,
+                    None => pending().await,
+                }
+            };
             futures::select! {
                 unit = self.units_from_runway.next() => {
                     let item = match unit {
@@ -60,6 +127,32 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
                         break;
                     }
                 },
+                round = round_to_save.fuse() => {
+                    let (round, persisted) = match round {
+                        Some(round) => round,
+                        None => {
+                            debug!(target: LOG_TARGET, "receiver of rounds to save closed");
+                            self.rounds_from_creator = None;
+                            continue;
+                        },
+                    };
+                    if let Err(e) = self.save_round(round).await {
+                        error!(target: LOG_TARGET, "couldn't save round {} to backup: {:?}", round, e);
+                        break;
+                    }
+                    if persisted.send(()).is_err() {
+                        debug!(target: LOG_TARGET, "creator stopped waiting for round {} to be saved", round);
+                    }
+                },
+                _ = flush_due.fuse() => {
+                    if let Err(e) = self.flush().await {
+                        error!(target: LOG_TARGET, "couldn't flush backup: {:?}", e);
+                        break;
+                    }
+                    if let Some((timer, period)) = flush_timer.as_mut() {
+                        timer.reset(*period);
+                    }
+                },
                 _ = terminator.get_exit().fuse() => {
                     debug!(target: LOG_TARGET, "backup saver received exit signal.");
                     terminator_exit = true;
@@ -68,6 +161,9 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
 
             if terminator_exit {
                 debug!(target: LOG_TARGET, "backup saver decided to exit.");
+                if let Err(e) = self.flush().await {
+                    error!(target: LOG_TARGET, "couldn't flush backup: {:?}", e);
+                }
                 terminator.terminate_sync().await;
                 break;
             }
@@ -77,38 +173,93 @@ impl<H: Hasher, D: Data, MK: MultiKeychain, W: AsyncWrite> BackupSaver<H, D, MK,
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        io,
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll},
+        time::Duration,
+    };
+
     use futures::{
         channel::{mpsc, oneshot},
-        StreamExt,
+        AsyncWrite, StreamExt,
     };
+    use parking_lot::Mutex;
 
     use aleph_bft_mock::{Data, Hasher64, Keychain, Saver};
 
     use crate::{
-        backup::BackupSaver,
+        backup::{BackupSaver, RoundCounter},
         dag::ReconstructedUnit,
         units::{creator_set, preunit_to_signed_unit, TestingSignedUnit},
-        NodeCount, Terminator,
+        NodeCount, SyncMode, Terminator,
     };
 
     type TestUnit = ReconstructedUnit<TestingSignedUnit>;
-    type TestBackupSaver = BackupSaver<Hasher64, Data, Keychain, Saver>;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum BackupEvent {
+        Write,
+        Flush,
+    }
+
+    /// A backup recording the writes and flushes made to it.
+    #[derive(Clone, Default)]
+    struct RecordingBackup {
+        events: Arc<Mutex<Vec<BackupEvent>>>,
+    }
+
+    impl RecordingBackup {
+        fn events(&self) -> Vec<BackupEvent> {
+            self.events.lock().clone()
+        }
+    }
+
+    impl AsyncWrite for RecordingBackup {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.events.lock().push(BackupEvent::Write);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.events.lock().push(BackupEvent::Flush);
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
     struct PrepareSaverResponse<F: futures::Future> {
         task: F,
         units_for_saver: mpsc::UnboundedSender<TestUnit>,
         units_from_saver: mpsc::UnboundedReceiver<TestUnit>,
+        round_counter: RoundCounter,
         exit_tx: oneshot::Sender<()>,
     }
 
-    fn prepare_saver() -> PrepareSaverResponse<impl futures::Future> {
+    fn prepare_saver<W: AsyncWrite + Send + 'static>(
+        backup: W,
+        sync_mode: SyncMode,
+    ) -> PrepareSaverResponse<impl futures::Future> {
         let (units_for_saver, units_from_runway) = mpsc::unbounded();
         let (units_for_runway, units_from_saver) = mpsc::unbounded();
+        let (rounds_for_saver, rounds_from_creator) = mpsc::unbounded();
         let (exit_tx, exit_rx) = oneshot::channel();
-        let backup = Saver::new();
 
         let task = {
-            let mut saver: TestBackupSaver =
-                BackupSaver::new(units_from_runway, units_for_runway, backup);
+            let mut saver: BackupSaver<Hasher64, Data, Keychain, W> = BackupSaver::new(
+                units_from_runway,
+                units_for_runway,
+                rounds_from_creator,
+                backup,
+                sync_mode,
+            );
 
             async move {
                 saver.run(Terminator::create_root(exit_rx, "saver")).await;
@@ -119,30 +270,18 @@ mod tests {
             task,
             units_for_saver,
             units_from_saver,
+            round_counter: RoundCounter::new(rounds_for_saver),
             exit_tx,
         }
     }
 
-    #[tokio::test]
-    async fn test_proper_relative_responses_ordering() {
-        let node_count = NodeCount(5);
-        let PrepareSaverResponse {
-            task,
-            units_for_saver,
-            mut units_from_saver,
-            exit_tx,
-        } = prepare_saver();
-
-        let handle = tokio::spawn(async {
-            task.await;
-        });
-
+    fn initial_units(node_count: NodeCount) -> Vec<TestUnit> {
         let creators = creator_set(node_count);
         let keychains: Vec<_> = node_count
             .into_iterator()
             .map(|id| Keychain::new(node_count, id))
             .collect();
-        let units: Vec<TestUnit> = node_count
+        node_count
             .into_iterator()
             .map(|id| {
                 ReconstructedUnit::initial(preunit_to_signed_unit(
@@ -151,7 +290,25 @@ mod tests {
                     &keychains[id.0],
                 ))
             })
-            .collect();
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_proper_relative_responses_ordering() {
+        let node_count = NodeCount(5);
+        let PrepareSaverResponse {
+            task,
+            units_for_saver,
+            mut units_from_saver,
+            exit_tx,
+            ..
+        } = prepare_saver(Saver::new(), SyncMode::PerUnit);
+
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        let units = initial_units(node_count);
 
         for u in units.iter() {
             units_for_saver.unbounded_send(u.clone()).unwrap();
@@ -165,4 +322,102 @@ mod tests {
         exit_tx.send(()).unwrap();
         handle.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn per_unit_sync_flushes_before_responding() {
+        use BackupEvent::*;
+        let backup = RecordingBackup::default();
+        let PrepareSaverResponse {
+            task,
+            units_for_saver,
+            mut units_from_saver,
+            exit_tx,
+            ..
+        } = prepare_saver(backup.clone(), SyncMode::PerUnit);
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        let units = initial_units(NodeCount(3));
+        for (i, u) in units.iter().enumerate() {
+            units_for_saver.unbounded_send(u.clone()).unwrap();
+            assert_eq!(&units_from_saver.next().await.unwrap(), u);
+            // The unit is only announced, and so broadcast, after the write is flushed.
+            assert_eq!(backup.events().last(), Some(&Flush));
+            let flushes = backup.events().iter().filter(|e| **e == Flush).count();
+            assert_eq!(flushes, i + 1);
+        }
+
+        exit_tx.send(()).unwrap();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn periodic_sync_flushes_later() {
+        use BackupEvent::*;
+        let backup = RecordingBackup::default();
+        let PrepareSaverResponse {
+            task,
+            units_for_saver,
+            mut units_from_saver,
+            exit_tx,
+            ..
+        } = prepare_saver(
+            backup.clone(),
+            SyncMode::Periodic(Duration::from_millis(100)),
+        );
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        let units = initial_units(NodeCount(3));
+        for u in units.iter() {
+            units_for_saver.unbounded_send(u.clone()).unwrap();
+        }
+        for u in units.iter() {
+            assert_eq!(&units_from_saver.next().await.unwrap(), u);
+        }
+        assert!(!backup.events().contains(&Flush));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(backup.events().last(), Some(&Flush));
+        // Nothing new was written, so there is nothing to flush.
+        assert_eq!(backup.events().iter().filter(|e| **e == Flush).count(), 1);
+
+        units_for_saver.unbounded_send(units[0].clone()).unwrap();
+        units_from_saver.next().await.unwrap();
+        exit_tx.send(()).unwrap();
+        handle.await.unwrap();
+        // Units written since the last periodic flush get flushed on exit.
+        assert_eq!(backup.events().last(), Some(&Flush));
+    }
+
+    #[tokio::test]
+    async fn round_counter_flushes_before_responding() {
+        use BackupEvent::*;
+        let backup = RecordingBackup::default();
+        let PrepareSaverResponse {
+            task,
+            units_for_saver,
+            mut units_from_saver,
+            round_counter,
+            exit_tx,
+        } = prepare_saver(backup.clone(), SyncMode::Periodic(Duration::from_secs(60)));
+        let handle = tokio::spawn(async {
+            task.await;
+        });
+
+        let units = initial_units(NodeCount(3));
+        units_for_saver.unbounded_send(units[0].clone()).unwrap();
+        units_from_saver.next().await.unwrap();
+        assert_eq!(backup.events(), vec![Write]);
+        // Even with periodic syncing the round is durable once the counter advances.
+        round_counter.advance(1).await.expect("saver is running");
+        assert_eq!(backup.events(), vec![Write, Write, Flush]);
+
+        exit_tx.send(()).unwrap();
+        handle.await.unwrap();
+        // Nothing was written since the round, so there is nothing more to flush.
+        assert_eq!(backup.events(), vec![Write, Write, Flush]);
+    }
 }
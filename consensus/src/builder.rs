@@ -0,0 +1,270 @@
+use crate::{
+    run_session, Config, Data, DataProvider, FinalizationHandler, Hasher, LocalIO, MultiKeychain,
+    Network, NetworkData, SpawnHandle, Terminator,
+};
+use futures::{
+    io::{empty, sink},
+    AsyncRead, AsyncWrite,
+};
+use std::marker::PhantomData;
+use thiserror::Error;
+
+type UnitSaver = Box<dyn AsyncWrite + Send + Sync + Unpin>;
+type UnitLoader = Box<dyn AsyncRead + Send + Sync + Unpin>;
+
+/// A component required by [`MemberBuilder::build`] that was not provided.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum MemberBuilderError {
+    #[error("Missing config, set it with `with_config`.")]
+    MissingConfig,
+    #[error("Missing network, set it with `with_network`.")]
+    MissingNetwork,
+    #[error("Missing data provider, set it with `with_data_provider`.")]
+    MissingDataProvider,
+    #[error("Missing finalization handler, set it with `with_finalization_handler`.")]
+    MissingFinalizationHandler,
+    #[error("Missing keychain, set it with `with_keychain`.")]
+    MissingKeychain,
+}
+
+/// Gathers the components of a member, to be run with [`BuiltMember::run`].
+/// The backup is optional, without it the member neither saves nor loads any units.
+/// Metrics and other optional features are enabled through the [`Config`].
+pub struct MemberBuilder<H, D, DP, FH, N, MK> {
+    config: Option<Config>,
+    network: Option<N>,
+    data_provider: Option<DP>,
+    finalization_handler: Option<FH>,
+    keychain: Option<MK>,
+    backup: Option<(UnitSaver, UnitLoader)>,
+    _phantom: PhantomData<(H, D)>,
+}
+
+impl<H, D, DP, FH, N, MK> Default for MemberBuilder<H, D, DP, FH, N, MK> {
+    fn default() -> Self {
+        MemberBuilder {
+            config: None,
+            network: None,
+            data_provider: None,
+            finalization_handler: None,
+            keychain: None,
+            backup: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<H, D, DP, FH, N, MK> MemberBuilder<H, D, DP, FH, N, MK>
+where
+    H: Hasher,
+    D: Data,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+    N: Network<NetworkData<H, D, MK::Signature, MK::PartialMultisignature>> + 'static,
+    MK: MultiKeychain,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(self, config: Config) -> Self {
+        MemberBuilder {
+            config: Some(config),
+            ..self
+        }
+    }
+
+    pub fn with_network(self, network: N) -> Self {
+        MemberBuilder {
+            network: Some(network),
+            ..self
+        }
+    }
+
+    pub fn with_data_provider(self, data_provider: DP) -> Self {
+        MemberBuilder {
+            data_provider: Some(data_provider),
+            ..self
+        }
+    }
+
+    pub fn with_finalization_handler(self, finalization_handler: FH) -> Self {
+        MemberBuilder {
+            finalization_handler: Some(finalization_handler),
+            ..self
+        }
+    }
+
+    pub fn with_keychain(self, keychain: MK) -> Self {
+        MemberBuilder {
+            keychain: Some(keychain),
+            ..self
+        }
+    }
+
+    /// Save created units with the saver, and recover the ones saved before from the loader.
+    pub fn with_backup(
+        self,
+        unit_saver: impl AsyncWrite + Send + Sync + Unpin + 'static,
+        unit_loader: impl AsyncRead + Send + Sync + Unpin + 'static,
+    ) -> Self {
+        MemberBuilder {
+            backup: Some((Box::new(unit_saver), Box::new(unit_loader))),
+            ..self
+        }
+    }
+
+    /// Check that all the required components were provided.
+    pub fn build(self) -> Result<BuiltMember<H, D, DP, FH, N, MK>, MemberBuilderError> {
+        let config = self.config.ok_or(MemberBuilderError::MissingConfig)?;
+        let network = self.network.ok_or(MemberBuilderError::MissingNetwork)?;
+        let data_provider = self
+            .data_provider
+            .ok_or(MemberBuilderError::MissingDataProvider)?;
+        let finalization_handler = self
+            .finalization_handler
+            .ok_or(MemberBuilderError::MissingFinalizationHandler)?;
+        let keychain = self.keychain.ok_or(MemberBuilderError::MissingKeychain)?;
+        let (unit_saver, unit_loader) = self
+            .backup
+            .unwrap_or_else(|| (Box::new(sink()), Box::new(empty())));
+        Ok(BuiltMember {
+            config,
+            local_io: LocalIO::new(data_provider, finalization_handler, unit_saver, unit_loader),
+            network,
+            keychain,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// A member with all its components, ready to run a session.
+pub struct BuiltMember<H, D, DP, FH, N, MK>
+where
+    D: Data,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+{
+    config: Config,
+    local_io: LocalIO<D, DP, FH, UnitSaver, UnitLoader>,
+    network: N,
+    keychain: MK,
+    _phantom: PhantomData<H>,
+}
+
+impl<H, D, DP, FH, N, MK> BuiltMember<H, D, DP, FH, N, MK>
+where
+    H: Hasher,
+    D: Data,
+    DP: DataProvider<D>,
+    FH: FinalizationHandler<D>,
+    N: Network<NetworkData<H, D, MK::Signature, MK::PartialMultisignature>> + 'static,
+    MK: MultiKeychain,
+{
+    /// Run the session, see [`run_session`].
+    pub async fn run(self, spawn_handle: impl SpawnHandle, terminator: Terminator) {
+        run_session(
+            self.config,
+            self.local_io,
+            self.network,
+            self.keychain,
+            spawn_handle,
+            terminator,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{gen_config, gen_delay_config, init_log, Network},
+        MemberBuilder, MemberBuilderError, NodeCount, NodeIndex, SpawnHandle, Terminator,
+    };
+    use aleph_bft_mock::{
+        Data, DataProvider, FinalizationHandler, Hasher64, Keychain, Router, Saver, Spawner,
+    };
+    use futures::{channel::oneshot, StreamExt};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    type Builder =
+        MemberBuilder<Hasher64, Data, DataProvider, FinalizationHandler, Network, Keychain>;
+
+    #[test]
+    fn reports_missing_components() {
+        let n_members = NodeCount(4);
+        let node_ix = NodeIndex(0);
+        let (_, mut networks) = Router::new(n_members, 1.0);
+        let (network, _) = networks.pop().expect("there are networks");
+        let (finalization_handler, _) = FinalizationHandler::new();
+
+        let builder = Builder::new();
+        assert_eq!(
+            builder.build().err(),
+            Some(MemberBuilderError::MissingConfig)
+        );
+
+        let builder = Builder::new()
+            .with_config(gen_config(node_ix, n_members, gen_delay_config()))
+            .with_network(network)
+            .with_finalization_handler(finalization_handler)
+            .with_keychain(Keychain::new(n_members, node_ix));
+        assert_eq!(
+            builder.build().err(),
+            Some(MemberBuilderError::MissingDataProvider)
+        );
+    }
+
+    #[tokio::test]
+    async fn built_members_finalize_data() {
+        init_log();
+        let n_members = NodeCount(4);
+        let n_batches = 5;
+        let spawner = Spawner::new();
+        let (net_hub, networks) = Router::new(n_members, 1.0);
+        spawner.spawn("network-hub", net_hub);
+
+        let mut members = Vec::new();
+        for (network, _) in networks {
+            let ix = network.index();
+            let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+            let builder = Builder::new()
+                .with_config(gen_config(ix, n_members, gen_delay_config()))
+                .with_network(network)
+                .with_data_provider(DataProvider::new())
+                .with_finalization_handler(finalization_handler)
+                .with_keychain(Keychain::new(n_members, ix));
+            // Only some of the members keep a backup, the others run without one.
+            let (builder, saved_state) = match ix.0 % 2 {
+                0 => {
+                    let saved_state = Arc::new(Mutex::new(Vec::new()));
+                    let builder =
+                        builder.with_backup(Saver::from(saved_state.clone()), futures::io::empty());
+                    (builder, Some(saved_state))
+                }
+                _ => (builder, None),
+            };
+            let member = builder.build().expect("all components are provided");
+            let (exit_tx, exit_rx) = oneshot::channel();
+            let handle = spawner.spawn_essential(
+                "member",
+                member.run(spawner, Terminator::create_root(exit_rx, "AlephBFT-member")),
+            );
+            members.push((finalization_rx, saved_state, exit_tx, handle));
+        }
+
+        for (finalization_rx, ..) in members.iter_mut() {
+            for _ in 0..n_batches {
+                finalization_rx.next().await.expect("the member is running");
+            }
+        }
+        for (_, saved_state, exit_tx, handle) in members {
+            let _ = exit_tx.send(());
+            let _ = handle.await;
+            if let Some(saved_state) = saved_state {
+                assert!(!saved_state.lock().is_empty());
+            }
+        }
+    }
+}
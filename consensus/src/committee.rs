@@ -0,0 +1,127 @@
+use crate::{Config, NodeCount, NodeIndex, SessionId};
+use thiserror::Error;
+
+/// The nodes running the consensus in a session, identified by their public keys.
+/// The key of the node with index `i` is at position `i`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Committee<PK> {
+    pub n_members: NodeCount,
+    pub public_keys: Vec<PK>,
+}
+
+/// A committee that cannot run a session.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum CommitteeError {
+    #[error("Committee of session {session_id} has {} members, but {n_keys} public keys.", .n_members.0)]
+    SizeMismatch {
+        session_id: SessionId,
+        n_members: NodeCount,
+        n_keys: usize,
+    },
+    #[error("Node {} is not a member of the committee of session {session_id} with {} members.", .node_ix.0, .n_members.0)]
+    NotAMember {
+        session_id: SessionId,
+        node_ix: NodeIndex,
+        n_members: NodeCount,
+    },
+}
+
+/// Supplies the committee of every session, for chains rotating their validators.
+pub trait CommitteeProvider {
+    type PublicKey;
+
+    /// The committee running the given session.
+    fn committee_for(&self, session: SessionId) -> Committee<Self::PublicKey>;
+
+    /// Consult the provider when starting a session. Returns the config for running it as the node
+    /// with the given index, together with the committee, which the keychain should be built from.
+    /// All the thresholds of the session are computed from the size of its committee.
+    fn session_config(
+        &self,
+        config: Config,
+        session_id: SessionId,
+        node_ix: NodeIndex,
+    ) -> Result<(Config, Committee<Self::PublicKey>), CommitteeError> {
+        let committee = self.committee_for(session_id);
+        let Committee {
+            n_members,
+            public_keys,
+        } = &committee;
+        if n_members.0 != public_keys.len() {
+            return Err(CommitteeError::SizeMismatch {
+                session_id,
+                n_members: *n_members,
+                n_keys: public_keys.len(),
+            });
+        }
+        if node_ix.0 >= n_members.0 {
+            return Err(CommitteeError::NotAMember {
+                session_id,
+                node_ix,
+                n_members: *n_members,
+            });
+        }
+        let config = config.for_session(session_id, node_ix, *n_members);
+        Ok((config, committee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{gen_config, gen_delay_config},
+        Committee, CommitteeError, CommitteeProvider, NodeCount, NodeIndex, SessionId,
+    };
+
+    struct GrowingCommittee;
+
+    impl CommitteeProvider for GrowingCommittee {
+        type PublicKey = u64;
+
+        fn committee_for(&self, session: SessionId) -> Committee<u64> {
+            let size = 4 + 3 * session as usize;
+            Committee {
+                n_members: NodeCount(size),
+                // The last session claims more members than it has keys.
+                public_keys: (0..size as u64).take(size.min(7)).collect(),
+            }
+        }
+    }
+
+    #[test]
+    fn sets_session_and_committee_size() {
+        let base = gen_config(NodeIndex(0), NodeCount(1), gen_delay_config());
+        for (session_id, n_members) in [(0, NodeCount(4)), (1, NodeCount(7))] {
+            let (config, committee) = GrowingCommittee
+                .session_config(base.clone(), session_id, NodeIndex(3))
+                .expect("the committee is valid");
+            assert_eq!(config.session_id(), session_id);
+            assert_eq!(config.node_ix(), NodeIndex(3));
+            assert_eq!(config.n_members(), n_members);
+            assert_eq!(committee.public_keys.len(), n_members.0);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_committees() {
+        let base = gen_config(NodeIndex(0), NodeCount(1), gen_delay_config());
+        assert_eq!(
+            GrowingCommittee
+                .session_config(base.clone(), 2, NodeIndex(0))
+                .err(),
+            Some(CommitteeError::SizeMismatch {
+                session_id: 2,
+                n_members: NodeCount(10),
+                n_keys: 7,
+            })
+        );
+        assert_eq!(
+            GrowingCommittee.session_config(base, 0, NodeIndex(4)).err(),
+            Some(CommitteeError::NotAMember {
+                session_id: 0,
+                node_ix: NodeIndex(4),
+                n_members: NodeCount(4),
+            })
+        );
+    }
+}
@@ -1,6 +1,16 @@
-use crate::{NodeCount, NodeIndex, Round, SessionId};
+#[cfg(feature = "metrics")]
+use crate::Metrics;
+use crate::{
+    creation::CreatorHandle,
+    dag::{DagObserver, UnitFilter},
+    Data, ElectionConfig, Hasher, MultiKeychain, NodeCount, NodeIndex, Quorum, Round, SessionId,
+    StatusMonitor,
+};
 use log::error;
+use parking_lot::Mutex;
+use rand::RngCore;
 use std::{
+    any::Any,
     fmt::{Debug, Formatter},
     sync::Arc,
     time::Duration,
@@ -60,6 +70,95 @@ impl Debug for DelayConfig {
     }
 }
 
+/// The default number of created units that can wait for dispatching.
+const DEFAULT_OUTGOING_UNITS_CAPACITY: usize = 16;
+
+/// The default minimal time between two alerts about the same creator forking in the same round.
+const DEFAULT_ALERT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How many of the available units of the previous round new units use as parents.
+/// Using more parents makes the DAG denser, which makes elections decide faster.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParentMode {
+    /// Only our own previous unit and just enough others to reach the consensus threshold.
+    Minimal,
+    /// All the available units.
+    #[default]
+    Maximal,
+    /// Like `Minimal`, but preferring the creators whose units we cited least often so far,
+    /// so that no node gets consistently ignored.
+    Diverse,
+}
+
+/// When units written to the backup are made durable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Flush the backup after every unit, before the unit is used or broadcast.
+    /// This guarantees that a restarted node never creates a unit conflicting with one it already sent.
+    #[default]
+    PerUnit,
+    /// Flush the backup at most once per the given period. Units are used before they are durable,
+    /// so a node restarted after a crash might equivocate, in exchange for fewer flushes.
+    Periodic(Duration),
+}
+
+/// The source of randomness for the randomized decisions of a member, which are the choice of peers
+/// to send requests to, the jitter of unit rebroadcasts and the salt of the initial unit collection.
+/// Parents of units and the elections do not use randomness. Clones share the underlying generator.
+#[derive(Clone, Default)]
+pub struct Randomness {
+    // If missing `rand::thread_rng` is used.
+    rng: Option<Arc<Mutex<dyn RngCore + Send>>>,
+}
+
+impl Randomness {
+    /// Draw all the random values from the given generator, e.g. a seeded one for reproducible runs.
+    pub fn from_rng(rng: impl RngCore + Send + 'static) -> Self {
+        Randomness {
+            rng: Some(Arc::new(Mutex::new(rng))),
+        }
+    }
+}
+
+impl RngCore for Randomness {
+    fn next_u32(&mut self) -> u32 {
+        match &self.rng {
+            Some(rng) => rng.lock().next_u32(),
+            None => rand::thread_rng().next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match &self.rng {
+            Some(rng) => rng.lock().next_u64(),
+            None => rand::thread_rng().next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match &self.rng {
+            Some(rng) => rng.lock().fill_bytes(dest),
+            None => rand::thread_rng().fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match &self.rng {
+            Some(rng) => rng.lock().try_fill_bytes(dest),
+            None => rand::thread_rng().try_fill_bytes(dest),
+        }
+    }
+}
+
+impl Debug for Randomness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.rng {
+            Some(_) => write!(f, "Randomness(custom)"),
+            None => write!(f, "Randomness(thread_rng)"),
+        }
+    }
+}
+
 /// Main configuration of the consensus. We refer to [the documentation](https://cardinal-cryptography.github.io/AlephBFT/aleph_bft_api.html#34-alephbft-sessions)
 /// Section 3.4 for a discussion of some of these parameters and their significance.
 #[derive(Clone, Debug)]
@@ -74,6 +173,38 @@ pub struct Config {
     delay_config: DelayConfig,
     /// Maximum allowable round of a unit.
     max_round: Round,
+    /// How many parents new units should have.
+    parent_mode: ParentMode,
+    /// How many created units can wait to be dispatched before the creator stops creating more.
+    outgoing_units_capacity: usize,
+    /// The minimal time between two alerts we send about the same creator forking in the same round.
+    alert_cooldown: Duration,
+    /// How many times to request missing parents of a unit before requesting them only rarely, unlimited if `None`.
+    parent_request_attempts: Option<usize>,
+    /// When units written to the backup are made durable.
+    sync_mode: SyncMode,
+    /// The round of the first unit to create, inferred from the backup and other nodes if `None`.
+    starting_round: Option<Round>,
+    /// How many rounds the created units can get ahead of the ordered heads, unlimited if `None`.
+    max_finalization_lag: Option<Round>,
+    /// Decides whether enough parents are available and whether votes decide elections,
+    /// more than two thirds of the nodes if `None`.
+    quorum: Option<Arc<dyn Quorum>>,
+    /// The rules of the head elections.
+    election_config: ElectionConfig,
+    /// Collects the progress of the member, if its status should be available.
+    status_monitor: Option<StatusMonitor>,
+    /// Pauses and resumes the creation of units, if it should be controllable.
+    creator_handle: Option<CreatorHandle>,
+    /// Observers of the units added to the Dag, each an `Arc<dyn DagObserver<H, D, MK>>` for the types of the member.
+    dag_observers: Vec<Arc<dyn Any + Send + Sync>>,
+    /// Decides which units can enter the Dag, an `Arc<dyn UnitFilter<H, D, MK>>` for the types of the member.
+    unit_filter: Option<Arc<dyn Any + Send + Sync>>,
+    /// The source of randomness for all the randomized decisions.
+    randomness: Randomness,
+    /// Metrics of the head elections, if they should be reported.
+    #[cfg(feature = "metrics")]
+    metrics: Option<Metrics>,
 }
 
 impl Config {
@@ -86,12 +217,217 @@ impl Config {
     pub fn n_members(&self) -> NodeCount {
         self.n_members
     }
+    // Everything else stays the same in all the sessions.
+    pub(crate) fn for_session(
+        self,
+        session_id: SessionId,
+        node_ix: NodeIndex,
+        n_members: NodeCount,
+    ) -> Self {
+        Config {
+            session_id,
+            node_ix,
+            n_members,
+            ..self
+        }
+    }
     pub fn delay_config(&self) -> &DelayConfig {
         &self.delay_config
     }
     pub fn max_round(&self) -> Round {
         self.max_round
     }
+    pub fn parent_mode(&self) -> ParentMode {
+        self.parent_mode
+    }
+    pub fn outgoing_units_capacity(&self) -> usize {
+        self.outgoing_units_capacity
+    }
+    /// Allow at most the given number of created units to wait for dispatching.
+    /// When that many are waiting, the creator waits before creating more.
+    pub fn with_outgoing_units_capacity(self, outgoing_units_capacity: usize) -> Self {
+        Config {
+            outgoing_units_capacity,
+            ..self
+        }
+    }
+    pub fn alert_cooldown(&self) -> Duration {
+        self.alert_cooldown
+    }
+    /// Send alerts about the same creator forking in the same round at most once per the given time.
+    pub fn with_alert_cooldown(self, alert_cooldown: Duration) -> Self {
+        Config {
+            alert_cooldown,
+            ..self
+        }
+    }
+    pub fn parent_request_attempts(&self) -> Option<usize> {
+        self.parent_request_attempts
+    }
+    /// After the given number of attempts request missing parents of a unit only every 30 seconds.
+    /// The requests never stop until the parents arrive, so a unit is eventually added even over a lossy network.
+    /// The delays between the earlier attempts are governed by `parent_request_delay` in the [`DelayConfig`].
+    pub fn with_parent_request_attempts(self, attempts: usize) -> Self {
+        Config {
+            parent_request_attempts: Some(attempts),
+            ..self
+        }
+    }
+    pub fn sync_mode(&self) -> SyncMode {
+        self.sync_mode
+    }
+    /// Make units written to the backup durable as specified by the mode.
+    pub fn with_sync_mode(self, sync_mode: SyncMode) -> Self {
+        Config { sync_mode, ..self }
+    }
+    pub fn starting_round(&self) -> Option<Round> {
+        self.starting_round
+    }
+    /// Create the first unit in the given round, e.g. when restarting in the middle of a session.
+    /// The member refuses to start if units of this or higher rounds were already created according to
+    /// the backup or other nodes, as new units would be forks of them. Every unit needs our own unit
+    /// of the previous round as a parent, so the creator waits for it if it is not known.
+    pub fn with_starting_round(self, starting_round: Round) -> Self {
+        Config {
+            starting_round: Some(starting_round),
+            ..self
+        }
+    }
+    pub fn max_finalization_lag(&self) -> Option<Round> {
+        self.max_finalization_lag
+    }
+    /// Stop creating units while the round of the next one would exceed the round of the last ordered head
+    /// by more than the given lag, and resume once the ordering catches up. Ordering a head requires units
+    /// of a few rounds above it, so with a small lag all the nodes can end up waiting for each other.
+    pub fn with_max_finalization_lag(self, max_finalization_lag: Round) -> Self {
+        Config {
+            max_finalization_lag: Some(max_finalization_lag),
+            ..self
+        }
+    }
+    pub fn quorum(&self) -> Option<&Arc<dyn Quorum>> {
+        self.quorum.as_ref()
+    }
+    /// Use the provided quorum for creating units and deciding elections, in addition to requiring
+    /// more than two thirds of the nodes, or of their weights in weighted elections.
+    /// All the nodes of the session have to use the same one.
+    pub fn with_quorum(self, quorum: Arc<dyn Quorum>) -> Self {
+        Config {
+            quorum: Some(quorum),
+            ..self
+        }
+    }
+    pub fn election_config(&self) -> &ElectionConfig {
+        &self.election_config
+    }
+    /// Elect the heads using the provided rules. They decide the order of the units,
+    /// so all the nodes of the session have to use the same ones.
+    pub fn with_election_config(self, election_config: ElectionConfig) -> Self {
+        Config {
+            election_config,
+            ..self
+        }
+    }
+    pub fn status_monitor(&self) -> Option<&StatusMonitor> {
+        self.status_monitor.as_ref()
+    }
+    /// Report the progress of the member to the provided monitor.
+    pub fn with_status_monitor(self, status_monitor: StatusMonitor) -> Self {
+        Config {
+            status_monitor: Some(status_monitor),
+            ..self
+        }
+    }
+    pub fn creator_handle(&self) -> Option<&CreatorHandle> {
+        self.creator_handle.as_ref()
+    }
+    /// Pause and resume the creation of units with the provided handle. While paused, the member still
+    /// adds units of other nodes to the Dag and orders them, so it keeps up with the others.
+    pub fn with_creator_handle(self, creator_handle: CreatorHandle) -> Self {
+        Config {
+            creator_handle: Some(creator_handle),
+            ..self
+        }
+    }
+    pub(crate) fn dag_observers<H: Hasher, D: Data, MK: MultiKeychain>(
+        &self,
+    ) -> Vec<Arc<dyn DagObserver<H, D, MK>>> {
+        self.dag_observers
+            .iter()
+            .filter_map(|observer| {
+                match observer.downcast_ref::<Arc<dyn DagObserver<H, D, MK>>>() {
+                    Some(observer) => Some(observer.clone()),
+                    None => {
+                        error!(target: "AlephBFT-config", "Dag observer of different types than the member, ignoring it.");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+    /// Report all the units added to the Dag to the observer, in the order they get added, so always after their parents.
+    /// Its types have to be the ones of the member running the session, otherwise it is ignored.
+    pub fn with_dag_observer<H: Hasher, D: Data, MK: MultiKeychain>(
+        self,
+        observer: Arc<dyn DagObserver<H, D, MK>>,
+    ) -> Self {
+        let mut dag_observers = self.dag_observers;
+        dag_observers.push(Arc::new(observer));
+        Config {
+            dag_observers,
+            ..self
+        }
+    }
+    pub(crate) fn unit_filter<H: Hasher, D: Data, MK: MultiKeychain>(
+        &self,
+    ) -> Option<Arc<dyn UnitFilter<H, D, MK>>> {
+        let filter = self.unit_filter.as_ref()?;
+        match filter.downcast_ref::<Arc<dyn UnitFilter<H, D, MK>>>() {
+            Some(filter) => Some(filter.clone()),
+            None => {
+                error!(target: "AlephBFT-config", "Unit filter of different types than the member, ignoring it.");
+                None
+            }
+        }
+    }
+    /// Drop all the units from the network that the filter does not accept, instead of adding them to the Dag.
+    /// Units built on top of dropped units never get added either, so all the nodes should use the same filter.
+    /// Its types have to be the ones of the member running the session, otherwise it is ignored.
+    pub fn with_unit_filter<H: Hasher, D: Data, MK: MultiKeychain>(
+        self,
+        filter: Arc<dyn UnitFilter<H, D, MK>>,
+    ) -> Self {
+        Config {
+            unit_filter: Some(Arc::new(filter)),
+            ..self
+        }
+    }
+    pub fn randomness(&self) -> &Randomness {
+        &self.randomness
+    }
+    /// Draw all the random values from the provided source instead of `rand::thread_rng`.
+    pub fn with_randomness(self, randomness: Randomness) -> Self {
+        Config { randomness, ..self }
+    }
+    /// Create units with the given number of parents.
+    pub fn with_parent_mode(self, parent_mode: ParentMode) -> Self {
+        Config {
+            parent_mode,
+            ..self
+        }
+    }
+    /// Report the progress of head elections using the provided metrics.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(self, metrics: Metrics) -> Self {
+        Config {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.as_ref()
+    }
 }
 
 pub fn exponential_slowdown(
@@ -138,6 +474,22 @@ pub fn create_config(
         n_members,
         delay_config,
         max_round,
+        parent_mode: ParentMode::default(),
+        outgoing_units_capacity: DEFAULT_OUTGOING_UNITS_CAPACITY,
+        alert_cooldown: DEFAULT_ALERT_COOLDOWN,
+        parent_request_attempts: None,
+        sync_mode: SyncMode::default(),
+        starting_round: None,
+        max_finalization_lag: None,
+        quorum: None,
+        election_config: ElectionConfig::default(),
+        status_monitor: None,
+        creator_handle: None,
+        dag_observers: Vec::new(),
+        unit_filter: None,
+        randomness: Randomness::default(),
+        #[cfg(feature = "metrics")]
+        metrics: None,
     })
 }
 
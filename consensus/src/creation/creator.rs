@@ -1,8 +1,12 @@
 use crate::{
+    creation::liveness::{Liveness, LivenessHandle},
+    dag::ParentDiversity,
     units::{ControlHash, PreUnit, Unit},
-    Hasher, NodeCount, NodeIndex, NodeMap, Round,
+    Hasher, NodeCount, NodeIndex, NodeMap, NodeSubset, ParentMode, Quorum, Round,
 };
 use anyhow::Result;
+use log::warn;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Eq, Error, Debug, PartialEq)]
@@ -51,10 +55,107 @@ impl<H: Hasher> UnitsCollector<H> {
     }
 }
 
+// Whether the parents have enough creators, the quorum is required on top of a supermajority.
+fn is_quorum<T>(parents: &NodeMap<T>, quorum: Option<&Arc<dyn Quorum>>) -> bool {
+    if !NodeCount(parents.item_count()).is_supermajority(parents.size()) {
+        return false;
+    }
+    let quorum = match quorum {
+        Some(quorum) => quorum,
+        None => return true,
+    };
+    let mut creators = NodeSubset::with_size(parents.size());
+    for (node_id, _) in parents.iter() {
+        creators.insert(node_id);
+    }
+    quorum.is_quorum(&creators)
+}
+
+/// The policy choosing the parents of a new unit out of the units available in the previous round.
+pub trait ParentSelector<H: Hasher>: Send + Sync {
+    /// Selects the parents for a unit of the given round. The available units always satisfy the constraints
+    /// on parents, and if the selection does not, all the available units are used instead.
+    fn select(&self, round: Round, available: &NodeMap<H::Hash>) -> NodeMap<H::Hash>;
+}
+
+/// Uses all the available parents.
+pub struct AllParents;
+
+impl<H: Hasher> ParentSelector<H> for AllParents {
+    fn select(&self, _round: Round, available: &NodeMap<H::Hash>) -> NodeMap<H::Hash> {
+        available.clone()
+    }
+}
+
+/// Uses our own parent and just enough others to reach the consensus threshold and the quorum, preferring lower indices.
+pub struct MinimalParents {
+    node_id: NodeIndex,
+    quorum: Option<Arc<dyn Quorum>>,
+}
+
+impl MinimalParents {
+    // Our own parent and then the others in the given order, until they are enough.
+    fn select_in_order<'a, H: Hasher>(
+        &self,
+        available: &'a NodeMap<H::Hash>,
+        others: impl Iterator<Item = (NodeIndex, &'a H::Hash)>,
+    ) -> NodeMap<H::Hash> {
+        let mut selected = NodeMap::with_size(available.size());
+        let own = available.get(self.node_id).map(|hash| (self.node_id, hash));
+        for (node_id, hash) in own.into_iter().chain(others) {
+            if is_quorum(&selected, self.quorum.as_ref()) {
+                break;
+            }
+            selected.insert(node_id, *hash);
+        }
+        selected
+    }
+
+    fn others<'a, H: Hasher>(
+        &self,
+        available: &'a NodeMap<H::Hash>,
+    ) -> impl Iterator<Item = (NodeIndex, &'a H::Hash)> {
+        let node_id = self.node_id;
+        available.iter().filter(move |(other, _)| *other != node_id)
+    }
+}
+
+impl<H: Hasher> ParentSelector<H> for MinimalParents {
+    fn select(&self, _round: Round, available: &NodeMap<H::Hash>) -> NodeMap<H::Hash> {
+        self.select_in_order::<H>(available, self.others::<H>(available))
+    }
+}
+
+/// Like [`MinimalParents`], but prefers the creators we cited least often so far, lower indices among equally cited ones.
+pub struct DiverseParents {
+    minimal: MinimalParents,
+    diversity: Arc<ParentDiversity>,
+}
+
+impl<H: Hasher> ParentSelector<H> for DiverseParents {
+    fn select(&self, _round: Round, available: &NodeMap<H::Hash>) -> NodeMap<H::Hash> {
+        let citations = self.diversity.citations_by(self.minimal.node_id);
+        let mut others: Vec<_> = self.minimal.others::<H>(available).collect();
+        // Stable, so equally cited creators stay ordered by their indices.
+        others.sort_by_key(|(node_id, _)| {
+            citations
+                .as_ref()
+                .and_then(|citations| citations.get(*node_id).copied())
+                .unwrap_or(0)
+        });
+        self.minimal
+            .select_in_order::<H>(available, others.into_iter())
+    }
+}
+
 pub struct Creator<H: Hasher> {
     round_collectors: Vec<UnitsCollector<H>>,
     node_id: NodeIndex,
     n_members: NodeCount,
+    parent_selector: Arc<dyn ParentSelector<H>>,
+    quorum: Option<Arc<dyn Quorum>>,
+    liveness: Liveness,
+    parent_diversity: Arc<ParentDiversity>,
 }
 
 impl<H: Hasher> Creator<H> {
@@ -63,6 +164,54 @@ impl<H: Hasher> Creator<H> {
             node_id,
             n_members,
             round_collectors: vec![UnitsCollector::new(n_members)],
+            parent_selector: Arc::new(AllParents),
+            quorum: None,
+            liveness: Liveness::new(n_members),
+            parent_diversity: Arc::new(ParentDiversity::new(n_members)),
+        }
+    }
+
+    /// Additionally require the creators of the parents to form the given quorum.
+    /// Set it before the parent mode, which selects parents according to it.
+    pub fn with_quorum(mut self, quorum: Arc<dyn Quorum>) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Use the given policy for choosing parents of new units.
+    pub fn with_parent_selector(mut self, parent_selector: Arc<dyn ParentSelector<H>>) -> Self {
+        self.parent_selector = parent_selector;
+        self
+    }
+
+    /// Prefer parents according to the given citation statistics in the `Diverse` parent mode.
+    /// They should be fed with all the units added to the Dag. Set it before the parent mode.
+    pub fn with_parent_diversity(mut self, parent_diversity: Arc<ParentDiversity>) -> Self {
+        self.parent_diversity = parent_diversity;
+        self
+    }
+
+    /// A read-only view of which creators appear to have stopped producing units.
+    pub fn liveness(&self) -> LivenessHandle {
+        self.liveness.handle()
+    }
+
+    /// Choose parents of new units according to the given mode.
+    pub fn with_parent_mode(self, parent_mode: ParentMode) -> Self {
+        let minimal_parents = MinimalParents {
+            node_id: self.node_id,
+            quorum: self.quorum.clone(),
+        };
+        match parent_mode {
+            ParentMode::Minimal => self.with_parent_selector(Arc::new(minimal_parents)),
+            ParentMode::Maximal => self.with_parent_selector(Arc::new(AllParents)),
+            ParentMode::Diverse => {
+                let diverse_parents = DiverseParents {
+                    minimal: minimal_parents,
+                    diversity: self.parent_diversity.clone(),
+                };
+                self.with_parent_selector(Arc::new(diverse_parents))
+            }
         }
     }
 
@@ -81,17 +230,45 @@ impl<H: Hasher> Creator<H> {
         &mut self.round_collectors[round_ix]
     }
 
-    /// To create a new unit, we need to have at least the consensus threshold of parents available in previous round.
+    // whether the selected parents are a subset of the available ones that still satisfies the constraints
+    fn is_valid_selection(
+        &self,
+        selected: &NodeMap<H::Hash>,
+        available: &NodeMap<H::Hash>,
+    ) -> bool {
+        selected.size() == available.size()
+            && selected
+                .iter()
+                .all(|(node_id, hash)| available.get(node_id) == Some(hash))
+            && is_quorum(selected, self.quorum.as_ref())
+            && selected.get(self.node_id).is_some()
+    }
+
+    /// To create a new unit, we need to have at least the consensus threshold of parents available in previous round,
+    /// and their creators have to form the quorum if there is one.
     /// Additionally, our unit from previous round must be available.
+    /// The parents are chosen out of the available ones by the parent selector.
     pub fn create_unit(&self, round: Round) -> Result<PreUnit<H>> {
         let parents = match round.checked_sub(1) {
             None => NodeMap::with_size(self.n_members),
-            Some(prev_round) => self
-                .round_collectors
-                .get(usize::from(prev_round))
-                .ok_or(ConstraintError::NotEnoughParents)?
-                .prospective_parents(self.node_id)?
-                .clone(),
+            Some(prev_round) => {
+                let available = self
+                    .round_collectors
+                    .get(usize::from(prev_round))
+                    .ok_or(ConstraintError::NotEnoughParents)?
+                    .prospective_parents(self.node_id)?;
+                if !is_quorum(available, self.quorum.as_ref()) {
+                    return Err(ConstraintError::NotEnoughParents.into());
+                }
+                let selected = self.parent_selector.select(round, available);
+                match self.is_valid_selection(&selected, available) {
+                    true => selected,
+                    false => {
+                        warn!(target: "AlephBFT-creator", "Invalid parents selected for a unit of round {}, using all available ones.", round);
+                        available.clone()
+                    }
+                }
+            }
         };
         Ok(PreUnit::new(
             self.node_id,
@@ -101,6 +278,7 @@ impl<H: Hasher> Creator<H> {
     }
 
     pub fn add_unit<U: Unit<Hasher = H>>(&mut self, unit: &U) {
+        self.liveness.record(unit.creator(), unit.round());
         self.get_or_initialize_collector_for_round(unit.round())
             .add_unit(unit);
     }
@@ -108,14 +286,20 @@ impl<H: Hasher> Creator<H> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Creator as GenericCreator, UnitsCollector};
+    use super::{Creator as GenericCreator, ParentSelector, UnitsCollector};
     use crate::{
         creation::creator::ConstraintError,
-        units::{create_preunits, creator_set, preunit_to_full_unit, Unit},
-        NodeCount, NodeIndex,
+        dag::{ParentDiversity, ReconstructedUnit},
+        extension::{ElectionConfig, Ordering},
+        quorum::MandatoryNodeQuorum,
+        units::{
+            create_preunits, creator_set, preunit_to_full_unit, preunit_to_signed_unit,
+            preunit_to_unchecked_signed_unit, random_full_parent_units_up_to, Unit, Validator,
+        },
+        Hasher, NodeCount, NodeIndex, NodeMap, ParentMode, Round,
     };
-    use aleph_bft_mock::Hasher64;
-    use std::collections::HashSet;
+    use aleph_bft_mock::{FinalizationHandler, Hasher64, Keychain};
+    use std::{collections::HashSet, sync::Arc};
 
     type Creator = GenericCreator<Hasher64>;
 
@@ -223,6 +407,30 @@ mod tests {
         dont_create_unit_below_parents_threshold(NodeCount(7));
     }
 
+    #[test]
+    fn waits_for_mandatory_node_of_quorum() {
+        let n_members = NodeCount(4);
+        let mut creators = creator_set(n_members);
+        let initial_units: Vec<_> = create_preunits(creators.iter(), 0)
+            .into_iter()
+            .map(|pu| preunit_to_full_unit(pu, 0))
+            .collect();
+        let mut creator = Creator::new(NodeIndex(1), n_members)
+            .with_quorum(Arc::new(MandatoryNodeQuorum(NodeIndex(0))))
+            .with_parent_mode(ParentMode::Minimal);
+        creators[1].add_units(&initial_units[1..]);
+        creators[1]
+            .create_unit(1)
+            .expect("Creation should succeed.");
+        creator.add_units(&initial_units[1..]);
+        assert!(creator.create_unit(1).is_err());
+        creator.add_units(&initial_units[..1]);
+        let preunit = creator.create_unit(1).expect("Creation should succeed.");
+        let parents: HashSet<_> = preunit.control_hash().parents().collect();
+        assert!(parents.contains(&NodeIndex(0)));
+        assert!(parents.contains(&NodeIndex(1)));
+    }
+
     #[test]
     fn creates_two_units_when_possible() {
         let n_members = NodeCount(7);
@@ -332,4 +540,199 @@ mod tests {
             ConstraintError::MissingOwnParent
         );
     }
+
+    struct DropCreator(NodeIndex);
+
+    impl ParentSelector<Hasher64> for DropCreator {
+        fn select(
+            &self,
+            _round: Round,
+            available: &NodeMap<<Hasher64 as Hasher>::Hash>,
+        ) -> NodeMap<<Hasher64 as Hasher>::Hash> {
+            let mut selected = NodeMap::with_size(available.size());
+            for (node_id, hash) in available.iter() {
+                if node_id != self.0 {
+                    selected.insert(node_id, *hash);
+                }
+            }
+            selected
+        }
+    }
+
+    fn create_round_one_unit_with_selector(n_members: NodeCount, dropped: NodeIndex) -> NodeCount {
+        let session_id = 0;
+        let mut creators = creator_set(n_members);
+        let new_units = create_preunits(creators.iter(), 0);
+        let new_units: Vec<_> = new_units
+            .into_iter()
+            .map(|pu| preunit_to_full_unit(pu, session_id))
+            .collect();
+        let mut creator = creators
+            .remove(0)
+            .with_parent_selector(Arc::new(DropCreator(dropped)));
+        creator.add_units(&new_units);
+        let preunit = creator.create_unit(1).expect("Creation should succeed.");
+        let n_parents = preunit.n_parents();
+        let keychain = Keychain::new(n_members, NodeIndex(0));
+        let validator = Validator::new(session_id, keychain, 2);
+        validator
+            .validate_unit(preunit_to_unchecked_signed_unit(
+                preunit, session_id, &keychain,
+            ))
+            .expect("Unit should validate.");
+        n_parents
+    }
+
+    #[test]
+    fn creates_valid_unit_with_custom_parent_selector() {
+        assert_eq!(
+            create_round_one_unit_with_selector(NodeCount(4), NodeIndex(3)),
+            NodeCount(3)
+        );
+    }
+
+    #[test]
+    fn ignores_parent_selection_violating_constraints() {
+        // Dropping our own parent is not allowed, so all the parents are used.
+        assert_eq!(
+            create_round_one_unit_with_selector(NodeCount(4), NodeIndex(0)),
+            NodeCount(4)
+        );
+    }
+
+    #[test]
+    fn tracks_creators_that_stopped() {
+        let n_members = NodeCount(4);
+        let stopping = NodeIndex(3);
+        let mut creator = Creator::new(NodeIndex(0), n_members);
+        let liveness = creator.liveness();
+        for units in random_full_parent_units_up_to(10, n_members, 0) {
+            let round = units[0].round();
+            let units: Vec<_> = units
+                .into_iter()
+                .filter(|unit| unit.creator() != stopping || unit.round() <= 5)
+                .collect();
+            creator.add_units(&units);
+            let expected = match round {
+                0..=5 => None,
+                _ => Some(6),
+            };
+            assert_eq!(liveness.silent_since(stopping), expected);
+        }
+        assert_eq!(liveness.silent_nodes(), vec![(stopping, 6)]);
+    }
+
+    // Runs all the creators in the given mode for the given number of rounds, checking the units they create,
+    // and returns how many units got finalized, together with the statistics of their parents.
+    fn finalized_with_parent_mode(
+        parent_mode: ParentMode,
+        n_members: NodeCount,
+    ) -> (usize, Arc<ParentDiversity>) {
+        let session_id = 0;
+        let max_round: Round = 12;
+        let diversity = Arc::new(ParentDiversity::new(n_members));
+        let mut creators: Vec<_> = creator_set(n_members)
+            .into_iter()
+            .map(|creator| {
+                creator
+                    .with_parent_diversity(diversity.clone())
+                    .with_parent_mode(parent_mode)
+            })
+            .collect();
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect();
+        let validator = Validator::new(session_id, keychains[0], max_round);
+        let (finalization_handler, mut finalized_rx) = FinalizationHandler::new();
+        let mut ordering = Ordering::new(
+            n_members,
+            finalization_handler,
+            ElectionConfig::default(),
+            max_round,
+        );
+        let mut previous: NodeMap<<Hasher64 as Hasher>::Hash> = NodeMap::with_size(n_members);
+        for round in 0..=max_round {
+            let units: Vec<_> = create_preunits(creators.iter(), round)
+                .into_iter()
+                .map(|preunit| {
+                    let keychain = &keychains[preunit.creator().0];
+                    preunit_to_signed_unit(preunit, session_id, keychain)
+                })
+                .collect();
+            let mut current = NodeMap::with_size(n_members);
+            for unit in &units {
+                validator
+                    .validate_unit(unit.clone().into())
+                    .expect("Unit should validate.");
+                let n_parents = unit.control_hash().n_parents();
+                let expected_parents = match (round, parent_mode) {
+                    (0, _) => NodeCount(0),
+                    (_, ParentMode::Minimal | ParentMode::Diverse) => {
+                        n_members.consensus_threshold()
+                    }
+                    (_, ParentMode::Maximal) => NodeCount(previous.item_count()),
+                };
+                assert_eq!(n_parents, expected_parents);
+                let mut parents = NodeMap::with_size(n_members);
+                for parent_id in unit.control_hash().parents() {
+                    parents.insert(
+                        parent_id,
+                        *previous.get(parent_id).expect("parent was created"),
+                    );
+                }
+                current.insert(unit.creator(), unit.hash());
+                let unit = match round {
+                    0 => ReconstructedUnit::initial(unit.clone()),
+                    _ => ReconstructedUnit::with_parents(unit.clone(), parents)
+                        .expect("correct parents"),
+                };
+                diversity.add_unit(&unit);
+                ordering.add_unit(unit);
+            }
+            for creator in creators.iter_mut() {
+                creator.add_units(&units);
+            }
+            previous = current;
+        }
+        let mut finalized = 0;
+        while let Ok(Some(_)) = finalized_rx.try_next() {
+            finalized += 1;
+        }
+        (finalized, diversity)
+    }
+
+    #[test]
+    fn maximal_parent_mode_uses_all_available_parents() {
+        assert!(finalized_with_parent_mode(ParentMode::Maximal, NodeCount(7)).0 > 0);
+    }
+
+    #[test]
+    fn minimal_parent_mode_uses_threshold_parents() {
+        let n_members = NodeCount(7);
+        let (finalized, diversity) = finalized_with_parent_mode(ParentMode::Minimal, n_members);
+        assert!(finalized > 0);
+        // The last node is only ever cited by itself.
+        let last = NodeIndex(n_members.0 - 1);
+        for creator in n_members.into_iterator().filter(|creator| *creator != last) {
+            assert_eq!(diversity.citation_rate(creator, last), Some(0.0));
+        }
+    }
+
+    #[test]
+    fn diverse_parent_mode_cites_all_nodes() {
+        let n_members = NodeCount(7);
+        let (finalized, diversity) = finalized_with_parent_mode(ParentMode::Diverse, n_members);
+        assert!(finalized > 0);
+        for (creator, citations) in diversity.citation_matrix().iter() {
+            for (parent_creator, citations) in citations.iter() {
+                assert!(
+                    *citations > 0,
+                    "{:?} never cited {:?}",
+                    creator,
+                    parent_creator
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,104 @@
+use crate::{NodeCount, NodeIndex, NodeMap, Round};
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+// The round from which the node appears silent, i.e. the one after the highest round we have
+// a unit of the node from, as long as some other node got further than that.
+fn silent_since(highest_rounds: &NodeMap<Round>, node_id: NodeIndex) -> Option<Round> {
+    let top_round = highest_rounds.values().max()?;
+    match highest_rounds.get(node_id) {
+        Some(round) if round >= top_round => None,
+        Some(round) => Some(round + 1),
+        None => Some(0),
+    }
+}
+
+/// Records, for every creator, the highest round from which we have seen a unit of theirs.
+pub struct Liveness {
+    highest_rounds: Arc<RwLock<NodeMap<Round>>>,
+}
+
+impl Liveness {
+    pub fn new(n_members: NodeCount) -> Self {
+        Liveness {
+            highest_rounds: Arc::new(RwLock::new(NodeMap::with_size(n_members))),
+        }
+    }
+
+    /// Note that we have seen a unit of the given round from the given creator.
+    pub fn record(&mut self, creator: NodeIndex, round: Round) {
+        let mut highest_rounds = self.highest_rounds.write();
+        if highest_rounds
+            .get(creator)
+            .map_or(true, |highest| *highest < round)
+        {
+            highest_rounds.insert(creator, round);
+        }
+    }
+
+    /// A read-only view of the tracker, e.g. for reporting which nodes appear offline.
+    pub fn handle(&self) -> LivenessHandle {
+        LivenessHandle {
+            highest_rounds: self.highest_rounds.clone(),
+        }
+    }
+}
+
+/// A read-only view of a [`Liveness`] tracker, always up to date with it.
+#[derive(Clone, Debug)]
+pub struct LivenessHandle {
+    highest_rounds: Arc<RwLock<NodeMap<Round>>>,
+}
+
+impl LivenessHandle {
+    /// The round from which the node stopped producing units, if some other node produced units since.
+    pub fn silent_since(&self, node_id: NodeIndex) -> Option<Round> {
+        silent_since(&self.highest_rounds.read(), node_id)
+    }
+
+    /// All the nodes that appear silent, with the rounds they have been silent since.
+    pub fn silent_nodes(&self) -> Vec<(NodeIndex, Round)> {
+        let highest_rounds = self.highest_rounds.read();
+        highest_rounds
+            .size()
+            .into_iterator()
+            .filter_map(|node_id| Some((node_id, silent_since(&highest_rounds, node_id)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Liveness;
+    use crate::{NodeCount, NodeIndex, Round};
+
+    #[test]
+    fn reports_node_silent_after_it_stops() {
+        let n_members = NodeCount(4);
+        let mut liveness = Liveness::new(n_members);
+        let handle = liveness.handle();
+        let stopping = NodeIndex(3);
+        for round in 0..=10 {
+            for node_id in n_members.into_iterator() {
+                if node_id != stopping || round <= 5 {
+                    liveness.record(node_id, round);
+                }
+            }
+            let expected: Option<Round> = match round {
+                0..=5 => None,
+                _ => Some(6),
+            };
+            assert_eq!(handle.silent_since(stopping), expected);
+            assert_eq!(handle.silent_since(NodeIndex(0)), None);
+        }
+        assert_eq!(handle.silent_nodes(), vec![(stopping, 6)]);
+    }
+
+    #[test]
+    fn nothing_is_silent_without_units() {
+        let liveness = Liveness::new(NodeCount(4));
+        let handle = liveness.handle();
+        assert_eq!(handle.silent_since(NodeIndex(0)), None);
+        assert!(handle.silent_nodes().is_empty());
+    }
+}
@@ -1,41 +1,128 @@
 use crate::{
+    backup::{RoundCounter, RoundCounterError},
     config::Config,
+    dag::ParentDiversity,
     units::{PreUnit, SignedUnit, Unit},
-    Data, DataProvider, MultiKeychain, Receiver, Round, Sender, Terminator,
+    Data, DataProvider, MultiKeychain, Receiver, Round, Terminator,
 };
 use futures::{
     channel::{
-        mpsc::{SendError, TrySendError},
+        mpsc::{self, SendError},
         oneshot,
     },
-    FutureExt, StreamExt,
+    FutureExt, SinkExt, StreamExt,
 };
 use futures_timer::Delay;
-use log::{debug, error, trace, warn};
+use log::{debug, error, info, trace, warn};
+use parking_lot::Mutex;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 mod creator;
+mod liveness;
 mod packer;
 
 pub use creator::Creator;
+pub use liveness::LivenessHandle;
 use packer::Packer;
 
 const LOG_TARGET: &str = "AlephBFT-creator";
 
+// How often a paused creator checks whether it got resumed.
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
 enum CreatorError {
     OutChannelClosed(SendError),
     ParentsChannelClosed,
+    RoundNotPersisted(RoundCounterError),
+}
+
+impl From<SendError> for CreatorError {
+    fn from(e: SendError) -> Self {
+        Self::OutChannelClosed(e)
+    }
 }
 
-impl<T> From<TrySendError<T>> for CreatorError {
-    fn from(e: TrySendError<T>) -> Self {
-        Self::OutChannelClosed(e.into_send_error())
+impl From<RoundCounterError> for CreatorError {
+    fn from(e: RoundCounterError) -> Self {
+        Self::RoundNotPersisted(e)
+    }
+}
+
+/// Allows pausing and resuming the creation of units at runtime, e.g. during maintenance.
+/// A paused creator keeps processing incoming units, so it stays up to date with the Dag.
+/// Also tells the creator how far the ordering got, so that it can wait for it when it lags too far behind.
+/// Pass a clone to the [`Config`] to control the creation of a member.
+#[derive(Clone, Debug, Default)]
+pub struct CreatorHandle {
+    paused: Arc<AtomicBool>,
+    ordered_round: Arc<Mutex<Option<Round>>>,
+}
+
+impl CreatorHandle {
+    pub fn new() -> Self {
+        CreatorHandle::default()
+    }
+
+    /// Stop creating units. A unit whose creation already started might still get created.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume creating units, starting with the round after the last created unit.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    // Shares the pausing, but not the progress of the ordering, which starts anew in every session.
+    pub(crate) fn for_session(&self) -> Self {
+        CreatorHandle {
+            paused: self.paused.clone(),
+            ordered_round: Arc::default(),
+        }
+    }
+
+    /// Report that the head of the given round got ordered.
+    pub(crate) fn report_ordered_round(&self, round: Round) {
+        let mut ordered_round = self.ordered_round.lock();
+        *ordered_round = (*ordered_round).max(Some(round));
+    }
+
+    // Before anything gets ordered, the lag is counted from round 0.
+    fn ordering_lags(&self, round: Round, max_lag: Option<Round>) -> bool {
+        match max_lag {
+            Some(max_lag) => {
+                round
+                    > self
+                        .ordered_round
+                        .lock()
+                        .unwrap_or(0)
+                        .saturating_add(max_lag)
+            }
+            None => false,
+        }
     }
 }
 
 pub struct IO<U: Unit, D: Data, MK: MultiKeychain, DP: DataProvider<D>> {
     pub incoming_parents: Receiver<U>,
-    pub outgoing_units: Sender<SignedUnit<U::Hasher, D, MK>>,
+    /// Bounded, so that the creator waits instead of creating units faster than they get dispatched.
+    pub outgoing_units: mpsc::Sender<SignedUnit<U::Hasher, D, MK>>,
     pub data_provider: DP,
+    pub handle: CreatorHandle,
+    /// Persists the round of every unit before it gets created.
+    pub round_counter: RoundCounter,
+    /// Statistics of the parents of all the units added to the Dag, for the `Diverse` parent mode.
+    pub parent_diversity: Arc<ParentDiversity>,
 }
 
 async fn create_unit<U: Unit>(
@@ -93,6 +180,42 @@ async fn keep_processing_units_until<U: Unit>(
     Ok(())
 }
 
+async fn keep_processing_units_while_paused<U: Unit>(
+    creator: &mut Creator<U::Hasher>,
+    incoming_parents: &mut Receiver<U>,
+    handle: &CreatorHandle,
+) -> anyhow::Result<(), CreatorError> {
+    if !handle.is_paused() {
+        return Ok(());
+    }
+    info!(target: LOG_TARGET, "Unit creation paused.");
+    while handle.is_paused() {
+        let check = Delay::new(PAUSE_CHECK_INTERVAL);
+        keep_processing_units_until(creator, incoming_parents, check).await?;
+    }
+    info!(target: LOG_TARGET, "Unit creation resumed.");
+    Ok(())
+}
+
+async fn keep_processing_units_while_ordering_lags<U: Unit>(
+    creator: &mut Creator<U::Hasher>,
+    incoming_parents: &mut Receiver<U>,
+    handle: &CreatorHandle,
+    round: Round,
+    max_lag: Option<Round>,
+) -> anyhow::Result<(), CreatorError> {
+    if !handle.ordering_lags(round, max_lag) {
+        return Ok(());
+    }
+    info!(target: LOG_TARGET, "Ordering lags too far behind, waiting before creating a unit of round {}.", round);
+    while handle.ordering_lags(round, max_lag) {
+        let check = Delay::new(PAUSE_CHECK_INTERVAL);
+        keep_processing_units_until(creator, incoming_parents, check).await?;
+    }
+    info!(target: LOG_TARGET, "Ordering caught up, creating a unit of round {}.", round);
+    Ok(())
+}
+
 /// A process responsible for creating new units. It receives all the units added locally to the Dag
 /// via the `incoming_parents` channel. It creates units according to an internal strategy respecting
 /// always the following constraints: if round is equal to 0, U has no parents, otherwise for a unit U of round r > 0
@@ -155,6 +278,9 @@ async fn read_starting_round_and_run_creator<
             CreatorError::ParentsChannelClosed => {
                 debug!(target: LOG_TARGET, "Incoming parent channel closed, exiting.")
             }
+            CreatorError::RoundNotPersisted(e) => {
+                error!(target: LOG_TARGET, "{} Not creating any more units.", e)
+            }
         }
     }
 }
@@ -169,15 +295,27 @@ async fn run_creator<U: Unit, D: Data, MK: MultiKeychain, DP: DataProvider<D>>(
     let n_members = conf.n_members();
     let create_delay = conf.delay_config().unit_creation_delay.clone();
     let max_round = conf.max_round();
+    let max_finalization_lag = conf.max_finalization_lag();
     let session_id = conf.session_id();
     let mut creator = Creator::new(node_id, n_members);
+    if let Some(quorum) = conf.quorum() {
+        creator = creator.with_quorum(quorum.clone());
+    }
+    let mut creator = creator
+        .with_parent_diversity(io.parent_diversity.clone())
+        .with_parent_mode(conf.parent_mode());
+    if let Some(status_monitor) = conf.status_monitor() {
+        status_monitor.report_liveness(creator.liveness());
+    }
     let packer = Packer::new(keychain, session_id);
     let incoming_parents = &mut io.incoming_parents;
-    let outgoing_units = &io.outgoing_units;
+    let outgoing_units = &mut io.outgoing_units;
     let data_provider = &mut io.data_provider;
+    let handle = &io.handle;
+    let round_counter = &io.round_counter;
 
     debug!(target: LOG_TARGET, "Creator starting from round {}", starting_round);
-    for round in starting_round..max_round {
+    for round in starting_round..=max_round {
         // Skip waiting if someone created a unit of a higher round.
         // In such a case at least 2/3 nodes created units from this round so we aren't skipping a
         // delay we should observe.
@@ -187,16 +325,29 @@ async fn run_creator<U: Unit, D: Data, MK: MultiKeychain, DP: DataProvider<D>>(
 
             keep_processing_units_until(&mut creator, incoming_parents, delay).await?;
         }
+        // Rounds are created in order, so after resuming we continue with the round we stopped at.
+        keep_processing_units_while_paused(&mut creator, incoming_parents, handle).await?;
+        keep_processing_units_while_ordering_lags(
+            &mut creator,
+            incoming_parents,
+            handle,
+            round,
+            max_finalization_lag,
+        )
+        .await?;
 
         let preunit = create_unit(round, &mut creator, incoming_parents).await?;
         trace!(target: LOG_TARGET, "Created a new preunit {:?} at round {:?}.", preunit, round);
         let data = data_provider.get_data().await;
         trace!(target: LOG_TARGET, "Received data: {:?}.", data);
+        // Once the round is durable we never create another unit of it, even after a restart.
+        round_counter.advance(round).await?;
         let unit = packer.pack(preunit, data);
 
-        outgoing_units.unbounded_send(unit)?;
+        outgoing_units.send(unit).await?;
     }
 
     warn!(target: LOG_TARGET, "Maximum round reached. Not creating another unit.");
-    Ok(())
+    // Keep the session running, so that the units created so far can still get ordered.
+    keep_processing_units(&mut creator, incoming_parents).await
 }
@@ -0,0 +1,144 @@
+use crate::{
+    dag::{DagObserver, DagUnit},
+    units::UnitWithParents,
+    Data, Hasher, MultiKeychain, NodeCount, NodeIndex, NodeMap,
+};
+use parking_lot::Mutex;
+
+/// Counts how often every creator cites units of every other creator as parents, to spot creators
+/// consistently ignoring some nodes, which might indicate collusion.
+/// Observes the Dag, so it gets fed with all the units added to it.
+#[derive(Debug)]
+pub struct ParentDiversity {
+    // The row of a creator counts the parents of its units by their creators.
+    citations: Mutex<NodeMap<NodeMap<u64>>>,
+}
+
+impl ParentDiversity {
+    pub fn new(n_members: NodeCount) -> Self {
+        let mut citations = NodeMap::with_size(n_members);
+        for creator in n_members.into_iterator() {
+            let mut row = NodeMap::with_size(n_members);
+            for parent_creator in n_members.into_iterator() {
+                row.insert(parent_creator, 0);
+            }
+            citations.insert(creator, row);
+        }
+        ParentDiversity {
+            citations: Mutex::new(citations),
+        }
+    }
+
+    /// Account for a unit added to the Dag.
+    pub fn add_unit<U: UnitWithParents>(&self, unit: &U) {
+        let mut citations = self.citations.lock();
+        let row = match citations.get_mut(unit.creator()) {
+            Some(row) => row,
+            None => return,
+        };
+        for (parent_creator, _) in unit.parents().iter() {
+            if let Some(citations) = row.get_mut(parent_creator) {
+                *citations += 1;
+            }
+        }
+    }
+
+    /// How many times each creator cited units of each creator, indexed by the citing creator first.
+    pub fn citation_matrix(&self) -> NodeMap<NodeMap<u64>> {
+        self.citations.lock().clone()
+    }
+
+    /// How many times the creator cited units of each creator.
+    pub fn citations_by(&self, creator: NodeIndex) -> Option<NodeMap<u64>> {
+        self.citations.lock().get(creator).cloned()
+    }
+
+    /// The fraction of the units of the creator above round 0 citing a unit of the parent creator,
+    /// `None` if it has no such units.
+    pub fn citation_rate(&self, creator: NodeIndex, parent_creator: NodeIndex) -> Option<f64> {
+        let row = self.citations_by(creator)?;
+        let citations = *row.get(parent_creator)?;
+        // Units of round 0 have no parents, but always cite their own previous unit otherwise.
+        let units_with_parents = *row.get(creator)?;
+        match units_with_parents {
+            0 => None,
+            units => Some(citations as f64 / units as f64),
+        }
+    }
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> DagObserver<H, D, MK> for ParentDiversity {
+    fn unit_added(&self, unit: &DagUnit<H, D, MK>) {
+        self.add_unit(unit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dag::{diversity::ParentDiversity, Dag},
+        units::{
+            full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to,
+            random_unit_with_parents, Unit, UnitStore, Validator as UnitValidator,
+            WrappedSignedUnit,
+        },
+        NodeCount, NodeIndex,
+    };
+    use aleph_bft_mock::Keychain;
+    use std::sync::Arc;
+
+    #[test]
+    fn reflects_ignored_creator() {
+        let n_members = NodeCount(4);
+        let session_id = 2137;
+        let ignoring = NodeIndex(0);
+        let ignored = NodeIndex(3);
+        let max_round = 5;
+        let mut dag = random_full_parent_units_up_to(0, n_members, session_id);
+        for _ in 0..max_round {
+            let parents = dag.last().expect("there is a round").clone();
+            let round = n_members
+                .into_iterator()
+                .map(|creator| {
+                    let parents = match creator == ignoring {
+                        true => parents
+                            .iter()
+                            .filter(|parent| parent.creator() != ignored)
+                            .cloned()
+                            .collect(),
+                        false => parents.clone(),
+                    };
+                    random_unit_with_parents(creator, &parents)
+                })
+                .collect();
+            dag.push(round);
+        }
+
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect();
+        let diversity = Arc::new(ParentDiversity::new(n_members));
+        let store = UnitStore::<WrappedSignedUnit>::new(n_members);
+        let mut observed_dag = Dag::new(UnitValidator::new(session_id, keychains[0], 2137))
+            .with_observer(diversity.clone());
+        for unit in dag.into_iter().flatten() {
+            let keychain = &keychains[unit.creator().0];
+            let unit = full_unit_to_unchecked_signed_unit(unit, keychain);
+            assert_eq!(observed_dag.add_unit(unit, &store).units.len(), 1);
+        }
+
+        for (creator, row) in diversity.citation_matrix().iter() {
+            for (parent_creator, citations) in row.iter() {
+                let expected = match creator == ignoring && parent_creator == ignored {
+                    true => 0,
+                    false => max_round,
+                };
+                assert_eq!(*citations, expected);
+            }
+        }
+        assert_eq!(diversity.citation_rate(ignoring, ignored), Some(0.0));
+        assert_eq!(diversity.citation_rate(ignoring, NodeIndex(1)), Some(1.0));
+        assert_eq!(diversity.citation_rate(ignored, ignoring), Some(1.0));
+    }
+}
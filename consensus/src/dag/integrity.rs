@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    units::{HashFor, UnitWithParents},
+    Hasher, NodeIndex, NodeSubset, Round,
+};
+use thiserror::Error;
+
+/// A way in which a collection of units fails to form a correct Dag.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum IntegrityViolation<H: Hasher> {
+    /// The unit has a parent that is not among the units.
+    #[error("Unit {unit:?} has a missing parent {parent:?}.")]
+    MissingParent { unit: H::Hash, parent: H::Hash },
+    /// The unit has a parent that is not from the immediately preceding round.
+    #[error("Unit {unit:?} of round {round} has a parent {parent:?} of round {parent_round}.")]
+    WrongParentRound {
+        unit: H::Hash,
+        round: Round,
+        parent: H::Hash,
+        parent_round: Round,
+    },
+    /// The unit is not above round 0, but does not have a parent created by its own creator.
+    #[error("Unit {unit:?} does not have a parent by its own creator.")]
+    MissingOwnParent { unit: H::Hash },
+    /// The creator has more than one unit in the round, but is not a known forker.
+    #[error("Creator {} has {} units in round {round}, but is not a known forker.", .creator.0, .units.len())]
+    UnmarkedFork {
+        creator: NodeIndex,
+        round: Round,
+        units: Vec<H::Hash>,
+    },
+}
+
+/// Checks whether the units form a correct Dag, reporting all the violations found, ordered by rounds.
+/// Creators in `forkers` are allowed to have many units per round.
+pub fn check_integrity<'a, U: UnitWithParents + 'a>(
+    units: impl IntoIterator<Item = &'a U>,
+    forkers: &NodeSubset,
+) -> Vec<IntegrityViolation<U::Hasher>> {
+    use IntegrityViolation::*;
+    let mut units: Vec<_> = units.into_iter().collect();
+    units.sort_by_key(|unit| (unit.round(), unit.creator(), unit.hash()));
+    let by_hash: HashMap<_, _> = units.iter().map(|unit| (unit.hash(), *unit)).collect();
+    let mut by_coord: BTreeMap<(Round, NodeIndex), Vec<HashFor<U>>> = BTreeMap::new();
+    let mut violations = Vec::new();
+    for unit in units {
+        by_coord
+            .entry((unit.round(), unit.creator()))
+            .or_default()
+            .push(unit.hash());
+        if unit.round() > 0 && unit.parents().get(unit.creator()).is_none() {
+            violations.push(MissingOwnParent { unit: unit.hash() });
+        }
+        for parent in unit.parents().values() {
+            match by_hash.get(parent) {
+                None => violations.push(MissingParent {
+                    unit: unit.hash(),
+                    parent: *parent,
+                }),
+                Some(parent_unit) if parent_unit.round() + 1 != unit.round() => {
+                    violations.push(WrongParentRound {
+                        unit: unit.hash(),
+                        round: unit.round(),
+                        parent: *parent,
+                        parent_round: parent_unit.round(),
+                    })
+                }
+                Some(_) => (),
+            }
+        }
+    }
+    for ((round, creator), units) in by_coord {
+        if units.len() > 1 && !forkers.contains(creator) {
+            violations.push(UnmarkedFork {
+                creator,
+                round,
+                units,
+            });
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        dag::integrity::{check_integrity, IntegrityViolation},
+        units::{
+            random_full_parent_reconstrusted_units_up_to, random_reconstructed_unit_with_parents,
+            TestingDagUnit, Unit,
+        },
+        NodeCount, NodeIndex, NodeSubset,
+    };
+
+    fn dag(n_members: NodeCount) -> Vec<Vec<TestingDagUnit>> {
+        random_full_parent_reconstrusted_units_up_to(3, n_members, 2137)
+    }
+
+    #[test]
+    fn accepts_correct_dag() {
+        let n_members = NodeCount(4);
+        let dag = dag(n_members);
+        let forkers = NodeSubset::with_size(n_members);
+        assert!(check_integrity(dag.iter().flatten(), &forkers).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_parents() {
+        let n_members = NodeCount(4);
+        let mut dag = dag(n_members);
+        let missing = dag[1].remove(0);
+        let forkers = NodeSubset::with_size(n_members);
+        let violations = check_integrity(dag.iter().flatten(), &forkers);
+        let expected: Vec<_> = dag[2]
+            .iter()
+            .map(|unit| IntegrityViolation::MissingParent {
+                unit: unit.hash(),
+                parent: missing.hash(),
+            })
+            .collect();
+        assert_eq!(violations.len(), n_members.0);
+        for violation in expected {
+            assert!(violations.contains(&violation));
+        }
+    }
+
+    #[test]
+    fn reports_parents_from_wrong_rounds() {
+        let n_members = NodeCount(4);
+        let mut dag = dag(n_members);
+        // The parent by the last creator is from round 0, the rest from round 1.
+        let mut parents = vec![dag[0][3].clone()];
+        parents.extend(dag[1].iter().take(3).cloned());
+        let unit = random_reconstructed_unit_with_parents(NodeIndex(0), &parents);
+        assert_eq!(unit.round(), 2);
+        let forkers = NodeSubset::with_size(n_members);
+        dag[2][0] = unit.clone();
+        // The units of round 3 do not have the replaced unit as a parent.
+        dag.truncate(3);
+        assert_eq!(
+            check_integrity(dag.iter().flatten(), &forkers),
+            vec![IntegrityViolation::WrongParentRound {
+                unit: unit.hash(),
+                round: 2,
+                parent: dag[0][3].hash(),
+                parent_round: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_missing_own_parent() {
+        let n_members = NodeCount(4);
+        let mut dag = dag(n_members);
+        dag.truncate(2);
+        let unit = random_reconstructed_unit_with_parents(NodeIndex(3), &dag[0][..3].to_vec());
+        dag[1][3] = unit.clone();
+        let forkers = NodeSubset::with_size(n_members);
+        assert_eq!(
+            check_integrity(dag.iter().flatten(), &forkers),
+            vec![IntegrityViolation::MissingOwnParent { unit: unit.hash() }]
+        );
+    }
+
+    #[test]
+    fn reports_forks_of_unmarked_forkers() {
+        let n_members = NodeCount(4);
+        let mut dag = dag(n_members);
+        dag.truncate(2);
+        let fork = random_reconstructed_unit_with_parents(NodeIndex(1), &dag[0]);
+        dag[1].push(fork.clone());
+        let mut forkers = NodeSubset::with_size(n_members);
+        let mut fork_hashes = vec![dag[1][1].hash(), fork.hash()];
+        fork_hashes.sort();
+        assert_eq!(
+            check_integrity(dag.iter().flatten(), &forkers),
+            vec![IntegrityViolation::UnmarkedFork {
+                creator: NodeIndex(1),
+                round: 1,
+                units: fork_hashes,
+            }]
+        );
+        forkers.insert(NodeIndex(1));
+        assert!(check_integrity(dag.iter().flatten(), &forkers).is_empty());
+    }
+}
@@ -1,18 +1,27 @@
 //! Converts units from the network into ones that are in the Dag, in the correct order.
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
 
 use crate::{
     alerts::{Alert, ForkingNotification},
     units::{
-        SignedUnit, UncheckedSignedUnit, Unit, UnitStore, Validator as UnitValidator, WrappedUnit,
+        SignedUnit, UncheckedSignedUnit, Unit, UnitStore, UnitWithParents,
+        Validator as UnitValidator, WrappedUnit,
     },
-    Data, Hasher, MultiKeychain,
+    Data, Hasher, MultiKeychain, NodeSubset, Round,
 };
 use log::{debug, trace, warn};
 
+mod diversity;
+mod integrity;
 mod reconstruction;
 mod validation;
 
+pub use diversity::ParentDiversity;
+pub use integrity::IntegrityViolation;
 pub use reconstruction::{ReconstructedUnit, Request};
 use reconstruction::{Reconstruction, ReconstructionResult};
 pub use validation::ValidatorStatus as DagStatus;
@@ -78,10 +87,37 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> From<ReconstructionResult<SignedUnit
     }
 }
 
+/// A unit in an imported batch failed validation, so none of the units got imported.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ImportError<H: Hasher, D: Data, MK: MultiKeychain>(ValidationError<H, D, MK>);
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> Display for ImportError<H, D, MK> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "import contains invalid unit: {:?}", self.0)
+    }
+}
+
+/// Observes units getting added to the Dag, e.g. for tracing or custom indexing.
+pub trait DagObserver<H: Hasher, D: Data, MK: MultiKeychain>: Send + Sync {
+    /// Called for every unit added to the Dag, in the order they are added, so always after its parents.
+    /// Units still waiting for their parents are not reported until they get added.
+    fn unit_added(&self, unit: &DagUnit<H, D, MK>);
+}
+
+/// Decides whether units can enter the Dag based on application-level rules, e.g. about the data they contain.
+/// Consulted for units from the network after their signatures are verified, but before they get
+/// added to the Dag. Units committed to in alerts are not filtered, as they are needed to handle forks.
+/// Units that are not accepted get dropped, so units built on top of them never get added either.
+pub trait UnitFilter<H: Hasher, D: Data, MK: MultiKeychain>: Send + Sync {
+    /// Whether the unit can be added to the Dag.
+    fn accept(&self, unit: &SignedUnit<H, D, MK>) -> bool;
+}
+
 /// The Dag ensuring that all units from the network get returned reconstructed in the correct order.
 pub struct Dag<H: Hasher, D: Data, MK: MultiKeychain> {
     validator: Validator<H, D, MK>,
     reconstruction: Reconstruction<SignedUnit<H, D, MK>>,
+    observers: Vec<Arc<dyn DagObserver<H, D, MK>>>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
@@ -90,7 +126,33 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         Dag {
             validator: Validator::new(unit_validator),
             reconstruction: Reconstruction::new(),
+            observers: Vec::new(),
+        }
+    }
+
+    /// Report all the units added to the Dag to the given observer, in addition to the ones added before.
+    pub fn with_observer(mut self, observer: Arc<dyn DagObserver<H, D, MK>>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Drop all the incoming units that the filter does not accept.
+    pub fn with_filter(mut self, filter: Arc<dyn UnitFilter<H, D, MK>>) -> Self {
+        self.validator = self.validator.with_filter(filter);
+        self
+    }
+
+    fn integrated(
+        observers: &[Arc<dyn DagObserver<H, D, MK>>],
+        result: ReconstructionResult<SignedUnit<H, D, MK>>,
+    ) -> DagResult<H, D, MK> {
+        let result: DagResult<H, D, MK> = result.into();
+        for observer in observers {
+            for unit in &result.units {
+                observer.unit_added(unit);
+            }
         }
+        result
     }
 
     fn handle_validation_error(error: ValidationError<H, D, MK>) -> DagResult<H, D, MK> {
@@ -113,6 +175,10 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
                 trace!(target: LOG_TARGET, "Created alert: {:?}.", alert);
                 DagResult::alert(*alert)
             }
+            Rejected(unit) => {
+                debug!(target: LOG_TARGET, "Received unit with hash {:?} rejected by the unit filter, discarding.", unit.hash());
+                DagResult::empty()
+            }
         }
     }
 
@@ -122,8 +188,16 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
     ) -> DagResult<H, D, MK> {
-        match self.validator.validate(unit, store) {
-            Ok(unit) => self.reconstruction.add_unit(unit).into(),
+        let result = self.validator.validate(unit, store);
+        self.handle_validated(result)
+    }
+
+    fn handle_validated(
+        &mut self,
+        result: Result<SignedUnit<H, D, MK>, ValidationError<H, D, MK>>,
+    ) -> DagResult<H, D, MK> {
+        match result {
+            Ok(unit) => Self::integrated(&self.observers, self.reconstruction.add_unit(unit)),
             Err(e) => Self::handle_validation_error(e),
         }
     }
@@ -138,16 +212,24 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         use ValidationError::*;
         let mut result = DagResult::empty();
         let mut parent_hashes = HashMap::new();
-        for unit in parents {
-            let unit = match self.validator.validate(unit, store) {
+        // The signatures of all the parents are verified together.
+        for unit in self.validator.check_batch(parents) {
+            let unit = match unit.and_then(|unit| self.validator.validate_checked(unit, store)) {
                 Ok(unit) => {
-                    result.accumulate(self.reconstruction.add_unit(unit.clone()).into());
+                    result.accumulate(Self::integrated(
+                        &self.observers,
+                        self.reconstruction.add_unit(unit.clone()),
+                    ));
                     unit
                 }
                 Err(Invalid(e)) => {
                     warn!(target: LOG_TARGET, "Received parent failing validation: {}", e);
                     return result;
                 }
+                Err(Rejected(unit)) => {
+                    debug!(target: LOG_TARGET, "Received parent with hash {:?} rejected by the unit filter, discarding.", unit.hash());
+                    return result;
+                }
                 Err(Duplicate(unit)) => {
                     trace!(target: LOG_TARGET, "Received parent with hash {:?} again.", unit.hash());
                     unit
@@ -168,14 +250,66 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
             };
             parent_hashes.insert(unit.coord(), unit.hash());
         }
-        result.accumulate(
-            self.reconstruction
-                .add_parents(unit_hash, parent_hashes)
-                .into(),
-        );
+        result.accumulate(Self::integrated(
+            &self.observers,
+            self.reconstruction.add_parents(unit_hash, parent_hashes),
+        ));
         result
     }
 
+    /// All the units of rounds at least the given one from the store, in order of rounds,
+    /// e.g. to send them to a node that joined late using `import`.
+    /// The units are only copied out of the store when iterated over.
+    pub fn export_since<'a, U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &self,
+        round: Round,
+        store: &'a UnitStore<U>,
+    ) -> impl Iterator<Item = UncheckedSignedUnit<H, D, MK::Signature>> + 'a {
+        store
+            .units_since(round)
+            .into_iter()
+            .map(|unit| unit.clone().unpack().into_unchecked())
+    }
+
+    /// At most `limit` units of rounds from `from_round` to `to_round` inclusive from the store, in order
+    /// of rounds, e.g. to answer a catch-up request. When truncated, only the lowest rounds are included,
+    /// so the requester can import them and ask for the rest.
+    pub fn export_rounds<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &self,
+        from_round: Round,
+        to_round: Round,
+        limit: usize,
+        store: &UnitStore<U>,
+    ) -> Vec<UncheckedSignedUnit<H, D, MK::Signature>> {
+        self.export_since(from_round, store)
+            .take_while(|unit| unit.as_signable().round() <= to_round)
+            .take(limit)
+            .collect()
+    }
+
+    /// Add many units to the Dag at once, in order of rounds. If any of the units is invalid by itself,
+    /// e.g. wrongly signed, nothing gets added. Units with missing parents wait for them just like
+    /// units from the network, so units exported since a nonzero round can be imported.
+    pub fn import<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        units: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+        store: &UnitStore<U>,
+    ) -> Result<DagResult<H, D, MK>, ImportError<H, D, MK>> {
+        let mut units = self
+            .validator
+            .check_batch(units)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ImportError)?;
+        units.sort_by_key(|unit| unit.round());
+        let mut result = DagResult::empty();
+        for unit in units {
+            let validated = self.validator.validate_checked(unit, store);
+            result.accumulate(self.handle_validated(validated));
+        }
+        Ok(result)
+    }
+
     /// Process a forking notification, potentially returning a lot of unit processing results.
     pub fn process_forking_notification<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
         &mut self,
@@ -194,7 +328,9 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
             Units(units) => {
                 for unit in units {
                     result.accumulate(match self.validator.validate_committed(unit, store) {
-                        Ok(unit) => self.reconstruction.add_unit(unit).into(),
+                        Ok(unit) => {
+                            Self::integrated(&self.observers, self.reconstruction.add_unit(unit))
+                        }
                         Err(e) => Self::handle_validation_error(e),
                     })
                 }
@@ -208,6 +344,20 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
         self.validator.finished_processing(hash);
     }
 
+    /// Check whether the units in the store form a correct Dag, reporting all the violations found.
+    /// Only forks of nodes we know to be forkers are allowed, e.g. for debugging captured Dags.
+    pub fn check_integrity<U: UnitWithParents<Hasher = H>>(
+        &self,
+        store: &UnitStore<U>,
+    ) -> Vec<IntegrityViolation<H>> {
+        integrity::check_integrity(store.units_since(0), self.validator.known_forkers())
+    }
+
+    /// The nodes known to have created forks.
+    pub fn known_forkers(&self) -> &NodeSubset {
+        self.validator.known_forkers()
+    }
+
     pub fn status(&self) -> DagStatus {
         self.validator.status()
     }
@@ -217,14 +367,89 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Dag<H, D, MK> {
 mod test {
     use crate::{
         alerts::ForkingNotification,
-        dag::{Dag, DagResult, Request},
+        dag::{Dag, DagObserver, DagResult, DagUnit, Request, UnitFilter},
+        extension::{ElectionConfig, Ordering},
         units::{
-            random_full_parent_units_up_to, random_unit_with_parents, Unit, UnitStore,
-            Validator as UnitValidator, WrappedSignedUnit,
+            preunit_to_unchecked_signed_unit, random_full_parent_units_up_to,
+            random_unit_with_parents, ControlHash, PreUnit, SignedUnit, UncheckedSignedUnit, Unit,
+            UnitStore, UnitWithParents, Validator as UnitValidator, WrappedSignedUnit,
         },
-        NodeCount, NodeIndex, Signed,
+        NodeCount, NodeIndex, NodeMap, Round, Signed,
     };
-    use aleph_bft_mock::Keychain;
+    use aleph_bft_mock::{Data, FinalizationHandler, Hash64, Hasher64, Keychain, Signature};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    fn signed_units_up_to(
+        total_rounds: Round,
+        keychains: &[Keychain],
+        session_id: u64,
+    ) -> Vec<SignedUnit<Hasher64, Data, Keychain>> {
+        random_full_parent_units_up_to(total_rounds, NodeCount(keychains.len()), session_id)
+            .into_iter()
+            .flatten()
+            .map(|unit| {
+                let keychain = keychains
+                    .get(unit.creator().0)
+                    .expect("we have the keychains");
+                Signed::sign(unit, keychain)
+            })
+            .collect()
+    }
+
+    fn finalized(
+        node_count: NodeCount,
+        units: Vec<DagUnit<Hasher64, Data, Keychain>>,
+    ) -> Vec<Data> {
+        let (finalization_handler, mut finalized_rx) = FinalizationHandler::new();
+        let mut ordering = Ordering::new(
+            node_count,
+            finalization_handler,
+            ElectionConfig::default(),
+            Round::MAX,
+        );
+        for unit in units {
+            ordering.add_unit(unit);
+        }
+        let mut finalized = Vec::new();
+        while let Ok(Some(data)) = finalized_rx.try_next() {
+            finalized.push(data);
+        }
+        finalized
+    }
+
+    #[test]
+    fn rejects_unit_by_creator_outside_committee() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let outsider = NodeIndex(node_count.0);
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let validator = UnitValidator::new(
+            session_id,
+            Keychain::new(node_count, NodeIndex(0)),
+            max_round,
+        );
+        let mut dag = Dag::new(validator);
+        let preunit = PreUnit::new(
+            outsider,
+            0,
+            ControlHash::new(&NodeMap::with_size(node_count)),
+        );
+        let unit = preunit_to_unchecked_signed_unit(
+            preunit,
+            session_id,
+            &Keychain::new(node_count, outsider),
+        );
+        let DagResult {
+            units,
+            requests,
+            alerts,
+        } = dag.add_unit(unit, &store);
+        assert!(units.is_empty());
+        assert!(requests.is_empty());
+        assert!(alerts.is_empty());
+    }
 
     #[test]
     fn accepts_initial_units() {
@@ -661,4 +886,338 @@ mod test {
         assert_eq!(reconstructed_units.len(), 1);
         assert_eq!(reconstructed_units[0].hash(), confused_unit);
     }
+
+    #[test]
+    fn imported_units_are_ordered_identically() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let total_rounds = 10;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut added = Vec::new();
+        for unit in signed_units_up_to(total_rounds, &keychains, session_id) {
+            for unit in dag.add_unit(unit.into(), &store).units {
+                store.insert(unit.clone());
+                added.push(unit);
+            }
+        }
+        let exported: Vec<_> = dag.export_since(0, &store).collect();
+        assert_eq!(exported.len(), (total_rounds as usize + 1) * node_count.0);
+
+        let empty_store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut other_dag = Dag::new(UnitValidator::new(session_id, keychains[1], max_round));
+        let DagResult {
+            units,
+            requests,
+            alerts,
+        } = other_dag
+            .import(exported.into_iter().rev().collect(), &empty_store)
+            .expect("all units are valid");
+        assert!(requests.is_empty());
+        assert!(alerts.is_empty());
+        assert_eq!(units.len(), added.len());
+        let expected = finalized(node_count, added);
+        assert!(!expected.is_empty());
+        assert_eq!(finalized(node_count, units), expected);
+    }
+
+    #[test]
+    fn import_since_round_waits_for_parents() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let total_rounds = 10;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        for unit in signed_units_up_to(total_rounds, &keychains, session_id) {
+            for unit in dag.add_unit(unit.into(), &store).units {
+                store.insert(unit);
+            }
+        }
+
+        let empty_store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut other_dag = Dag::new(UnitValidator::new(session_id, keychains[1], max_round));
+        let DagResult {
+            units, requests, ..
+        } = other_dag
+            .import(dag.export_since(5, &store).collect(), &empty_store)
+            .expect("all units are valid");
+        assert!(units.is_empty());
+        assert!(!requests.is_empty());
+        let earlier = dag
+            .export_since(0, &store)
+            .filter(|unit| unit.as_signable().round() < 5)
+            .collect();
+        let DagResult { units, .. } = other_dag
+            .import(earlier, &empty_store)
+            .expect("all units are valid");
+        assert_eq!(units.len(), (total_rounds as usize + 1) * node_count.0);
+    }
+
+    #[test]
+    fn catches_up_from_peer_and_continues_in_lockstep() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let caught_up_rounds = 10;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let (held, later): (Vec<_>, Vec<_>) =
+            signed_units_up_to(caught_up_rounds + 1, &keychains, session_id)
+                .into_iter()
+                .partition(|unit| unit.round() <= caught_up_rounds);
+        let mut peer_store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut peer_dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut peer_added = Vec::new();
+        for unit in held {
+            for unit in peer_dag.add_unit(unit.into(), &peer_store).units {
+                peer_store.insert(unit.clone());
+                peer_added.push(unit);
+            }
+        }
+
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[1], max_round));
+        let response = peer_dag.export_rounds(0, caught_up_rounds, usize::MAX, &peer_store);
+        let DagResult {
+            units,
+            requests,
+            alerts,
+        } = dag.import(response, &store).expect("all units are valid");
+        assert!(requests.is_empty());
+        assert!(alerts.is_empty());
+        assert_eq!(units.len(), peer_added.len());
+        let mut added = Vec::new();
+        for unit in units {
+            store.insert(unit.clone());
+            added.push(unit);
+        }
+
+        for unit in later {
+            let peer_units = peer_dag.add_unit(unit.clone().into(), &peer_store).units;
+            let units = dag.add_unit(unit.into(), &store).units;
+            assert_eq!(
+                units.iter().map(|unit| unit.hash()).collect::<Vec<_>>(),
+                peer_units
+                    .iter()
+                    .map(|unit| unit.hash())
+                    .collect::<Vec<_>>()
+            );
+            for unit in peer_units {
+                peer_store.insert(unit.clone());
+                peer_added.push(unit);
+            }
+            for unit in units {
+                store.insert(unit.clone());
+                added.push(unit);
+            }
+        }
+        assert_eq!(added.len(), (caught_up_rounds as usize + 2) * node_count.0);
+        let expected = finalized(node_count, peer_added);
+        assert!(!expected.is_empty());
+        assert_eq!(finalized(node_count, added), expected);
+    }
+
+    #[test]
+    fn export_rounds_keeps_lowest_rounds_within_limit() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        for unit in signed_units_up_to(10, &keychains, session_id) {
+            for unit in dag.add_unit(unit.into(), &store).units {
+                store.insert(unit);
+            }
+        }
+        let rounds = |units: Vec<UncheckedSignedUnit<Hasher64, Data, Signature>>| {
+            units
+                .iter()
+                .map(|unit| unit.as_signable().round())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rounds(dag.export_rounds(3, 5, usize::MAX, &store)),
+            vec![3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5]
+        );
+        assert_eq!(
+            rounds(dag.export_rounds(3, 5, 6, &store)),
+            vec![3, 3, 3, 3, 4, 4]
+        );
+        assert!(dag.export_rounds(11, 20, usize::MAX, &store).is_empty());
+    }
+
+    #[test]
+    fn rejects_import_with_invalid_unit() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        let mut units: Vec<_> = signed_units_up_to(2, &keychains, session_id)
+            .into_iter()
+            .map(|unit| unit.into())
+            .collect();
+        // A unit from another session, otherwise correct.
+        let foreign = random_full_parent_units_up_to(0, node_count, session_id + 1)[0][0].clone();
+        let mut with_foreign = units.clone();
+        with_foreign.push(Signed::sign(foreign, &keychains[0]).into());
+        assert!(dag.import(with_foreign, &store).is_err());
+        // Nothing was added, so the valid units are all accepted afterwards.
+        units.reverse();
+        let DagResult { units: added, .. } =
+            dag.import(units, &store).expect("all units are valid");
+        assert_eq!(added.len(), 3 * node_count.0);
+    }
+
+    struct RecordingObserver {
+        added: Mutex<Vec<DagUnit<Hasher64, Data, Keychain>>>,
+    }
+
+    impl DagObserver<Hasher64, Data, Keychain> for RecordingObserver {
+        fn unit_added(&self, unit: &DagUnit<Hasher64, Data, Keychain>) {
+            self.added.lock().push(unit.clone());
+        }
+    }
+
+    #[test]
+    fn observes_units_in_order_of_integration() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let total_rounds = 5;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let observer = Arc::new(RecordingObserver {
+            added: Mutex::new(Vec::new()),
+        });
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round))
+            .with_observer(observer.clone());
+        let mut units = signed_units_up_to(total_rounds, &keychains, session_id);
+        let last = units.remove(0);
+        let mut added = Vec::new();
+        for unit in units.into_iter().rev() {
+            added.extend(dag.add_unit(unit.into(), &store).units);
+        }
+        // Only the remaining initial units were added, the rest waits for the first one.
+        assert_eq!(observer.added.lock().len(), node_count.0 - 1);
+        added.extend(dag.add_unit(last.into(), &store).units);
+        let added: Vec<_> = added.iter().map(|unit| unit.hash()).collect();
+        let observed: Vec<Hash64> = observer
+            .added
+            .lock()
+            .iter()
+            .map(|unit| unit.hash())
+            .collect();
+        assert_eq!(observed.len(), (total_rounds as usize + 1) * node_count.0);
+        assert_eq!(observed, added);
+        for (position, unit) in observer.added.lock().iter().enumerate() {
+            for parent in unit.parents().values() {
+                assert!(observed[..position].contains(parent));
+            }
+        }
+    }
+
+    struct CreatorFilter {
+        rejected: NodeIndex,
+    }
+
+    impl UnitFilter<Hasher64, Data, Keychain> for CreatorFilter {
+        fn accept(&self, unit: &SignedUnit<Hasher64, Data, Keychain>) -> bool {
+            unit.creator() != self.rejected
+        }
+    }
+
+    #[test]
+    fn drops_units_rejected_by_filter() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let rejected = NodeIndex(3);
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let store = UnitStore::<WrappedSignedUnit>::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round))
+            .with_filter(Arc::new(CreatorFilter { rejected }));
+        // The other creators never use the units of the rejected one as parents.
+        let mut rounds = random_full_parent_units_up_to(0, node_count, session_id);
+        for _ in 0..8 {
+            let parents: Vec<_> = rounds
+                .last()
+                .expect("there is a round")
+                .iter()
+                .filter(|unit| unit.creator() != rejected)
+                .cloned()
+                .collect();
+            rounds.push(
+                node_count
+                    .into_iterator()
+                    .map(|creator| random_unit_with_parents(creator, &parents))
+                    .collect(),
+            );
+        }
+        let rejected_data: Vec<_> = rounds
+            .iter()
+            .flatten()
+            .filter(|unit| unit.creator() == rejected)
+            .filter_map(|unit| *unit.data())
+            .collect();
+        let mut added = Vec::new();
+        for unit in rounds.into_iter().flatten() {
+            let keychain = &keychains[unit.creator().0];
+            added.extend(
+                dag.add_unit(Signed::sign(unit, keychain).into(), &store)
+                    .units,
+            );
+        }
+        assert_eq!(added.len(), 9 * 3);
+        assert!(added.iter().all(|unit| unit.creator() != rejected));
+        let finalized = finalized(node_count, added);
+        assert!(!finalized.is_empty());
+        assert!(finalized.iter().all(|data| !rejected_data.contains(data)));
+    }
+
+    #[test]
+    fn added_units_pass_integrity_check() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let max_round = 2137;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::new(node_count);
+        let mut dag = Dag::new(UnitValidator::new(session_id, keychains[0], max_round));
+        for unit in signed_units_up_to(5, &keychains, session_id) {
+            for added in dag.add_unit(unit.into(), &store).units {
+                store.insert(added);
+            }
+        }
+        assert_eq!(store.units_since(0).len(), 6 * node_count.0);
+        assert!(dag.check_integrity(&store).is_empty());
+    }
 }
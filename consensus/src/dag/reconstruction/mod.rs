@@ -301,6 +301,43 @@ mod test {
         assert_eq!(units.len(), 4 * 8 - 3);
     }
 
+    #[test]
+    fn requests_missing_parents_and_outputs_in_dag_order() {
+        let n_members = NodeCount(4);
+        let mut reconstruction = Reconstruction::new();
+        let dag = random_full_parent_units_up_to(3, n_members, 43);
+        for unit in dag.iter().take(2).flatten() {
+            let ReconstructionResult { units, requests } = reconstruction.add_unit(unit.clone());
+            assert!(requests.is_empty());
+            assert_eq!(units.len(), 1);
+        }
+        // A unit of round 3 arrives before its parents from round 2.
+        let late_child = dag[3][0].clone();
+        let ReconstructionResult { units, requests } = reconstruction.add_unit(late_child.clone());
+        assert!(units.is_empty());
+        let expected_requests: Vec<_> = n_members
+            .into_iterator()
+            .map(|creator| Request::Coord(UnitCoord::new(2, creator)))
+            .collect();
+        assert_eq!(requests, expected_requests);
+        let mut reconstructed = Vec::new();
+        for unit in &dag[2] {
+            let ReconstructionResult {
+                mut units,
+                requests,
+            } = reconstruction.add_unit(unit.clone());
+            assert!(requests.is_empty());
+            reconstructed.append(&mut units);
+        }
+        assert_eq!(reconstructed.len(), n_members.0 + 1);
+        // The parents are output before the unit waiting for them.
+        let child = reconstructed.pop().expect("just checked");
+        assert_eq!(child.hash(), late_child.hash());
+        for (parent, reconstructed_parent) in reconstructed.iter().zip(child.parents().values()) {
+            assert_eq!(&parent.hash(), reconstructed_parent);
+        }
+    }
+
     #[test]
     fn handles_bad_hash() {
         let node_count = NodeCount(7);
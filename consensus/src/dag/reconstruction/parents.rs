@@ -50,7 +50,12 @@ impl<U: Unit> ReconstructingUnit<U> {
         match self {
             Reconstructing(unit, mut parents) => {
                 parents.insert(parent_id, parent_hash);
-                match parents.item_count() == unit.control_hash().parents().count() {
+                match unit
+                    .control_hash()
+                    .parents_mask
+                    .difference(&parents.to_subset())
+                    .is_empty()
+                {
                     // We have enought parents, just need to check the control hash matches.
                     true => match ReconstructedUnit::with_parents(unit, parents) {
                         Ok(unit) => Reconstructed(unit),
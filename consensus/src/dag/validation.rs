@@ -1,7 +1,11 @@
-use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    sync::Arc,
+};
 
 use crate::{
     alerts::Alert,
+    dag::UnitFilter,
     units::{
         SignedUnit, UncheckedSignedUnit, Unit, UnitStore, UnitStoreStatus, ValidationError,
         Validator as UnitValidator, WrappedUnit,
@@ -16,6 +20,7 @@ pub enum Error<H: Hasher, D: Data, MK: MultiKeychain> {
     Duplicate(SignedUnit<H, D, MK>),
     Uncommitted(SignedUnit<H, D, MK>),
     NewForker(Box<Alert<H, D, MK::Signature>>),
+    Rejected(SignedUnit<H, D, MK>),
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Debug for Error<H, D, MK> {
@@ -26,6 +31,7 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Debug for Error<H, D, MK> {
             Duplicate(u) => write!(f, "Duplicate({:?})", u.clone().into_unchecked()),
             Uncommitted(u) => write!(f, "Uncommitted({:?})", u.clone().into_unchecked()),
             NewForker(a) => write!(f, "NewForker({:?})", a),
+            Rejected(u) => write!(f, "Rejected({:?})", u.clone().into_unchecked()),
         }
     }
 }
@@ -68,6 +74,7 @@ pub struct Validator<H: Hasher, D: Data, MK: MultiKeychain> {
     unit_validator: UnitValidator<MK>,
     processing_units: UnitStore<SignedUnit<H, D, MK>>,
     known_forkers: NodeSubset,
+    filter: Option<Arc<dyn UnitFilter<H, D, MK>>>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
@@ -78,9 +85,21 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
             unit_validator,
             processing_units: UnitStore::new(node_count),
             known_forkers: NodeSubset::with_size(node_count),
+            filter: None,
         }
     }
 
+    /// Reject all the incoming units that the filter does not accept.
+    pub fn with_filter(mut self, filter: Arc<dyn UnitFilter<H, D, MK>>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// The nodes we know to have created forks.
+    pub fn known_forkers(&self) -> &NodeSubset {
+        &self.known_forkers
+    }
+
     fn is_forker(&self, node_id: NodeIndex) -> bool {
         self.known_forkers[node_id]
     }
@@ -111,6 +130,14 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         store: &UnitStore<U>,
     ) -> ValidatorResult<H, D, MK> {
         let unit = self.unit_validator.validate_unit(unit)?;
+        self.check_duplicate(unit, store)
+    }
+
+    fn check_duplicate<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &self,
+        unit: SignedUnit<H, D, MK>,
+        store: &UnitStore<U>,
+    ) -> ValidatorResult<H, D, MK> {
         let unit_hash = unit.as_signable().hash();
         if store.unit(&unit_hash).is_some() || self.processing_units.unit(&unit_hash).is_some() {
             return Err(Error::Duplicate(unit));
@@ -118,14 +145,37 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
         Ok(unit)
     }
 
+    /// Check the properties of many units that do not depend on other units, e.g. their signatures,
+    /// verifying all the signatures in a single batch. The units then still have to go through `validate_checked`.
+    pub fn check_batch(
+        &self,
+        units: Vec<UncheckedSignedUnit<H, D, MK::Signature>>,
+    ) -> Vec<ValidatorResult<H, D, MK>> {
+        self.unit_validator
+            .validate_units(units)
+            .into_iter()
+            .map(|result| result.map_err(Error::from))
+            .collect()
+    }
+
     /// Validate an incoming unit.
     pub fn validate<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
         &mut self,
         unit: UncheckedSignedUnit<H, D, MK::Signature>,
         store: &UnitStore<U>,
+    ) -> ValidatorResult<H, D, MK> {
+        let unit = self.unit_validator.validate_unit(unit)?;
+        self.validate_checked(unit, store)
+    }
+
+    /// Validate an incoming unit that already passed `check_batch`.
+    pub fn validate_checked<U: WrappedUnit<H, Wrapped = SignedUnit<H, D, MK>>>(
+        &mut self,
+        unit: SignedUnit<H, D, MK>,
+        store: &UnitStore<U>,
     ) -> ValidatorResult<H, D, MK> {
         use Error::*;
-        let unit = self.pre_validate(unit, store)?;
+        let unit = self.check_duplicate(unit, store)?;
         let unit_coord = unit.as_signable().coord();
         if self.is_forker(unit_coord.creator()) {
             return Err(Uncommitted(unit));
@@ -143,6 +193,12 @@ impl<H: Hasher, D: Data, MK: MultiKeychain> Validator<H, D, MK> {
                 committed_units,
             ))));
         }
+        // Filtering after detecting forks, so rejected units can still serve as fork proofs.
+        if let Some(filter) = &self.filter {
+            if !filter.accept(&unit) {
+                return Err(Rejected(unit));
+            }
+        }
         self.processing_units.insert(unit.clone());
         Ok(unit)
     }
@@ -0,0 +1,165 @@
+use crate::{
+    Hasher, Index, Indexed, MultiKeychain, Multisigned, Round, Signable, Signature, Signed,
+    UncheckedSigned,
+};
+use codec::{Decode, Encode};
+
+/// The claim that the unit with the given hash got elected as the head of the round.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Decode, Encode)]
+pub struct HeadClaim<H: Hasher> {
+    pub round: Round,
+    pub head: H::Hash,
+}
+
+impl<H: Hasher> Signable for HeadClaim<H> {
+    type Hash = Vec<u8>;
+
+    fn hash(&self) -> Self::Hash {
+        self.encode()
+    }
+}
+
+/// A signature of a single node over a head claim, to be combined into a certificate.
+pub type SignedHeadClaim<H, S> = UncheckedSigned<Indexed<HeadClaim<H>>, S>;
+
+/// A proof that a supermajority of nodes agreed on the head of a round,
+/// which can be verified without replaying the elections, e.g. by light clients.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct HeadCertificate<H: Hasher, S: Signature> {
+    round: Round,
+    head: H::Hash,
+    multisignature: S,
+}
+
+impl<H: Hasher, S: Signature> HeadCertificate<H, S> {
+    /// The round of the certified head.
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// The hash of the certified head.
+    pub fn head(&self) -> H::Hash {
+        self.head
+    }
+
+    /// Whether the certificate contains a complete multisignature over its head.
+    pub fn verify<MK: MultiKeychain<PartialMultisignature = S>>(&self, keychain: &MK) -> bool {
+        let claim = HeadClaim::<H> {
+            round: self.round,
+            head: self.head,
+        };
+        keychain.is_complete(claim.hash().as_ref(), &self.multisignature)
+    }
+}
+
+/// Sign the claim that the unit with the given hash is the head of the round, for certifying it with other nodes.
+pub fn sign_head<H: Hasher, MK: MultiKeychain>(
+    round: Round,
+    head: H::Hash,
+    keychain: &MK,
+) -> SignedHeadClaim<H, MK::Signature> {
+    Signed::sign_with_index(HeadClaim { round, head }, keychain).into()
+}
+
+/// Combine the signatures of nodes over the given head into a certificate. Invalid signatures
+/// and ones over other claims are skipped. Returns `None` if the rest do not suffice for a complete multisignature.
+pub fn certify_head<H: Hasher, MK: MultiKeychain>(
+    round: Round,
+    head: H::Hash,
+    signatures: impl IntoIterator<Item = SignedHeadClaim<H, MK::Signature>>,
+    keychain: &MK,
+) -> Option<HeadCertificate<H, MK::PartialMultisignature>> {
+    let claim = HeadClaim { round, head };
+    let partials: Vec<_> = signatures
+        .into_iter()
+        .filter(|signed| signed.as_signable_strip_index() == &claim)
+        .filter_map(|signed| signed.check(keychain).ok())
+        .map(|signed| {
+            let unchecked = signed.into_unchecked();
+            (unchecked.as_signable().index(), unchecked.signature())
+        })
+        .collect();
+    let multisignature = Multisigned::combine(claim, &partials, keychain)?
+        .into_unchecked()
+        .signature();
+    Some(HeadCertificate {
+        round,
+        head,
+        multisignature,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        extension::certificate::{certify_head, sign_head, HeadCertificate},
+        MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
+    };
+    use aleph_bft_mock::{Hasher64, Keychain};
+
+    fn keychains(n_members: NodeCount) -> Vec<Keychain> {
+        n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect()
+    }
+
+    #[test]
+    fn certifies_head_signed_by_supermajority() {
+        let n_members = NodeCount(7);
+        let keychains = keychains(n_members);
+        let (round, head) = (5, [7; 8]);
+        let signatures: Vec<_> = keychains
+            .iter()
+            .take(5)
+            .map(|keychain| sign_head::<Hasher64, _>(round, head, keychain))
+            .collect();
+        let certificate =
+            certify_head(round, head, signatures, &keychains[6]).expect("enough signers");
+        assert_eq!(certificate.round(), round);
+        assert_eq!(certificate.head(), head);
+        for keychain in &keychains {
+            assert!(certificate.verify(keychain));
+        }
+        let mut other_head = certificate.clone();
+        other_head.head = [8; 8];
+        assert!(!other_head.verify(&keychains[0]));
+    }
+
+    #[test]
+    fn rejects_too_few_signers() {
+        let n_members = NodeCount(7);
+        let keychains = keychains(n_members);
+        let (round, head) = (5, [7; 8]);
+        let mut signatures: Vec<_> = keychains
+            .iter()
+            .take(4)
+            .map(|keychain| sign_head::<Hasher64, _>(round, head, keychain))
+            .collect();
+        // Signatures over another head do not count.
+        signatures.push(sign_head(round, [8; 8], &keychains[4]));
+        assert!(certify_head(round, head, signatures.clone(), &keychains[0]).is_none());
+
+        let multisignature = signatures
+            .into_iter()
+            .take(4)
+            .map(|signed| signed.signature())
+            .enumerate()
+            .fold(None, |multisignature, (index, signature)| {
+                let index = NodeIndex(index);
+                Some(match multisignature {
+                    None => keychains[0].bootstrap_multi(&signature, index),
+                    Some(multisignature) => {
+                        PartialMultisignature::add_signature(multisignature, &signature, index)
+                    }
+                })
+            })
+            .expect("there are signatures");
+        let certificate = HeadCertificate::<Hasher64, _> {
+            round,
+            head,
+            multisignature,
+        };
+        assert!(!certificate.verify(&keychains[0]));
+    }
+}
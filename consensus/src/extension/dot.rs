@@ -0,0 +1,108 @@
+use crate::{
+    extension::units::{UnitStorage, Units},
+    units::{HashFor, UnitWithParents},
+    Round,
+};
+use std::{collections::HashSet, fmt::Write, ops::RangeInclusive};
+
+// How many bytes of the hash go into the label of a unit.
+const SHORT_HASH_BYTES: usize = 4;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+/// Renders the units as a Graphviz DOT graph, e.g. for debugging. Every unit is a node labeled with its creator,
+/// round and the beginning of its hash, with edges going to its parents, and the heads are highlighted.
+/// If rounds are given only units of these rounds are included, together with the edges between them.
+pub fn to_dot<U: UnitWithParents, S: UnitStorage<U>>(
+    units: &Units<U, S>,
+    rounds: Option<RangeInclusive<Round>>,
+    heads: &[HashFor<U>],
+) -> String {
+    let rounds = rounds.unwrap_or(units.lowest_round()..=units.highest_round());
+    let heads: HashSet<_> = heads.iter().collect();
+    let mut dot = String::from("digraph dag {\n    rankdir=BT;\n");
+    for round in rounds.clone() {
+        for unit in units.in_round(round).unwrap_or_default() {
+            let hash = unit.hash();
+            let id = hex(hash.as_ref());
+            let short_hash = hex(&hash.as_ref()[..SHORT_HASH_BYTES.min(hash.as_ref().len())]);
+            let style = match heads.contains(&hash) {
+                true => ", style=filled, fillcolor=gold",
+                false => "",
+            };
+            // Writing to a string cannot fail.
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}/{}\\n{}\"{}];",
+                id,
+                unit.creator().0,
+                round,
+                short_hash,
+                style
+            );
+            // Parents are always from the previous round.
+            if round == 0 || !rounds.contains(&(round - 1)) {
+                continue;
+            }
+            for parent in unit.parents().values() {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\";", id, hex(parent.as_ref()));
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        extension::{dot::to_dot, extender::Extender, units::Units},
+        units::{random_full_parent_reconstrusted_units_up_to, Unit},
+        NodeCount, Round,
+    };
+
+    fn count_nodes_and_edges(dot: &str) -> (usize, usize) {
+        let nodes = dot.lines().filter(|line| line.contains("[label=")).count();
+        let edges = dot.lines().filter(|line| line.contains(" -> ")).count();
+        (nodes, edges)
+    }
+
+    #[test]
+    fn renders_units_and_parents() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 5;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, 2137);
+        let mut units = Units::new(n_members);
+        let mut extender = Extender::new(n_members);
+        let mut heads = Vec::new();
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+            for batch in extender.add_unit(unit.clone()) {
+                heads.push(batch.last().expect("batches are not empty").hash());
+            }
+        }
+        assert_eq!(heads.len(), 2);
+
+        let dot = to_dot(&units, None, &heads);
+        assert!(dot.starts_with("digraph dag {"));
+        // Every unit above round 0 has all the units of the previous round as parents.
+        assert_eq!(
+            count_nodes_and_edges(&dot),
+            (6 * n_members.0, 5 * n_members.0 * n_members.0)
+        );
+        assert_eq!(dot.matches("style=filled").count(), heads.len());
+
+        // Parents from outside of the rounds are skipped.
+        let dot = to_dot(&units, Some(2..=3), &heads);
+        assert_eq!(
+            count_nodes_and_edges(&dot),
+            (2 * n_members.0, n_members.0 * n_members.0)
+        );
+        assert_eq!(dot.matches("style=filled").count(), 0);
+    }
+}
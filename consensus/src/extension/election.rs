@@ -1,26 +1,470 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::{self, Debug, Formatter},
+    iter::Sum,
+    ops::{Add, AddAssign},
+    sync::Arc,
+};
 
+#[cfg(feature = "metrics")]
+use crate::Metrics;
 use crate::{
-    extension::units::Units,
-    units::{HashFor, UnitWithParents},
-    Hasher, NodeCount, NodeIndex, NodeMap, Round,
+    extension::units::{BatchOrder, CanonicalBatchOrder, UnitStorage, Units},
+    units::{HashFor, Unit, UnitWithParents},
+    Config, Hasher, NodeCount, NodeIndex, NodeMap, NodeSubset, Quorum, Round, StatusMonitor,
 };
+use codec::{Decode, Encode};
+use log::{debug, log_enabled, trace, warn, Level};
+use thiserror::Error;
+
+const LOG_TARGET: &str = "AlephBFT-election";
+
+/// The schedule of default votes, used when the votes of a unit's parents are not unanimous.
+pub trait CommonVote: Send + Sync {
+    /// The default vote of units at the given round, relative to the round of the candidate.
+    /// Only ever called for relative rounds of at least 2.
+    fn vote(&self, relative_round: Round) -> bool;
+}
+
+/// The common vote schedule of the Aleph protocol.
+pub struct DefaultCommonVote;
+
+impl CommonVote for DefaultCommonVote {
+    fn vote(&self, relative_round: Round) -> bool {
+        // This should only be called for relative round >= 2, so to be precise we start with true, false, true, and then
+        if relative_round == 3 {
+            return false;
+        }
+        if relative_round <= 4 {
+            return true;
+        }
+        // we alternate between true and false starting from true in round 5.
+        relative_round % 2 == 1
+    }
+}
+
+/// The order in which the candidates of a round are tried, which decides between them when more than one could be elected.
+pub trait CandidateOrder: Send + Sync {
+    /// The priority of a candidate, candidates with lower priorities are tried first.
+    /// Candidates with equal priorities are tried in the order of their hashes.
+    fn priority(&self, creator: NodeIndex, hash: &[u8]) -> u64;
+}
+
+/// Tries the candidates in the order of their hashes.
+pub struct HashOrder;
+
+impl CandidateOrder for HashOrder {
+    fn priority(&self, _creator: NodeIndex, _hash: &[u8]) -> u64 {
+        0
+    }
+}
+
+/// The voting weight of a node, or the total weight of a set of votes.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Weight(pub u64);
+
+impl Add for Weight {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Weight(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Weight {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0
+    }
+}
+
+impl Sum for Weight {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Weight(0), Add::add)
+    }
+}
+
+impl From<NodeCount> for Weight {
+    fn from(count: NodeCount) -> Self {
+        Weight(count.0 as u64)
+    }
+}
+
+/// The weight of votes required to decide a candidate election, determined by the assumed fault model.
+pub trait Threshold: Send + Sync {
+    /// The weight of votes out of the given total weight required to decide.
+    fn supermajority(&self, total: Weight) -> Weight;
+}
+
+/// The threshold for Byzantine faults, i.e. more than two thirds of the total weight.
+pub struct ByzantineThreshold;
+
+impl Threshold for ByzantineThreshold {
+    fn supermajority(&self, total: Weight) -> Weight {
+        NodeCount(total.0 as usize).supermajority().into()
+    }
+}
+
+/// The pluggable parts of the election rules.
+#[derive(Clone)]
+pub struct ElectionConfig {
+    common_vote: Arc<dyn CommonVote>,
+    threshold: Arc<dyn Threshold>,
+    candidate_order: Arc<dyn CandidateOrder>,
+    batch_order: Arc<dyn BatchOrder>,
+    // If missing every node has weight 1.
+    weights: Option<Arc<NodeMap<Weight>>>,
+    stall_budget: Option<Round>,
+    start_offset: Round,
+    // If present, votes of parents created by these nodes are ignored.
+    abstaining_equivocators: Option<NodeSubset>,
+    vote_warmup: bool,
+    // If present, units this many rounds below the last ordered head are forgotten.
+    pruning_depth: Option<Round>,
+    // If present, deciding votes additionally have to come from a quorum.
+    quorum: Option<Arc<dyn Quorum>>,
+    status_monitor: Option<StatusMonitor>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Metrics>,
+}
+
+const DEFAULT_STALL_BUDGET: Round = 50;
+
+const DEFAULT_PRUNING_DEPTH: Round = 50;
+
+/// Without units of at least this many rounds above the election round we might not know about
+/// the winning candidate, so starting the election earlier would not be safe.
+const MIN_START_OFFSET: Round = 3;
+
+/// A configuration of elections that would not be safe.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum ElectionConfigError {
+    #[error("Elections cannot start fewer than {MIN_START_OFFSET} rounds ahead, requested {0}.")]
+    StartOffsetTooSmall(Round),
+}
+
+impl Default for ElectionConfig {
+    fn default() -> Self {
+        ElectionConfig {
+            common_vote: Arc::new(DefaultCommonVote),
+            threshold: Arc::new(ByzantineThreshold),
+            candidate_order: Arc::new(HashOrder),
+            batch_order: Arc::new(CanonicalBatchOrder),
+            weights: None,
+            stall_budget: Some(DEFAULT_STALL_BUDGET),
+            start_offset: MIN_START_OFFSET,
+            abstaining_equivocators: None,
+            vote_warmup: false,
+            pruning_depth: Some(DEFAULT_PRUNING_DEPTH),
+            quorum: None,
+            status_monitor: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+}
+
+impl Debug for ElectionConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElectionConfig")
+            .field("weights", &self.weights)
+            .field("stall budget", &self.stall_budget)
+            .field("start offset", &self.start_offset)
+            .field("abstaining equivocators", &self.abstaining_equivocators)
+            .field("vote warmup", &self.vote_warmup)
+            .field("pruning depth", &self.pruning_depth)
+            .field("quorum", &self.quorum)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<&Config> for ElectionConfig {
+    fn from(config: &Config) -> Self {
+        let election_config = config.election_config().clone();
+        let election_config = ElectionConfig {
+            status_monitor: config.status_monitor().cloned(),
+            quorum: config.quorum().cloned().or(election_config.quorum),
+            ..election_config
+        };
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = config.metrics() {
+            return election_config.with_metrics(metrics.clone());
+        }
+        election_config
+    }
+}
+
+impl ElectionConfig {
+    fn total_weight(&self, n_members: NodeCount) -> Weight {
+        match &self.weights {
+            Some(weights) => weights.values().copied().sum(),
+            None => n_members.into(),
+        }
+    }
+
+    /// The weight of votes deciding elections in a committee of the given size.
+    #[cfg(test)]
+    pub(crate) fn threshold(&self, n_members: NodeCount) -> Weight {
+        self.threshold.supermajority(self.total_weight(n_members))
+    }
+
+    /// Use the provided common vote schedule.
+    pub fn with_common_vote(self, common_vote: Arc<dyn CommonVote>) -> Self {
+        ElectionConfig {
+            common_vote,
+            ..self
+        }
+    }
+
+    /// Use the provided threshold.
+    pub fn with_threshold(self, threshold: Arc<dyn Threshold>) -> Self {
+        ElectionConfig { threshold, ..self }
+    }
 
-fn common_vote(relative_round: Round) -> bool {
-    // This should only be called for relative round >= 2, so to be precise we start with true, false, true, and then
-    if relative_round == 3 {
-        return false;
+    /// Try the candidates in the provided order.
+    pub fn with_candidate_order(self, candidate_order: Arc<dyn CandidateOrder>) -> Self {
+        ElectionConfig {
+            candidate_order,
+            ..self
+        }
+    }
+
+    /// Order the units within batches using the provided order.
+    pub fn with_batch_order(self, batch_order: Arc<dyn BatchOrder>) -> Self {
+        ElectionConfig {
+            batch_order,
+            ..self
+        }
+    }
+
+    /// The order of the units within batches.
+    pub fn batch_order(&self) -> &dyn BatchOrder {
+        self.batch_order.as_ref()
+    }
+
+    /// Weigh the votes of nodes using the provided weights, nodes without a weight get no votes.
+    pub fn with_weights(self, weights: NodeMap<Weight>) -> Self {
+        ElectionConfig {
+            weights: Some(Arc::new(weights)),
+            ..self
+        }
+    }
+
+    /// Report elections that are still undecided after voters from more than the given number of rounds
+    /// above the election round have been added, or never report them if `None`.
+    pub fn with_stall_budget(self, stall_budget: Option<Round>) -> Self {
+        ElectionConfig {
+            stall_budget,
+            ..self
+        }
+    }
+
+    /// Treat the votes of parents created by the given equivocators as abstentions, lowering the threshold
+    /// accordingly, so that forkers cannot sway elections. Votes must be the same on all nodes,
+    /// so all of them have to use the same set, e.g. one agreed on before the session.
+    pub fn with_abstaining_equivocators(self, equivocators: NodeSubset) -> Self {
+        ElectionConfig {
+            abstaining_equivocators: Some(equivocators),
+            ..self
+        }
+    }
+
+    /// Only start elections once there are units of at least the given number of rounds above the election round.
+    /// Fails for offsets that are not safe, i.e. below 3, which is the default.
+    pub fn with_start_offset(self, start_offset: Round) -> Result<Self, ElectionConfigError> {
+        if start_offset < MIN_START_OFFSET {
+            return Err(ElectionConfigError::StartOffsetTooSmall(start_offset));
+        }
+        Ok(ElectionConfig {
+            start_offset,
+            ..self
+        })
+    }
+
+    /// How many rounds above the election round there have to be units of before it starts.
+    pub fn start_offset(&self) -> Round {
+        self.start_offset
+    }
+
+    /// Compute the votes on the candidates of the next round to elect while waiting for it to become electable,
+    /// so that the election can be decided sooner once it starts.
+    pub fn with_vote_warmup(self, vote_warmup: bool) -> Self {
+        ElectionConfig {
+            vote_warmup,
+            ..self
+        }
+    }
+
+    /// Only decide elections with votes of parents whose creators form the given quorum,
+    /// in addition to their weight reaching the threshold.
+    pub fn with_quorum(self, quorum: Arc<dyn Quorum>) -> Self {
+        ElectionConfig {
+            quorum: Some(quorum),
+            ..self
+        }
+    }
+
+    /// Forget the units of rounds more than the given number of rounds below the most recently ordered head,
+    /// so that memory usage is bounded, or keep all the units if `None`. Units of these rounds that were not
+    /// ordered yet never get ordered, so all the nodes have to use the same depth.
+    pub fn with_pruning_depth(self, pruning_depth: Option<Round>) -> Self {
+        ElectionConfig {
+            pruning_depth,
+            ..self
+        }
+    }
+
+    /// How many rounds below the most recently ordered head units are kept, if they are forgotten at all.
+    pub fn pruning_depth(&self) -> Option<Round> {
+        self.pruning_depth
     }
-    if relative_round <= 4 {
-        return true;
+
+    /// Whether votes get computed before elections start.
+    pub fn vote_warmup(&self) -> bool {
+        self.vote_warmup
+    }
+
+    /// Report the progress of elections using the provided metrics.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(self, metrics: Metrics) -> Self {
+        ElectionConfig {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    /// The highest round whose head can be elected if no units above `max_round` exist,
+    /// or `None` if no head can be elected at all.
+    pub fn last_head_round(&self, max_round: Round) -> Option<Round> {
+        // Votes decide an election at relative round 3 at the earliest, and only when the common vote is for the candidate.
+        (3..=max_round)
+            .find(|relative_round| self.common_vote.vote(*relative_round))
+            .map(|relative_round| max_round - relative_round)
+    }
+
+    /// Stop reporting metrics, e.g. for elections that are only simulated.
+    #[cfg(feature = "metrics")]
+    pub fn without_metrics(self) -> Self {
+        ElectionConfig {
+            metrics: None,
+            ..self
+        }
+    }
+
+    /// Stop reporting to the status monitor, e.g. for elections that are only simulated.
+    pub fn without_status_monitor(self) -> Self {
+        ElectionConfig {
+            status_monitor: None,
+            ..self
+        }
     }
-    // we alternate between true and false starting from true in round 5.
-    relative_round % 2 == 1
 }
 
 enum CandidateOutcome<H: Hasher> {
-    Eliminate,
-    ElectionDone(H::Hash),
+    Eliminate(H::Hash, VoteTally),
+    ElectionDone(H::Hash, VoteTally, NodeMap<bool>),
+    Abort(VoteError<H>),
+}
+
+/// Rules shared by all the candidate elections within a single round election.
+#[derive(Clone)]
+struct VotingRules {
+    common_vote: Arc<dyn CommonVote>,
+    weights: Option<Arc<NodeMap<Weight>>>,
+    stall_budget: Option<Round>,
+    abstaining_equivocators: Option<NodeSubset>,
+    quorum: Option<Arc<dyn Quorum>>,
+    status_monitor: Option<StatusMonitor>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Metrics>,
+    // Computed once per round election, as they only depend on the committee.
+    threshold: Weight,
+    min_parents: Weight,
+}
+
+impl VotingRules {
+    fn new(config: &ElectionConfig, n_members: NodeCount) -> Self {
+        let total_weight = config.total_weight(n_members);
+        let mut rules = VotingRules {
+            common_vote: config.common_vote.clone(),
+            weights: config.weights.clone(),
+            stall_budget: config.stall_budget,
+            abstaining_equivocators: config.abstaining_equivocators.clone(),
+            quorum: config.quorum.clone(),
+            status_monitor: config.status_monitor.clone(),
+            #[cfg(feature = "metrics")]
+            metrics: config.metrics.clone(),
+            threshold: Weight(0),
+            // Every unit has parents created by a supermajority of nodes, regardless of their weights.
+            min_parents: config.threshold.supermajority(n_members.into()),
+        };
+        // Abstaining nodes do not count towards the total.
+        let abstaining_weight: Weight = rules
+            .abstaining_equivocators
+            .iter()
+            .flat_map(|equivocators| equivocators.elements())
+            .map(|node_id| rules.weight(node_id))
+            .sum();
+        rules.threshold = config
+            .threshold
+            .supermajority(Weight(total_weight.0.saturating_sub(abstaining_weight.0)));
+        rules
+    }
+
+    fn abstains(&self, node_id: NodeIndex) -> bool {
+        self.abstaining_equivocators
+            .as_ref()
+            .map(|equivocators| equivocators.contains(node_id))
+            .unwrap_or(false)
+    }
+
+    fn weight(&self, node_id: NodeIndex) -> Weight {
+        match &self.weights {
+            Some(weights) => weights.get(node_id).copied().unwrap_or_default(),
+            None => Weight(1),
+        }
+    }
+
+    fn report_election_started(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.report_election_started();
+        }
+    }
+
+    fn report_candidate_eliminated(&self) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.report_candidate_eliminated();
+        }
+    }
+
+    fn report_vote_cached(&self) {
+        if let Some(status_monitor) = &self.status_monitor {
+            status_monitor.report_vote_cached();
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.report_vote_cached();
+        }
+    }
+
+    fn report_vote_computed(&self) {
+        if let Some(status_monitor) = &self.status_monitor {
+            status_monitor.report_vote_computed();
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.report_vote_computed();
+        }
+    }
+
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn report_head_elected(&self, relative_round: Round) {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.report_head_elected(relative_round);
+        }
+    }
 }
 
 struct CandidateElection<U: UnitWithParents> {
@@ -28,57 +472,132 @@ struct CandidateElection<U: UnitWithParents> {
     candidate_creator: NodeIndex,
     candidate_hash: HashFor<U>,
     votes: HashMap<HashFor<U>, bool>,
+    rules: VotingRules,
+    // Keep voting past decisions, to get the votes of all the units.
+    #[cfg(feature = "vote_diagnostics")]
+    exhaustive: bool,
 }
 
 impl<U: UnitWithParents> CandidateElection<U> {
     /// Creates an election for the given candidate.
     /// The candidate will eventually either get elected or eliminated.
     /// Might immediately return an outcome.
-    pub fn for_candidate(
+    pub fn for_candidate<S: UnitStorage<U>>(
         candidate: &U,
-        units: &Units<U>,
+        units: &Units<U, S>,
+        rules: VotingRules,
     ) -> Result<Self, CandidateOutcome<U::Hasher>> {
+        Self::without_votes(candidate, rules).compute_votes(units)
+    }
+
+    fn without_votes(candidate: &U, rules: VotingRules) -> Self {
         CandidateElection {
             round: candidate.round(),
             candidate_creator: candidate.creator(),
             candidate_hash: candidate.hash(),
             votes: HashMap::new(),
+            rules,
+            #[cfg(feature = "vote_diagnostics")]
+            exhaustive: false,
+        }
+    }
+
+    fn decides(&self) -> bool {
+        #[cfg(feature = "vote_diagnostics")]
+        if self.exhaustive {
+            return false;
         }
-        .compute_votes(units)
+        true
     }
 
     fn parent_votes(
         &mut self,
         parents: &NodeMap<HashFor<U>>,
-    ) -> Result<(NodeCount, NodeCount), CandidateOutcome<U::Hasher>> {
-        let (mut votes_for, mut votes_against) = (NodeCount(0), NodeCount(0));
-        for parent in parents.values() {
+    ) -> Result<(Weight, Weight), CandidateOutcome<U::Hasher>> {
+        let (mut votes_for, mut votes_against) = (Weight(0), Weight(0));
+        for (creator, parent) in parents.iter() {
+            if self.rules.abstains(creator) {
+                continue;
+            }
+            let weight = self.rules.weight(creator);
             match self.votes.get(parent).expect("units are added in order") {
-                true => votes_for += NodeCount(1),
-                false => votes_against += NodeCount(1),
+                true => votes_for += weight,
+                false => votes_against += weight,
             }
         }
         Ok((votes_for, votes_against))
     }
 
+    // The votes of the parents by their creators, without the abstaining ones.
+    fn parent_vote_map(&self, parents: &NodeMap<HashFor<U>>) -> NodeMap<bool> {
+        let mut votes = NodeMap::with_size(parents.size());
+        for (creator, parent) in parents.iter() {
+            if self.rules.abstains(creator) {
+                continue;
+            }
+            if let Some(vote) = self.votes.get(parent) {
+                votes.insert(creator, *vote);
+            }
+        }
+        votes
+    }
+
+    // Whether the parents voting the given way have enough weight and form the quorum, if there is one.
+    fn is_decisive(&self, parents: &NodeMap<HashFor<U>>, vote: bool, weight: Weight) -> bool {
+        if weight < self.rules.threshold {
+            return false;
+        }
+        let quorum = match &self.rules.quorum {
+            Some(quorum) => quorum,
+            None => return true,
+        };
+        let mut voters = NodeSubset::with_size(parents.size());
+        for (creator, parent) in parents.iter() {
+            if !self.rules.abstains(creator) && self.votes.get(parent) == Some(&vote) {
+                voters.insert(creator);
+            }
+        }
+        quorum.is_quorum(&voters)
+    }
+
     fn vote_from_parents(
         &mut self,
-        parents: &NodeMap<HashFor<U>>,
+        voter: &U,
         relative_round: Round,
     ) -> Result<bool, CandidateOutcome<U::Hasher>> {
         use CandidateOutcome::*;
-        let threshold = parents.size().consensus_threshold();
+        let threshold = self.rules.threshold;
+        let parents = voter.parents();
+        // Only possible with a malformed Dag or a threshold stricter than the one units are checked with.
+        let n_parents = Weight::from(NodeCount(parents.item_count()));
+        if n_parents < self.rules.min_parents {
+            return Err(Abort(VoteError::InsufficientParentVotes {
+                voter: voter.hash(),
+                parents: n_parents,
+                required: self.rules.min_parents,
+            }));
+        }
         // Gather parents' votes.
         let (votes_for, votes_against) = self.parent_votes(parents)?;
-        assert!(votes_for + votes_against >= threshold);
-        let common_vote = common_vote(relative_round);
+        let common_vote = self.rules.common_vote.vote(relative_round);
         // If the round is sufficiently high we are done voting for the candidate if
-        if relative_round >= 3 {
+        if relative_round >= 3 && self.decides() {
+            let tally = VoteTally {
+                votes_for,
+                votes_against,
+                threshold,
+                relative_round,
+            };
             match common_vote {
                 // the default vote is for the candidate and the parents' votes are for over the threshold,
-                true if votes_for >= threshold => return Err(ElectionDone(self.candidate_hash)),
+                true if self.is_decisive(parents, true, votes_for) => {
+                    let votes = self.parent_vote_map(parents);
+                    return Err(ElectionDone(self.candidate_hash, tally, votes));
+                }
                 // or the default vote is against the candidate and the parents' votes are against over the threshold.
-                false if votes_against >= threshold => return Err(Eliminate),
+                false if self.is_decisive(parents, false, votes_against) => {
+                    return Err(Eliminate(self.candidate_hash, tally))
+                }
                 _ => (),
                 // Note that this means the earliest we can have a head elected is round 4.
             }
@@ -86,8 +605,8 @@ impl<U: UnitWithParents> CandidateElection<U> {
 
         // The vote is either identical to all the votes of the parents, or the default vote if that is not possible.
         Ok(match (votes_for, votes_against) {
-            (NodeCount(0), _) => false,
-            (_, NodeCount(0)) => true,
+            (Weight(0), _) => false,
+            (_, Weight(0)) => true,
             _ => common_vote,
         })
     }
@@ -95,28 +614,47 @@ impl<U: UnitWithParents> CandidateElection<U> {
     fn vote(&mut self, voter: &U) -> Result<(), CandidateOutcome<U::Hasher>> {
         // If the vote is already computed we are done.
         if self.votes.get(&voter.hash()).is_some() {
+            self.rules.report_vote_cached();
             return Ok(());
         }
-        // Votes for old units are never used, so we just return.
-        if voter.round() <= self.round {
-            return Ok(());
-        }
-        let relative_round = voter.round() - self.round;
+        // Units not above the candidate cannot vote, so passing them is a bug in the caller.
+        debug_assert!(
+            voter.round() > self.round,
+            "unit of round {} cannot vote on a candidate of round {}",
+            voter.round(),
+            self.round
+        );
+        let relative_round = match voter.round().checked_sub(self.round) {
+            // Votes for old units are never used, so in release builds we just return.
+            None | Some(0) => return Ok(()),
+            Some(relative_round) => relative_round,
+        };
+        self.rules.report_vote_computed();
         let vote = match relative_round {
-            0 => unreachable!("just checked that voter and election rounds are not equal"),
             // Direct descendands vote for, all other units of that round against.
             1 => voter.parents().get(self.candidate_creator) == Some(&self.candidate_hash),
             // Otherwise we compute the vote based on the parents' votes.
-            _ => self.vote_from_parents(voter.parents(), relative_round)?,
+            _ => self.vote_from_parents(voter, relative_round)?,
         };
         self.votes.insert(voter.hash(), vote);
         Ok(())
     }
 
-    fn compute_votes(mut self, units: &Units<U>) -> Result<Self, CandidateOutcome<U::Hasher>> {
-        for round in self.round + 1..=units.highest_round() {
-            for voter in units.in_round(round).expect("units are added in order") {
-                self.vote(voter)?;
+    fn compute_votes<S: UnitStorage<U>>(
+        mut self,
+        units: &Units<U, S>,
+    ) -> Result<Self, CandidateOutcome<U::Hasher>> {
+        for round in self.round.saturating_add(1)..=units.highest_round() {
+            for voter in units
+                .hashes_in_round(round)
+                .expect("units are added in order")
+            {
+                // Cached votes do not need the voter, so we avoid loading it.
+                if self.votes.contains_key(voter) {
+                    self.rules.report_vote_cached();
+                    continue;
+                }
+                self.vote(&units.get(voter).expect("we have all the units"))?;
             }
         }
         Ok(self)
@@ -127,221 +665,2406 @@ impl<U: UnitWithParents> CandidateElection<U> {
     pub fn add_voter(mut self, voter: &U) -> Result<Self, CandidateOutcome<U::Hasher>> {
         self.vote(voter).map(|()| self)
     }
+
+    /// The weights of votes for and against the candidate among the units of the highest known round.
+    pub fn tally<S: UnitStorage<U>>(&self, units: &Units<U, S>) -> (Weight, Weight) {
+        let (mut votes_for, mut votes_against) = (Weight(0), Weight(0));
+        for voter in units.in_round(units.highest_round()).unwrap_or_default() {
+            if self.rules.abstains(voter.creator()) {
+                continue;
+            }
+            let weight = self.rules.weight(voter.creator());
+            match self.votes.get(&voter.hash()) {
+                Some(true) => votes_for += weight,
+                Some(false) => votes_against += weight,
+                None => (),
+            }
+        }
+        (votes_for, votes_against)
+    }
 }
 
 /// Election for a single round.
 pub struct RoundElection<U: UnitWithParents> {
-    // Remaining candidates for this round's head, in reverse order.
-    candidates: Vec<HashFor<U>>,
+    // Remaining candidates for this round's head.
+    candidates: RemainingCandidates<U>,
     voting: CandidateElection<U>,
+    // How many candidates got eliminated so far.
+    eliminated: usize,
+}
+
+type WarmedVotes<U> = HashMap<HashFor<U>, CandidateElection<U>>;
+
+struct RemainingCandidates<U: UnitWithParents> {
+    // In reverse order.
+    hashes: Vec<HashFor<U>>,
+    // Votes on them computed before the election started.
+    warmed: WarmedVotes<U>,
+}
+
+/// Computes the votes on the candidates of a round that cannot be elected yet as units arrive, so that
+/// the election started with [`RoundElection::for_round_warmed`] mostly reuses them instead of computing them all at once.
+/// Only votes of units less than 3 rounds above the candidates are computed, as these cannot decide anything.
+pub struct VoteWarmer<U: UnitWithParents> {
+    round: Round,
+    config: ElectionConfig,
+    candidates: WarmedVotes<U>,
+}
+
+impl<U: UnitWithParents> VoteWarmer<U> {
+    /// Start computing the votes on the candidates of the round, beginning with the units already present.
+    pub fn new<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Self {
+        let mut warmer = VoteWarmer {
+            round,
+            config: config.clone(),
+            candidates: HashMap::new(),
+        };
+        for candidate in units.in_round(round).unwrap_or_default() {
+            warmer.add_candidate(candidate.as_ref(), units);
+        }
+        warmer
+    }
+
+    /// The round whose candidates get voted on.
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    fn add_candidate<S: UnitStorage<U>>(&mut self, candidate: &U, units: &Units<U, S>) {
+        let rules = VotingRules::new(&self.config, candidate.parents().size());
+        let mut voting = CandidateElection::without_votes(candidate, rules);
+        for round in self.round + 1..self.round.saturating_add(MIN_START_OFFSET) {
+            for voter in units.in_round(round).unwrap_or_default() {
+                // Cannot decide, but a malformed voter aborts, leave such candidates to the election.
+                if voting.vote(voter.as_ref()).is_err() {
+                    return;
+                }
+            }
+        }
+        self.candidates.insert(candidate.hash(), voting);
+    }
+
+    /// Account for a unit, which has to be already added to the units, after all its parents.
+    pub fn add_unit<S: UnitStorage<U>>(&mut self, unit: &U, units: &Units<U, S>) {
+        match unit.round().checked_sub(self.round) {
+            Some(0) => self.add_candidate(unit, units),
+            Some(relative_round) if relative_round < MIN_START_OFFSET => {
+                self.candidates
+                    .retain(|_, voting| voting.vote(unit).is_ok());
+            }
+            _ => (),
+        }
+    }
 }
 
 /// An election result.
 pub enum ElectionResult<U: UnitWithParents> {
     /// The election is not done yet.
     Pending(RoundElection<U>),
-    /// The head has been elected.
+    /// The head has been elected after adding voters to a pending election.
     Elected(HashFor<U>),
+    /// The head has been elected as soon as the election started, because the units already sufficed,
+    /// e.g. when catching up.
+    ElectedImmediately(HashFor<U>),
+    /// The election is not done yet, even though voters from more rounds than the stall budget allows have been added.
+    /// The election can be continued just like a pending one.
+    Stalled {
+        election: RoundElection<U>,
+        rounds_waited: Round,
+    },
+    /// All the candidates have been eliminated, so the round has no head.
+    /// This cannot happen if less than a third of the nodes are faulty.
+    NoHead,
+    /// A voter could not vote, so the election cannot continue. Means a malformed unit got into the units.
+    Aborted(VoteError<U::Hasher>),
+}
+
+/// The votes of the parents of the unit that decided a candidate election.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VoteTally {
+    pub votes_for: Weight,
+    pub votes_against: Weight,
+    /// The weight of votes required to decide.
+    pub threshold: Weight,
+    /// The round of the deciding unit, relative to the round of the candidate.
+    pub relative_round: Round,
+}
+
+/// Something notable that happened during an election.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ElectionEvent<H: Hasher> {
+    /// The candidate got eliminated, with the tally showing how close it came to getting elected.
+    CandidateEliminated {
+        hash: H::Hash,
+        round: Round,
+        tally: VoteTally,
+    },
+    /// Voting on the candidate started, it will be either elected or eliminated eventually.
+    CandidateVoting { hash: H::Hash, round: Round },
+    /// The creator produced more than one candidate, i.e. it equivocated.
+    EquivocatingCreator { creator: NodeIndex, round: Round },
+    /// The head got elected after eliminating the given number of candidates.
+    HeadElected {
+        hash: H::Hash,
+        round: Round,
+        eliminated: usize,
+        tally: VoteTally,
+        /// The votes of the parents of the deciding unit by their creators, i.e. the ones that decided the election.
+        votes: NodeMap<bool>,
+    },
+}
+
+/// An election result together with the events that led to it.
+pub type ElectionResultWithEvents<U> = (ElectionResult<U>, Vec<ElectionEvent<<U as Unit>::Hasher>>);
+
+/// An impossibility in the votes cast on the candidates of a round, pointing at a bug or at corrupted election state.
+#[cfg(feature = "vote_diagnostics")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VoteAnomaly<H: Hasher> {
+    /// The voter voted differently than its parents determine, i.e. for a candidate that is not its parent
+    /// or against one that is, or against the unanimous votes of its parents.
+    InconsistentWithParents {
+        voter: H::Hash,
+        candidate: H::Hash,
+        vote: bool,
+    },
+    /// The voter voted for more than one candidate of the same creator, even though it can only
+    /// have one of them as a parent.
+    ForkedVotes {
+        voter: H::Hash,
+        creator: NodeIndex,
+        candidates: Vec<H::Hash>,
+    },
+}
+
+/// The votes each unit above a round cast on the candidates of the round, in the order of the candidates.
+#[cfg(feature = "vote_diagnostics")]
+pub type VoteVectors<H> = HashMap<<H as Hasher>::Hash, Vec<(<H as Hasher>::Hash, bool)>>;
+
+/// Reasons why a unit cannot vote in an election.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum VoteError<H: Hasher> {
+    /// The voter has parents of lower weight than every unit should.
+    #[error("Unit {voter:?} has parents of weight {}, but at least {} are required to vote.", .parents.0, .required.0)]
+    InsufficientParentVotes {
+        voter: H::Hash,
+        parents: Weight,
+        required: Weight,
+    },
+}
+
+/// Reasons why an election for a round cannot be started.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum ElectionStartError {
+    /// We need units of a higher round to finalize the candidate list.
+    #[error("Not enough rounds to start the election, have {have}, need {need}.")]
+    NotEnoughRounds { have: Round, need: Round },
+    /// There are no units at the round of the election.
+    #[error("No candidates for the election.")]
+    NoCandidates,
+}
+
+/// The complete state of a pending round election, allowing to resume it later.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ElectionSnapshot<H: Hasher> {
+    round: Round,
+    candidate_hash: H::Hash,
+    candidate_creator: NodeIndex,
+    // Remaining candidates, in reverse order.
+    candidates: Vec<H::Hash>,
+    // Sorted by hash, to make the encoding deterministic.
+    votes: Vec<(H::Hash, bool)>,
+    eliminated: u64,
+}
+
+/// Reasons why an election cannot be resumed from a snapshot.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// A candidate from the snapshot is not among the units.
+    #[error("Candidate from the snapshot missing.")]
+    MissingCandidate,
+    /// The candidate in the units does not match the round or creator from the snapshot.
+    #[error("Candidate inconsistent with the snapshot.")]
+    InconsistentCandidate,
 }
 
 impl<U: UnitWithParents> RoundElection<U> {
     /// Create a new round election. It might immediately be decided, so this might return an election result rather than a pending election.
-    /// Returns an error when it's too early to finalize the candidate list, i.e. we are not at least 3 rounds ahead of the election round.
+    /// Returns an error when it's too early to finalize the candidate list, i.e. we are not at least 3 rounds ahead of the election round,
+    /// or when there are no units in the election round at all.
+    /// The candidates are tried one by one, in the order given by the configured `CandidateOrder`, and the first one
+    /// that does not get eliminated becomes the head, so the order decides between candidates that could all be elected.
     ///
     /// Note: it is crucial that units are added to `Units` only when all their parents are there, otherwise this might panic.
-    pub fn for_round(round: Round, units: &Units<U>) -> Result<ElectionResult<U>, ()> {
+    pub fn for_round<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Result<ElectionResult<U>, ElectionStartError> {
+        Self::for_round_with_events(round, units, config).map(|(result, _)| result)
+    }
+
+    /// Like `for_round`, but additionally returns all the events that happened during the election so far.
+    pub fn for_round_with_events<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Result<ElectionResultWithEvents<U>, ElectionStartError> {
+        Self::start(round, units, config, None)
+    }
+
+    /// Like `for_round`, but reuses the votes computed by the warmer if it warmed this round.
+    /// The warmer is only used up if the election starts, the result is the same as without it.
+    pub fn for_round_warmed<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+        warmer: &mut VoteWarmer<U>,
+    ) -> Result<ElectionResult<U>, ElectionStartError> {
+        let warmer = match warmer.round == round {
+            true => Some(warmer),
+            false => None,
+        };
+        Self::start(round, units, config, warmer).map(|(result, _)| result)
+    }
+
+    fn start<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+        warmer: Option<&mut VoteWarmer<U>>,
+    ) -> Result<ElectionResultWithEvents<U>, ElectionStartError> {
         // If we don't yet have a unit of round + 3 we might not know about the winning candidate, so we cannot start the election.
-        if units.highest_round() < round + 3 {
-            return Err(());
+        match round.checked_add(config.start_offset) {
+            Some(need) if units.highest_round() >= need => (),
+            need => {
+                return Err(ElectionStartError::NotEnoughRounds {
+                    have: units.highest_round(),
+                    need: need.unwrap_or(Round::MAX),
+                })
+            }
         }
         // We might be missing units from this round, but any unit that is not an ancestor of an arbitrary unit from round + 3
         // will always eventually be eliminated in the voting, so we can freely skip it.
-        let mut candidates: Vec<_> = units
+        let round_units = units
+            .in_round(round)
+            .ok_or(ElectionStartError::NoCandidates)?;
+        let n_members = round_units
+            .first()
+            .ok_or(ElectionStartError::NoCandidates)?
+            .parents()
+            .size();
+        let rules = VotingRules::new(config, n_members);
+        // We will be `pop`ing the candidates from the back.
+        let candidates: Vec<_> = Self::ordered_candidates(&round_units, config)
+            .into_iter()
+            .rev()
+            .collect();
+        let mut events: Vec<_> = Self::equivocators(&round_units)
+            .into_iter()
+            .map(|creator| ElectionEvent::EquivocatingCreator { creator, round })
+            .collect();
+        rules.report_election_started();
+        debug!(target: LOG_TARGET, "election started: round={} candidates={}", round, candidates.len());
+        if let Some((head, tally, votes)) = Self::fast_path(round, &candidates, units, &rules) {
+            Self::log_decision("head elected", &head, round, units, &tally);
+            rules.report_head_elected(tally.relative_round);
+            events.push(ElectionEvent::CandidateVoting { hash: head, round });
+            events.push(ElectionEvent::HeadElected {
+                hash: head,
+                round,
+                eliminated: 0,
+                tally,
+                votes,
+            });
+            return Ok((ElectionResult::ElectedImmediately(head), events));
+        }
+        let warmed = warmer
+            .map(|warmer| std::mem::take(&mut warmer.candidates))
+            .unwrap_or_default();
+        let candidates = RemainingCandidates {
+            hashes: candidates,
+            warmed,
+        };
+        let result = match Self::start_next_candidate(candidates, 0, units, rules, &mut events) {
+            ElectionResult::Elected(head) => ElectionResult::ElectedImmediately(head),
+            result => result,
+        };
+        Ok((result, events))
+    }
+
+    /// In the ideal case, when every node created exactly one unit in the next round and all these units have all
+    /// the units of this round as parents, the first candidate gets every vote, so the first unit of a round with
+    /// a common vote for the candidate elects it. If we have such a unit, returns the candidate, the tally and
+    /// the votes the general procedure would decide with, without computing all the votes.
+    /// Only applies to unweighted elections without abstentions or a custom quorum, in which every voter up to
+    /// the deciding round has enough parents to vote and the deciding votes reach the threshold, so that
+    /// the general procedure cannot abort or decide otherwise.
+    fn fast_path<S: UnitStorage<U>>(
+        round: Round,
+        reversed_candidates: &[HashFor<U>],
+        units: &Units<U, S>,
+        rules: &VotingRules,
+    ) -> Option<(HashFor<U>, VoteTally, NodeMap<bool>)> {
+        if rules.weights.is_some()
+            || rules.abstaining_equivocators.is_some()
+            || rules.quorum.is_some()
+        {
+            return None;
+        }
+        // Without units of the first round with a common vote for the candidate nothing can be decided yet.
+        let relative_round = (3..=units.highest_round() - round)
+            .find(|relative_round| rules.common_vote.vote(*relative_round))?;
+        let candidate = units.get(reversed_candidates.last()?)?;
+        let voters = units.in_round(round.checked_add(1)?)?;
+        let n_members = candidate.parents().size();
+        let mut creators = NodeSubset::with_size(n_members);
+        for voter in &voters {
+            if creators.contains(voter.creator())
+                || voter.parents().item_count() != n_members.0
+                || voter.parents().get(candidate.creator()) != Some(&candidate.hash())
+            {
+                return None;
+            }
+            creators.insert(voter.creator());
+        }
+        if voters.len() != n_members.0 {
+            return None;
+        }
+        // The general procedure aborts on any voter with too few parents, so we leave such cases to it.
+        for voter_round in round + 2..=round + relative_round {
+            let voters = units.in_round(voter_round)?;
+            if voters.iter().any(|voter| {
+                Weight::from(NodeCount(voter.parents().item_count())) < rules.min_parents
+            }) {
+                return None;
+            }
+        }
+        // All the later units vote for the candidate too, so the first unit of that round elects it,
+        // as long as its parents are enough to decide.
+        let decider = units
+            .hashes_in_round(round + relative_round)?
+            .first()
+            .and_then(|decider| units.get(decider))?;
+        let votes_for = NodeCount(decider.parents().item_count()).into();
+        if votes_for < rules.threshold {
+            return None;
+        }
+        let tally = VoteTally {
+            votes_for,
+            votes_against: Weight(0),
+            threshold: rules.threshold,
+            relative_round,
+        };
+        let mut votes = NodeMap::with_size(n_members);
+        for (creator, _) in decider.parents().iter() {
+            votes.insert(creator, true);
+        }
+        Some((candidate.hash(), tally, votes))
+    }
+
+    /// The candidates of the round in the order in which an election would try them, given the units available now.
+    /// Does not run the election, so it might never get to try the later candidates.
+    pub fn candidate_order<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Vec<HashFor<U>> {
+        units
             .in_round(round)
-            .expect("units come in order, so we definitely have units from this round")
+            .map(|round_units| Self::ordered_candidates(&round_units, config))
+            .unwrap_or_default()
+    }
+
+    fn ordered_candidates(round_units: &[Cow<U>], config: &ElectionConfig) -> Vec<HashFor<U>> {
+        // Candidates with equal priorities are sorted by their hashes.
+        let mut candidates: Vec<_> = round_units
             .iter()
-            .map(|candidate| candidate.hash())
+            .map(|candidate| {
+                let hash = candidate.hash();
+                let priority = config
+                    .candidate_order
+                    .priority(candidate.creator(), hash.as_ref());
+                (priority, hash)
+            })
             .collect();
         candidates.sort();
-        // We will be `pop`ing the candidates from the back.
-        candidates.reverse();
-        let candidate = units
-            .get(&candidates.pop().expect("there is a candidate"))
-            .expect("we have all the units we work with");
-        Ok(Self::handle_candidate_election_result(
-            CandidateElection::for_candidate(candidate, units),
+        candidates.into_iter().map(|(_, hash)| hash).collect()
+    }
+
+    // Creators with more than one unit among the given ones, in ascending order.
+    fn equivocators(round_units: &[Cow<U>]) -> BTreeSet<NodeIndex> {
+        let mut creators = HashSet::new();
+        round_units
+            .iter()
+            .map(|unit| unit.creator())
+            .filter(|creator| !creators.insert(*creator))
+            .collect()
+    }
+
+    fn start_next_candidate<S: UnitStorage<U>>(
+        mut candidates: RemainingCandidates<U>,
+        eliminated: usize,
+        units: &Units<U, S>,
+        rules: VotingRules,
+        events: &mut Vec<ElectionEvent<U::Hasher>>,
+    ) -> ElectionResult<U> {
+        let candidate = match candidates.hashes.pop() {
+            Some(candidate) => units
+                .get(&candidate)
+                .expect("we have all the units we work with"),
+            None => return ElectionResult::NoHead,
+        };
+        events.push(ElectionEvent::CandidateVoting {
+            hash: candidate.hash(),
+            round: candidate.round(),
+        });
+        let voting = match candidates.warmed.remove(&candidate.hash()) {
+            Some(mut voting) => {
+                voting.rules = rules.clone();
+                voting.compute_votes(units)
+            }
+            None => CandidateElection::for_candidate(candidate.as_ref(), units, rules.clone()),
+        };
+        Self::handle_candidate_election_result(
+            voting,
             candidates,
+            eliminated,
+            candidate.round(),
             units,
-        ))
+            rules,
+            events,
+        )
+    }
+
+    // Decisions are logged as fixed keys with values, so that they can be extracted from the logs.
+    fn log_decision<S: UnitStorage<U>>(
+        decision: &str,
+        candidate: &HashFor<U>,
+        round: Round,
+        units: &Units<U, S>,
+        tally: &VoteTally,
+    ) {
+        if !log_enabled!(target: LOG_TARGET, Level::Debug) {
+            return;
+        }
+        let creator = match units.get(candidate) {
+            Some(candidate) => candidate.creator().0.to_string(),
+            None => "unknown".to_string(),
+        };
+        debug!(
+            target: LOG_TARGET,
+            "{}: round={} creator={} relative_round={} votes_for={} votes_against={} threshold={}",
+            decision,
+            round,
+            creator,
+            tally.relative_round,
+            tally.votes_for.0,
+            tally.votes_against.0,
+            tally.threshold.0,
+        );
+        trace!(target: LOG_TARGET, "{}: round={} creator={} hash={:?}", decision, round, creator, candidate);
     }
 
-    fn handle_candidate_election_result(
+    fn handle_candidate_election_result<S: UnitStorage<U>>(
         result: Result<CandidateElection<U>, CandidateOutcome<U::Hasher>>,
-        mut candidates: Vec<HashFor<U>>,
-        units: &Units<U>,
+        candidates: RemainingCandidates<U>,
+        eliminated: usize,
+        round: Round,
+        units: &Units<U, S>,
+        rules: VotingRules,
+        events: &mut Vec<ElectionEvent<U::Hasher>>,
     ) -> ElectionResult<U> {
         use CandidateOutcome::*;
         use ElectionResult::*;
         match result {
-            // Wait for more voters.
-            Ok(voting) => Pending(RoundElection { candidates, voting }),
-            // Pick the next candidate and keep trying.
-            Err(Eliminate) => {
-                let candidate = units
-                    .get(&candidates.pop().expect("there is a candidate"))
-                    .expect("we have all the units we work with");
-                Self::handle_candidate_election_result(
-                    CandidateElection::for_candidate(candidate, units),
+            // Wait for more voters, reporting if we have been waiting for too long.
+            Ok(voting) => {
+                let election = RoundElection {
                     candidates,
-                    units,
-                )
+                    voting,
+                    eliminated,
+                };
+                let rounds_waited = units.highest_round().saturating_sub(round);
+                match rules.stall_budget {
+                    Some(budget) if rounds_waited > budget => Stalled {
+                        election,
+                        rounds_waited,
+                    },
+                    _ => Pending(election),
+                }
+            }
+            // Pick the next candidate and keep trying.
+            Err(Eliminate(hash, tally)) => {
+                Self::log_decision("candidate eliminated", &hash, round, units, &tally);
+                rules.report_candidate_eliminated();
+                events.push(ElectionEvent::CandidateEliminated { hash, round, tally });
+                Self::start_next_candidate(candidates, eliminated + 1, units, rules, events)
+            }
+            // A malformed voter, we cannot safely continue with any candidate.
+            Err(Abort(error)) => {
+                warn!(target: LOG_TARGET, "Aborting the election for round {}: {}", round, error);
+                Aborted(error)
             }
             // Yay, we picked a head.
-            Err(ElectionDone(head)) => Elected(head),
+            Err(ElectionDone(head, tally, votes)) => {
+                Self::log_decision("head elected", &head, round, units, &tally);
+                rules.report_head_elected(tally.relative_round);
+                events.push(ElectionEvent::HeadElected {
+                    hash: head,
+                    round,
+                    eliminated,
+                    tally,
+                    votes,
+                });
+                Elected(head)
+            }
+        }
+    }
+
+    /// Add many voters to the election, in order, stopping as soon as it is decided, so the remaining voters are ignored.
+    /// All the voters have to be present in `units`, otherwise this might panic.
+    pub fn add_voters<S: UnitStorage<U>>(
+        self,
+        voters: impl IntoIterator<Item = HashFor<U>>,
+        units: &Units<U, S>,
+    ) -> ElectionResult<U> {
+        use ElectionResult::*;
+        let mut result = Pending(self);
+        for voter in voters {
+            let voter = units.get(&voter).expect("all voters are present");
+            result = match result {
+                Pending(election) | Stalled { election, .. } => election.add_voter(&voter, units),
+                decided => return decided,
+            };
+            if matches!(
+                result,
+                Elected(_) | ElectedImmediately(_) | NoHead | Aborted(_)
+            ) {
+                break;
+            }
         }
+        result
+    }
+
+    /// Run the elections of consecutive rounds starting from the given one, as long as they get decided with the units
+    /// available, returning the elected heads in ascending order of rounds. Stops at the first round that cannot be decided yet,
+    /// or that has no head.
+    pub fn run_all_ready<S: UnitStorage<U>>(
+        start_round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Vec<(Round, HashFor<U>)> {
+        let mut heads = Vec::new();
+        let mut round = start_round;
+        while let Ok(ElectionResult::Elected(head) | ElectionResult::ElectedImmediately(head)) =
+            Self::for_round(round, units, config)
+        {
+            heads.push((round, head));
+            round = match round.checked_add(1) {
+                Some(round) => round,
+                None => break,
+            };
+        }
+        heads
     }
 
     /// Add a single voter to the election.
     /// Might panic if not all parents were added previously.
-    pub fn add_voter(self, voter: &U, units: &Units<U>) -> ElectionResult<U> {
-        let RoundElection { candidates, voting } = self;
-        Self::handle_candidate_election_result(voting.add_voter(voter), candidates, units)
+    pub fn add_voter<S: UnitStorage<U>>(self, voter: &U, units: &Units<U, S>) -> ElectionResult<U> {
+        self.add_voter_with_events(voter, units).0
+    }
+
+    /// Like `add_voter`, but additionally returns all the events that happened due to adding the voter.
+    pub fn add_voter_with_events<S: UnitStorage<U>>(
+        self,
+        voter: &U,
+        units: &Units<U, S>,
+    ) -> ElectionResultWithEvents<U> {
+        let RoundElection {
+            candidates,
+            voting,
+            eliminated,
+        } = self;
+        let rules = voting.rules.clone();
+        let round = voting.round;
+        let mut events = Vec::new();
+        let result = Self::handle_candidate_election_result(
+            voting.add_voter(voter),
+            candidates,
+            eliminated,
+            round,
+            units,
+            rules,
+            &mut events,
+        );
+        (result, events)
+    }
+
+    /// The candidate currently being voted on.
+    pub fn current_candidate(&self) -> HashFor<U> {
+        self.voting.candidate_hash
+    }
+
+    /// The weights of votes for and against the current candidate among the units of the highest known round.
+    pub fn tally<S: UnitStorage<U>>(&self, units: &Units<U, S>) -> (Weight, Weight) {
+        self.voting.tally(units)
+    }
+
+    /// The state of the election, which can be used to resume it with `from_snapshot`.
+    pub fn snapshot(&self) -> ElectionSnapshot<U::Hasher> {
+        let mut votes: Vec<_> = self
+            .voting
+            .votes
+            .iter()
+            .map(|(hash, vote)| (*hash, *vote))
+            .collect();
+        votes.sort();
+        ElectionSnapshot {
+            round: self.voting.round,
+            candidate_hash: self.voting.candidate_hash,
+            candidate_creator: self.voting.candidate_creator,
+            candidates: self.candidates.hashes.clone(),
+            votes,
+            eliminated: self.eliminated as u64,
+        }
+    }
+
+    /// Resume an election from a snapshot. All the units present when the snapshot was taken
+    /// must be in `units`, any voters added since then are taken into account immediately,
+    /// so this might return a decided election.
+    pub fn from_snapshot<S: UnitStorage<U>>(
+        snapshot: ElectionSnapshot<U::Hasher>,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Result<ElectionResult<U>, SnapshotError> {
+        let ElectionSnapshot {
+            round,
+            candidate_hash,
+            candidate_creator,
+            candidates,
+            votes,
+            eliminated,
+        } = snapshot;
+        let candidate = units
+            .get(&candidate_hash)
+            .ok_or(SnapshotError::MissingCandidate)?;
+        if candidate.round() != round || candidate.creator() != candidate_creator {
+            return Err(SnapshotError::InconsistentCandidate);
+        }
+        for hash in &candidates {
+            match units.get(hash) {
+                Some(unit) if unit.round() == round => (),
+                Some(_) => return Err(SnapshotError::InconsistentCandidate),
+                None => return Err(SnapshotError::MissingCandidate),
+            }
+        }
+        let rules = VotingRules::new(config, candidate.parents().size());
+        let voting = CandidateElection {
+            round,
+            candidate_creator,
+            candidate_hash,
+            votes: votes.into_iter().collect(),
+            rules: rules.clone(),
+            #[cfg(feature = "vote_diagnostics")]
+            exhaustive: false,
+        }
+        .compute_votes(units);
+        Ok(Self::handle_candidate_election_result(
+            voting,
+            RemainingCandidates {
+                hashes: candidates,
+                warmed: HashMap::new(),
+            },
+            eliminated as usize,
+            round,
+            units,
+            rules,
+            &mut Vec::new(),
+        ))
+    }
+
+    /// The votes all the units above the round cast on all the candidates of the round,
+    /// computed without stopping at decisions, for diagnosing elections.
+    #[cfg(feature = "vote_diagnostics")]
+    pub fn vote_vectors<S: UnitStorage<U>>(
+        round: Round,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> VoteVectors<U::Hasher> {
+        let mut vectors = VoteVectors::<U::Hasher>::new();
+        let round_units = match units.in_round(round) {
+            Some(round_units) if !round_units.is_empty() => round_units,
+            _ => return vectors,
+        };
+        let rules = VotingRules::new(config, round_units[0].parents().size());
+        for candidate in Self::ordered_candidates(&round_units, config) {
+            let candidate = units
+                .get(&candidate)
+                .expect("we have all the units we work with");
+            let voting = CandidateElection {
+                round,
+                candidate_creator: candidate.creator(),
+                candidate_hash: candidate.hash(),
+                votes: HashMap::new(),
+                rules: rules.clone(),
+                exhaustive: true,
+            }
+            .compute_votes(units);
+            let voting = match voting {
+                Ok(voting) => voting,
+                Err(_) => unreachable!("exhaustive voting never decides"),
+            };
+            for (voter, vote) in voting.votes {
+                vectors
+                    .entry(voter)
+                    .or_default()
+                    .push((candidate.hash(), vote));
+            }
+        }
+        vectors
+    }
+
+    /// Check the votes cast on the candidates of the round, e.g. as computed by `vote_vectors`, for impossibilities.
+    /// The anomalies are ordered by the rounds of the voters.
+    #[cfg(feature = "vote_diagnostics")]
+    pub fn vote_anomalies<S: UnitStorage<U>>(
+        round: Round,
+        vectors: &VoteVectors<U::Hasher>,
+        units: &Units<U, S>,
+        config: &ElectionConfig,
+    ) -> Vec<VoteAnomaly<U::Hasher>> {
+        let mut voters: Vec<_> = vectors
+            .keys()
+            .filter_map(|voter| units.get(voter))
+            .filter(|voter| voter.round() > round)
+            .collect();
+        voters.sort_by_key(|voter| (voter.round(), voter.hash()));
+        let mut anomalies = Vec::new();
+        for voter in voters {
+            let votes = &vectors[&voter.hash()];
+            let mut voted_for_by_creator = std::collections::BTreeMap::<_, Vec<_>>::new();
+            let rules = VotingRules::new(config, voter.parents().size());
+            for (candidate_hash, vote) in votes {
+                let candidate = match units.get(candidate_hash) {
+                    Some(candidate) => candidate,
+                    None => continue,
+                };
+                let expected = match voter.round() - round {
+                    1 => Some(voter.parents().get(candidate.creator()) == Some(candidate_hash)),
+                    _ => Self::unanimous_vote(voter.parents(), candidate_hash, vectors, &rules),
+                };
+                if expected.map_or(false, |expected| expected != *vote) {
+                    anomalies.push(VoteAnomaly::InconsistentWithParents {
+                        voter: voter.hash(),
+                        candidate: *candidate_hash,
+                        vote: *vote,
+                    });
+                }
+                if *vote && voter.round() == round + 1 {
+                    voted_for_by_creator
+                        .entry(candidate.creator())
+                        .or_default()
+                        .push(*candidate_hash);
+                }
+            }
+            for (creator, candidates) in voted_for_by_creator {
+                if candidates.len() > 1 {
+                    anomalies.push(VoteAnomaly::ForkedVotes {
+                        voter: voter.hash(),
+                        creator,
+                        candidates,
+                    });
+                }
+            }
+        }
+        anomalies
+    }
+
+    // The vote all the parents agree on, if they do, the same way as when voting.
+    #[cfg(feature = "vote_diagnostics")]
+    fn unanimous_vote(
+        parents: &NodeMap<HashFor<U>>,
+        candidate: &HashFor<U>,
+        vectors: &VoteVectors<U::Hasher>,
+        rules: &VotingRules,
+    ) -> Option<bool> {
+        let (mut votes_for, mut votes_against) = (Weight(0), Weight(0));
+        for (creator, parent) in parents.iter() {
+            let vote = vectors
+                .get(parent)?
+                .iter()
+                .find(|(voted_on, _)| voted_on == candidate)?
+                .1;
+            match vote {
+                true => votes_for += rules.weight(creator),
+                false => votes_against += rules.weight(creator),
+            }
+        }
+        match (votes_for, votes_against) {
+            (Weight(0), _) => Some(false),
+            (_, Weight(0)) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// Try electing the head of the round using only the units we have, without keeping a pending election around,
+/// e.g. in tests. Returns the round and the head if it got elected.
+pub fn extend_once<U: UnitWithParents, S: UnitStorage<U>>(
+    units: &Units<U, S>,
+    round: Round,
+    config: &ElectionConfig,
+) -> Option<(Round, HashFor<U>)> {
+    use ElectionResult::*;
+    match RoundElection::for_round(round, units, config).ok()? {
+        Elected(head) | ElectedImmediately(head) => Some((round, head)),
+        Pending(_) | Stalled { .. } | NoHead | Aborted(_) => None,
+    }
+}
+
+/// The best and worst case number of rounds from creating a unit to ordering it, assuming honest nodes.
+/// At best the unit is the head of its round, elected by the first unit with a common vote for it. At worst it gets
+/// ordered with the head of the next round, elected only once two consecutive rounds have a common vote for it.
+/// With a single node every unit is a head. `Round::MAX` means the schedule never lets this happen.
+pub fn finalization_rounds(n: NodeCount, schedule: &dyn CommonVote) -> (Round, Round) {
+    // Votes decide an election at relative round 3 at the earliest.
+    let best = (3..Round::MAX)
+        .find(|relative_round| schedule.vote(*relative_round))
+        .unwrap_or(Round::MAX);
+    if n.0 <= 1 {
+        return (best, best);
     }
+    let worst = (3..Round::MAX)
+        .find(|relative_round| schedule.vote(relative_round - 1) && schedule.vote(*relative_round))
+        .map(|relative_round| relative_round + 1)
+        .unwrap_or(Round::MAX);
+    (best, worst)
 }
 
 #[cfg(test)]
 mod test {
+    use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
+
+    #[cfg(feature = "metrics")]
+    use crate::Metrics;
     use crate::{
         extension::{
-            election::{ElectionResult, RoundElection},
-            units::Units,
+            election::{
+                extend_once, finalization_rounds, ByzantineThreshold, CandidateElection,
+                CandidateOrder, CommonVote, DefaultCommonVote, ElectionConfig, ElectionConfigError,
+                ElectionEvent, ElectionResult, ElectionSnapshot, ElectionStartError,
+                RemainingCandidates, RoundElection, SnapshotError, Threshold, VoteError, VoteTally,
+                VoteWarmer, VotingRules, Weight,
+            },
+            extender::Extender,
+            testing::{
+                dag_with_parents, units_with_unpopular_candidate, AlwaysAgainstCommonVote,
+                AlwaysForCommonVote, UnanimousThreshold,
+            },
+            units::{UnitStorage, Units},
         },
+        quorum::MandatoryNodeQuorum,
+        testing::{capture_logs, gen_config, gen_delay_config},
         units::{
+            full_parent_reconstructed_units_with_hasher_up_to,
             random_full_parent_reconstrusted_units_up_to, random_reconstructed_unit_with_parents,
-            TestingDagUnit, Unit,
+            TestingDagUnit, Unit, UnitWithParents,
         },
-        NodeCount,
+        ConsensusStatus, Hasher, NodeCount, NodeIndex, NodeMap, NodeSubset, Round, StatusMonitor,
+    };
+    use aleph_bft_mock::{
+        Blake2b256Algo, Blake2b512Algo, Hash64, Hasher64, RuntimeHasher, Sha256Algo,
     };
+    use codec::{Decode, Encode};
+    use log::Level;
+
+    struct CreatorOrder;
+
+    impl CandidateOrder for CreatorOrder {
+        fn priority(&self, creator: NodeIndex, _hash: &[u8]) -> u64 {
+            creator.0 as u64
+        }
+    }
+
+    struct CrashThreshold;
+
+    impl Threshold for CrashThreshold {
+        fn supermajority(&self, total: Weight) -> Weight {
+            Weight(total.0 / 2 + 1)
+        }
+    }
 
     #[test]
     fn refuses_to_elect_without_units() {
-        let units = Units::<TestingDagUnit>::new();
-        assert!(RoundElection::for_round(0, &units).is_err());
+        let units = Units::<TestingDagUnit>::new(NodeCount(4));
+        assert!(matches!(
+            RoundElection::for_round(0, &units, &ElectionConfig::default()),
+            Err(ElectionStartError::NotEnoughRounds { have: 0, need: 3 })
+        ));
     }
 
     #[test]
     fn refuses_to_elect_with_insufficient_units() {
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let max_round = 2;
         let session_id = 2137;
         for round_units in
             random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
         {
             for unit in round_units {
-                units.add_unit(unit);
+                units.add_unit(unit).expect("unit is valid");
             }
         }
-        assert!(RoundElection::for_round(0, &units).is_err());
+        assert!(matches!(
+            RoundElection::for_round(0, &units, &ElectionConfig::default()),
+            Err(ElectionStartError::NotEnoughRounds { have: 2, need: 3 })
+        ));
+    }
+
+    #[test]
+    fn respects_start_offset() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 4;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        for unit in dag.iter().take(4).flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let default_offset = ElectionConfig::default()
+            .with_start_offset(3)
+            .expect("offset is safe");
+        assert!(RoundElection::for_round(0, &units, &default_offset).is_ok());
+        assert!(matches!(
+            RoundElection::for_round(1, &units, &default_offset),
+            Err(ElectionStartError::NotEnoughRounds { have: 3, need: 4 })
+        ));
+        let larger_offset = ElectionConfig::default()
+            .with_start_offset(4)
+            .expect("offset is safe");
+        assert!(matches!(
+            RoundElection::for_round(0, &units, &larger_offset),
+            Err(ElectionStartError::NotEnoughRounds { have: 3, need: 4 })
+        ));
+        for unit in &dag[4] {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        assert!(RoundElection::for_round(0, &units, &larger_offset).is_ok());
+        assert!(matches!(
+            ElectionConfig::default().with_start_offset(2),
+            Err(ElectionConfigError::StartOffsetTooSmall(2))
+        ));
     }
 
     #[test]
     fn easy_election() {
-        use ElectionResult::*;
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let max_round = 4;
         let session_id = 2137;
         let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
         for round_units in dag.iter().take(4) {
             for unit in round_units {
-                units.add_unit(unit.clone());
+                units.add_unit(unit.clone()).expect("unit is valid");
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
-        let election = match election {
-            Pending(election) => election,
-            Elected(_) => panic!("elected head without units of round + 4"),
-        };
+        let election = RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds");
+        let election = election.expect_pending("elected head without units of round + 4");
         let last_voter = dag[4].last().expect("created all units").clone();
-        units.add_unit(last_voter.clone());
-        match election.add_voter(&last_voter, &units) {
-            Pending(_) => panic!("failed to elect obvious head"),
-            Elected(head) => {
-                assert_eq!(units.get(&head).expect("we have the head").round(), 0);
-            }
+        units.add_unit(last_voter.clone()).expect("unit is valid");
+        let (result, events) = election.add_voter_with_events(&last_voter, &units);
+        let head = result.expect_elected("failed to elect obvious head");
+        assert_eq!(units.get(&head).expect("we have the head").round(), 0);
+        // Every parent of the deciding unit voted for the head.
+        let votes = match events.last() {
+            Some(ElectionEvent::HeadElected { hash, votes, .. }) if *hash == head => votes,
+            _ => panic!("head election not reported"),
+        };
+        assert_eq!(votes.item_count(), n_members.0);
+        assert!(votes.values().all(|vote| *vote));
+    }
+
+    #[test]
+    fn logs_election_decisions() {
+        let mut units = Units::new(NodeCount(4));
+        let dag = random_full_parent_reconstrusted_units_up_to(4, NodeCount(4), 2137);
+        for unit in dag[..4].iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
         }
+        let election = match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::Pending(election)) => election,
+            _ => panic!("should be pending without units of round 4"),
+        };
+        let last_voter = dag[4].last().expect("created all units").clone();
+        units.add_unit(last_voter.clone()).expect("unit is valid");
+        let (result, records) = capture_logs(|| election.add_voter(&last_voter, &units));
+        let head = match result {
+            ElectionResult::Elected(head) => head,
+            _ => panic!("failed to elect obvious head"),
+        };
+        let creator = units.get(&head).expect("we have the head").creator();
+        let expected = format!(
+            "head elected: round=0 creator={} relative_round=4 votes_for=4 votes_against=0 threshold=3",
+            creator.0
+        );
+        assert!(records.iter().any(|record| record.level == Level::Debug
+            && record.target == "AlephBFT-election"
+            && record.message == expected));
+        // Only trace-level records carry the hash.
+        let expected = format!(
+            "head elected: round=0 creator={} hash={:?}",
+            creator.0, head
+        );
+        assert!(records.iter().any(|record| record.level == Level::Trace
+            && record.target == "AlephBFT-election"
+            && record.message == expected));
+        let hash = format!("{:?}", head);
+        assert!(records
+            .iter()
+            .filter(|record| record.level <= Level::Debug)
+            .all(|record| !record.message.contains(&hash)));
     }
 
     #[test]
     fn immediate_election() {
-        use ElectionResult::*;
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let max_round = 4;
         let session_id = 2137;
         for round_units in
             random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
         {
             for unit in round_units {
-                units.add_unit(unit.clone());
+                units.add_unit(unit.clone()).expect("unit is valid");
             }
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
-        match election {
-            Pending(_) => panic!("should have elected"),
-            Elected(head) => {
-                assert_eq!(units.get(&head).expect("we have the head").round(), 0);
-            }
+        let election = RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds");
+        let head = election.expect_elected_immediately("should have elected immediately");
+        assert_eq!(units.get(&head).expect("we have the head").round(), 0);
+    }
+
+    #[test]
+    fn finalization_rounds_for_schedules() {
+        // The best case is the head getting elected at once, like in `immediate_election`.
+        assert_eq!(
+            finalization_rounds(NodeCount(4), &DefaultCommonVote),
+            (4, 6)
+        );
+        assert_eq!(
+            finalization_rounds(NodeCount(1), &DefaultCommonVote),
+            (4, 4)
+        );
+        assert_eq!(
+            finalization_rounds(NodeCount(4), &AlwaysForCommonVote),
+            (3, 4)
+        );
+        assert_eq!(
+            finalization_rounds(NodeCount(4), &AlwaysAgainstCommonVote),
+            (Round::MAX, Round::MAX)
+        );
+    }
+
+    #[test]
+    fn single_shot_extension() {
+        let config = ElectionConfig::default();
+        let dag = random_full_parent_reconstrusted_units_up_to(4, NodeCount(4), 2137);
+        let mut units = Units::new(NodeCount(4));
+        for unit in dag[..4].iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        // Without units of round 4 there is no head yet, like in `easy_election`.
+        assert_eq!(extend_once(&units, 0, &config), None);
+        units.add_unit(dag[4][3].clone()).expect("unit is valid");
+        let (round, head) = extend_once(&units, 0, &config).expect("obvious head");
+        assert_eq!(round, 0);
+        assert_eq!(units.get(&head).expect("we have the head").round(), 0);
+        // With all the units it is elected at once, like in `immediate_election`.
+        let mut units = Units::new(NodeCount(4));
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
         }
+        assert_eq!(extend_once(&units, 0, &config), Some((0, head)));
+        assert_eq!(extend_once(&units, 1, &config), None);
     }
 
     #[test]
     fn eliminates_unpopular() {
+        let (units, candidate_hashes) = units_with_unpopular_candidate(NodeCount(4), 4, 2137);
+        let (election, events) =
+            RoundElection::for_round_with_events(0, &units, &ElectionConfig::default())
+                .expect("we have enough rounds");
+        let head = election.expect_elected_immediately("should have elected immediately");
+        // This should be the second unit in order, as the first was not popular.
+        assert_eq!(head, candidate_hashes[1]);
+        let tally = events
+            .iter()
+            .find_map(|event| match event {
+                ElectionEvent::CandidateEliminated { hash, tally, .. }
+                    if hash == &candidate_hashes[0] =>
+                {
+                    Some(*tally)
+                }
+                _ => None,
+            })
+            .expect("the unpopular candidate got eliminated");
+        assert!(tally.votes_for < tally.threshold);
+        assert!(tally.votes_against >= tally.threshold);
+    }
+
+    #[test]
+    fn last_head_round_depends_on_common_vote() {
+        assert_eq!(ElectionConfig::default().last_head_round(10), Some(6));
+        let config = ElectionConfig::default().with_common_vote(Arc::new(AlwaysForCommonVote));
+        assert_eq!(config.last_head_round(10), Some(7));
+        assert_eq!(config.last_head_round(2), None);
+        let config = ElectionConfig::default().with_common_vote(Arc::new(AlwaysAgainstCommonVote));
+        assert_eq!(config.last_head_round(10), None);
+    }
+
+    #[test]
+    fn default_common_vote_schedule() {
+        let schedule: Vec<_> = (2..=8).map(|round| DefaultCommonVote.vote(round)).collect();
+        assert_eq!(schedule, vec![true, false, true, true, false, true, false]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "cannot vote on a candidate of round 0")]
+    fn too_old_voter_is_loud_in_debug_builds() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 3;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let election = match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::Pending(election)) => election,
+            _ => panic!("should not have elected yet"),
+        };
+        election.add_voter(&dag[0][1], &units);
+    }
+
+    #[test]
+    fn custom_common_vote_elects_earlier() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 3;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        // The default schedule votes against in relative round 3, so it cannot decide yet.
+        RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+            .expect_pending("elected head without units of round + 4");
+        let head = RoundElection::for_round(
+            0,
+            &units,
+            &ElectionConfig::default().with_common_vote(Arc::new(AlwaysForCommonVote)),
+        )
+        .expect("we have enough rounds")
+        .expect_elected_immediately("should have elected with an always for schedule");
+        assert_eq!(units.get(&head).expect("we have the head").round(), 0);
+    }
+
+    #[test]
+    fn reports_elimination_events() {
+        use ElectionEvent::*;
+        let n_members = NodeCount(4);
+        let (units, candidate_hashes) = units_with_unpopular_candidate(n_members, 4, 2137);
+        let inactive_node = units
+            .get(&candidate_hashes[0])
+            .expect("we have the candidate")
+            .creator();
+        let mut active_votes = NodeMap::with_size(n_members);
+        for node_id in n_members
+            .into_iterator()
+            .filter(|node_id| node_id != &inactive_node)
+        {
+            active_votes.insert(node_id, true);
+        }
+        let (election, events) =
+            RoundElection::for_round_with_events(0, &units, &ElectionConfig::default())
+                .expect("we have enough rounds");
+        assert!(matches!(election, ElectionResult::ElectedImmediately(_)));
+        assert_eq!(
+            events,
+            vec![
+                CandidateVoting {
+                    hash: candidate_hashes[0],
+                    round: 0
+                },
+                CandidateEliminated {
+                    hash: candidate_hashes[0],
+                    round: 0,
+                    tally: VoteTally {
+                        votes_for: Weight(0),
+                        votes_against: Weight(3),
+                        threshold: Weight(3),
+                        relative_round: 3,
+                    },
+                },
+                CandidateVoting {
+                    hash: candidate_hashes[1],
+                    round: 0
+                },
+                HeadElected {
+                    hash: candidate_hashes[1],
+                    round: 0,
+                    eliminated: 1,
+                    tally: VoteTally {
+                        votes_for: Weight(3),
+                        votes_against: Weight(0),
+                        threshold: Weight(3),
+                        relative_round: 4,
+                    },
+                    votes: active_votes,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn reports_elimination_metrics() {
+        let (units, _) = units_with_unpopular_candidate(NodeCount(4), 4, 2137);
+        let metrics = Metrics::register(&prometheus::Registry::new()).expect("registry is empty");
+        let config = ElectionConfig::default().with_metrics(metrics.clone());
+        let (election, events) = RoundElection::for_round_with_events(0, &units, &config)
+            .expect("we have enough rounds");
+        assert!(matches!(election, ElectionResult::ElectedImmediately(_)));
+        let eliminations = events
+            .iter()
+            .filter(|event| matches!(event, ElectionEvent::CandidateEliminated { .. }))
+            .count();
+        assert_eq!(eliminations, 1);
+        assert_eq!(metrics.elections_started(), 1);
+        assert_eq!(metrics.candidates_eliminated(), eliminations as u64);
+        assert_eq!(metrics.heads_elected(), 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn reports_vote_cache_metrics() {
         use ElectionResult::*;
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(4, n_members, 2137);
+        let mut units = Units::new(n_members);
+        for unit in dag[..4].iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let metrics = Metrics::register(&prometheus::Registry::new()).expect("registry is empty");
+        let config = ElectionConfig::default().with_metrics(metrics.clone());
+        let election = match RoundElection::for_round(0, &units, &config) {
+            Ok(Pending(election)) => election,
+            _ => panic!("should be pending without units of round 4"),
+        };
+        // Every voter of the three rounds votes once, nothing is cached yet.
+        assert_eq!(metrics.votes_computed(), 3 * n_members.0 as u64);
+        assert_eq!(metrics.votes_cached(), 0);
+        // Adding a known voter again only hits the cache.
+        let election = match election.add_voter(&dag[3][0], &units) {
+            Pending(election) => election,
+            _ => panic!("should still be pending"),
+        };
+        assert_eq!(metrics.votes_computed(), 3 * n_members.0 as u64);
+        assert_eq!(metrics.votes_cached(), 1);
+        // Resuming recomputes nothing, and the first unit of round 4 decides.
+        let snapshot = election.snapshot();
+        for unit in &dag[4] {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        match RoundElection::from_snapshot(snapshot, &units, &config) {
+            Ok(Elected(_)) => (),
+            _ => panic!("should have elected"),
+        }
+        assert_eq!(metrics.votes_computed(), 3 * n_members.0 as u64 + 1);
+        assert_eq!(metrics.votes_cached(), 1 + 3 * n_members.0 as u64);
+    }
+
+    #[test]
+    fn resumes_from_snapshot() {
+        use ElectionResult::*;
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let max_round = 4;
         let session_id = 2137;
-        for unit in random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)
-            .last()
-            .expect("just created")
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        for round_units in dag.iter().take(4) {
+            for unit in round_units {
+                units.add_unit(unit.clone()).expect("unit is valid");
+            }
+        }
+        let election = RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+            .expect_pending("elected head without units of round + 4");
+        let encoded = election.snapshot().encode();
+        let snapshot = ElectionSnapshot::<Hasher64>::decode(&mut encoded.as_slice())
+            .expect("should decode correctly");
+        assert_eq!(snapshot, election.snapshot());
+        let resumed = RoundElection::from_snapshot(snapshot, &units, &ElectionConfig::default())
+            .expect("snapshot is consistent with units")
+            .expect_pending("elected head without units of round + 4");
+        let last_voter = dag[4].last().expect("created all units").clone();
+        units.add_unit(last_voter.clone()).expect("unit is valid");
+        match (
+            election.add_voter(&last_voter, &units),
+            resumed.add_voter(&last_voter, &units),
+        ) {
+            (Elected(head), Elected(resumed_head)) => assert_eq!(head, resumed_head),
+            _ => panic!("failed to elect obvious head"),
+        }
+    }
+
+    #[test]
+    fn rejects_snapshot_with_missing_candidate() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 3;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
         {
-            units.add_unit(unit.clone());
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
         }
-        let mut candidate_hashes: Vec<_> = units
+        let election = RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+            .expect_pending("elected head without units of round + 4");
+        let snapshot = election.snapshot();
+        let mut other_units = Units::new(n_members);
+        // Units without data would be identical in the same session, so use a different one.
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id + 1)
+        {
+            for unit in round_units {
+                other_units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        assert!(matches!(
+            RoundElection::from_snapshot(snapshot, &other_units, &ElectionConfig::default()),
+            Err(SnapshotError::MissingCandidate)
+        ));
+    }
+
+    #[test]
+    fn reports_current_candidate_and_tally() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 3;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        let election = RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+            .expect_pending("elected head without units of round + 4");
+        let first_candidate = units
             .in_round(0)
             .expect("just added these")
             .iter()
             .map(|candidate| candidate.hash())
+            .min()
+            .expect("there are candidates");
+        assert_eq!(election.current_candidate(), first_candidate);
+        assert_eq!(election.tally(&units), (n_members.into(), Weight(0)));
+    }
+
+    #[test]
+    fn reports_events_for_added_voters() {
+        use ElectionEvent::*;
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 4;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        for round_units in dag.iter().take(4) {
+            for unit in round_units {
+                units.add_unit(unit.clone()).expect("unit is valid");
+            }
+        }
+        let (election, events) =
+            RoundElection::for_round_with_events(0, &units, &ElectionConfig::default())
+                .expect("we have enough rounds");
+        let election = election.expect_pending("elected head without units of round + 4");
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], CandidateVoting { round: 0, .. }));
+        let last_voter = dag[4].last().expect("created all units").clone();
+        units.add_unit(last_voter.clone()).expect("unit is valid");
+        let (election, events) = election.add_voter_with_events(&last_voter, &units);
+        let head = election.expect_elected("failed to elect obvious head");
+        assert_eq!(
+            events,
+            vec![HeadElected {
+                hash: head,
+                round: 0,
+                eliminated: 0,
+                tally: VoteTally {
+                    votes_for: Weight(4),
+                    votes_against: Weight(0),
+                    threshold: Weight(3),
+                    relative_round: 4,
+                },
+                votes: NodeMap::from(vec![Some(true); n_members.0]),
+            }]
+        );
+    }
+
+    #[test]
+    fn elects_heads_in_large_committee() {
+        let n_members = NodeCount(128);
+        let mut units = Units::new(n_members);
+        let max_round = 10;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        for round in 0..=max_round - 4 {
+            let head = RoundElection::for_round(round, &units, &ElectionConfig::default())
+                .expect("we have enough rounds")
+                .expect_elected_immediately("should have elected");
+            assert_eq!(units.get(&head).expect("we have the head").round(), round);
+        }
+    }
+
+    #[test]
+    fn reports_equivocating_candidates() {
+        use ElectionEvent::*;
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 3;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        let fork =
+            random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)[0][0].clone();
+        assert_eq!(fork.creator(), NodeIndex(0));
+        units.add_unit(fork).expect("unit is valid");
+        let (_, events) =
+            RoundElection::for_round_with_events(0, &units, &ElectionConfig::default())
+                .expect("we have enough rounds");
+        let equivocators: Vec<_> = events
+            .into_iter()
+            .filter(|event| matches!(event, EquivocatingCreator { .. }))
+            .collect();
+        assert_eq!(
+            equivocators,
+            vec![EquivocatingCreator {
+                creator: NodeIndex(0),
+                round: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_votes_of_abstaining_equivocators() {
+        let n_members = NodeCount(4);
+        let equivocator = NodeIndex(3);
+        let dag = random_full_parent_reconstrusted_units_up_to(1, n_members, 43);
+        let candidate = dag[0]
+            .iter()
+            .find(|unit| unit.creator() == NodeIndex(0))
+            .expect("every node created a unit")
+            .clone();
+        // The fork does not see the candidate, so it votes against it.
+        let fork_parents: Vec<_> = dag[0]
+            .iter()
+            .filter(|unit| unit.creator() != candidate.creator())
+            .cloned()
+            .collect();
+        let fork = random_reconstructed_unit_with_parents(equivocator, &fork_parents);
+        let mut voter_parents: Vec<_> = dag[1]
+            .iter()
+            .filter(|unit| unit.creator() != equivocator)
+            .cloned()
+            .collect();
+        voter_parents.push(fork.clone());
+        let voter = random_reconstructed_unit_with_parents(NodeIndex(1), &voter_parents);
+        let mut units = Units::new(n_members);
+        for unit in dag.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        units.add_unit(fork).expect("unit is valid");
+        units.add_unit(voter.clone()).expect("unit is valid");
+
+        let mut equivocators = NodeSubset::with_size(n_members);
+        equivocators.insert(equivocator);
+        for (config, expected_votes, total_weight) in [
+            (ElectionConfig::default(), (Weight(3), Weight(1)), Weight(4)),
+            (
+                ElectionConfig::default().with_abstaining_equivocators(equivocators),
+                (Weight(3), Weight(0)),
+                Weight(3),
+            ),
+        ] {
+            let rules = VotingRules::new(&config, n_members);
+            assert_eq!(
+                rules.threshold,
+                ByzantineThreshold.supermajority(total_weight)
+            );
+            let mut election = match CandidateElection::for_candidate(&candidate, &units, rules) {
+                Ok(election) => election,
+                Err(_) => panic!("cannot decide before relative round 3"),
+            };
+            assert_eq!(
+                election.parent_votes(voter.parents()).ok(),
+                Some(expected_votes)
+            );
+        }
+    }
+
+    #[test]
+    fn byzantine_threshold_is_consensus_threshold() {
+        for n_members in 1..=100 {
+            let n_members = NodeCount(n_members);
+            assert_eq!(
+                ByzantineThreshold.supermajority(n_members.into()),
+                n_members.consensus_threshold().into()
+            );
+        }
+    }
+
+    fn add_voters_until_elected(
+        mut election: RoundElection<TestingDagUnit>,
+        voters: Vec<TestingDagUnit>,
+        units: &mut Units<TestingDagUnit>,
+    ) -> Option<Hash64> {
+        for voter in voters {
+            units.add_unit(voter.clone()).expect("unit is valid");
+            match election.add_voter(&voter, units) {
+                ElectionResult::Pending(next_election)
+                | ElectionResult::Stalled {
+                    election: next_election,
+                    ..
+                } => election = next_election,
+                ElectionResult::Elected(head) | ElectionResult::ElectedImmediately(head) => {
+                    return Some(head)
+                }
+                ElectionResult::NoHead | ElectionResult::Aborted(_) => return None,
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn aborts_election_on_voter_with_too_few_parents() {
+        let n_members = NodeCount(4);
+        let full = vec![0, 1, 2, 3];
+        let rounds = dag_with_parents(
+            n_members,
+            vec![
+                vec![full.clone(); 4],
+                vec![full.clone(), full.clone(), full.clone(), vec![0, 1, 2]],
+                vec![full; 4],
+            ],
+        );
+        let malformed = rounds[2][3].hash();
+        let mut units = Units::new(n_members);
+        for unit in rounds.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let config = ElectionConfig::default().with_threshold(Arc::new(UnanimousThreshold));
+        match RoundElection::for_round(0, &units, &config).expect("we have enough rounds") {
+            ElectionResult::Aborted(error) => assert_eq!(
+                error,
+                VoteError::InsufficientParentVotes {
+                    voter: malformed,
+                    parents: Weight(3),
+                    required: Weight(4),
+                }
+            ),
+            ElectionResult::Pending(_)
+            | ElectionResult::Elected(_)
+            | ElectionResult::ElectedImmediately(_)
+            | ElectionResult::Stalled { .. }
+            | ElectionResult::NoHead => panic!("voted with too few parents"),
+        }
+    }
+
+    #[test]
+    fn quorum_delays_election_until_mandatory_node_votes() {
+        let n_members = NodeCount(4);
+        let full = vec![0, 1, 2, 3];
+        let without_first = vec![1, 2, 3];
+        let rounds = dag_with_parents(
+            n_members,
+            vec![
+                vec![full.clone(); 4],
+                vec![without_first.clone(); 4],
+                vec![without_first.clone(); 4],
+                vec![without_first; 4],
+                vec![full; 4],
+            ],
+        );
+        let mut units = Units::new(n_members);
+        for unit in rounds[..5].iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let head = match RoundElection::for_round(0, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+        {
+            ElectionResult::ElectedImmediately(head) => head,
+            _ => panic!("should elect without a quorum"),
+        };
+        let config =
+            ElectionConfig::default().with_quorum(Arc::new(MandatoryNodeQuorum(NodeIndex(0))));
+        let election =
+            match RoundElection::for_round(0, &units, &config).expect("we have enough rounds") {
+                ElectionResult::Pending(election) => election,
+                _ => panic!("should wait for the vote of the mandatory node"),
+            };
+        assert_eq!(
+            add_voters_until_elected(election, rounds[5].clone(), &mut units),
+            Some(head)
+        );
+    }
+
+    #[test]
+    fn keeps_election_rules_of_member_config() {
+        let n_members = NodeCount(4);
+        let monitor = StatusMonitor::new();
+        let config = gen_config(NodeIndex(0), n_members, gen_delay_config())
+            .with_election_config(
+                ElectionConfig::default()
+                    .with_start_offset(5)
+                    .expect("offset is safe"),
+            )
+            .with_status_monitor(monitor);
+        let election_config = ElectionConfig::from(&config);
+        assert_eq!(election_config.start_offset(), 5);
+        assert!(election_config.status_monitor.is_some());
+        assert!(election_config.quorum.is_none());
+        let quorum_config = config.with_quorum(Arc::new(MandatoryNodeQuorum(NodeIndex(0))));
+        assert!(ElectionConfig::from(&quorum_config).quorum.is_some());
+    }
+
+    #[test]
+    fn warmed_election_matches_cold_one() {
+        use ElectionResult::*;
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(4, n_members, 2137);
+        let mut units = Units::new(n_members);
+        let mut warmer = VoteWarmer::new(0, &units, &ElectionConfig::default());
+        for unit in dag[..4].iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+            warmer.add_unit(unit, &units);
+        }
+        let (cold_monitor, warm_monitor) = (StatusMonitor::new(), StatusMonitor::new());
+        let config_with = |monitor: &StatusMonitor| ElectionConfig {
+            status_monitor: Some(monitor.clone()),
+            ..ElectionConfig::default()
+        };
+        let cold = RoundElection::for_round(0, &units, &config_with(&cold_monitor));
+        let warm =
+            RoundElection::for_round_warmed(0, &units, &config_with(&warm_monitor), &mut warmer);
+        for unit in &dag[4] {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let heads: Vec<_> = [cold, warm]
+            .into_iter()
+            .map(|election| match election {
+                Ok(Pending(election)) => match election.add_voter(&dag[4][0], &units) {
+                    Elected(head) => head,
+                    _ => panic!("should have elected"),
+                },
+                _ => panic!("should be pending without units of round 4"),
+            })
+            .collect();
+        assert_eq!(heads[0], heads[1]);
+        // The votes of the two rounds above the candidate were warmed, only the ones of round 3
+        // and of the first unit of round 4, which decides, had to be computed.
+        let hit_rate = |monitor: &StatusMonitor| {
+            ConsensusStatus::gather(monitor)
+                .cache_hit_rate
+                .expect("votes were cast")
+        };
+        assert_eq!(hit_rate(&cold_monitor), 0.0);
+        assert_eq!(hit_rate(&warm_monitor), 8.0 / 13.0);
+    }
+
+    #[test]
+    fn crash_threshold_elects_one_round_earlier() {
+        let n_members = NodeCount(7);
+        let mut rounds = dag_with_parents(
+            n_members,
+            vec![
+                // Only two units vote for the candidate,
+                [
+                    vec![vec![0, 1, 2, 3, 4, 5, 6]; 2],
+                    vec![vec![1, 2, 3, 4, 5, 6]; 5],
+                ]
+                .concat(),
+                // so only the units with exclusively against parents vote against, the rest take the common vote,
+                [
+                    vec![vec![2, 3, 4, 5, 6]; 2],
+                    vec![vec![0, 1, 2, 3, 4, 5, 6]; 5],
+                ]
+                .concat(),
+                // then four units see only for votes, but the rest take the common vote which is against,
+                [
+                    vec![vec![2, 3, 4, 5, 6]; 4],
+                    vec![vec![0, 1, 2, 3, 4, 5, 6]; 3],
+                ]
+                .concat(),
+                // and now every unit sees four votes for and one against.
+                vec![vec![0, 1, 2, 3, 4]; 7],
+                vec![vec![0, 1, 2, 3, 4, 5, 6]; 7],
+            ],
+        );
+        let candidate = rounds[0][0].hash();
+        let last_round = rounds.pop().expect("last round present");
+        let mut units = Units::new(n_members);
+        for unit in rounds.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let crash_config = ElectionConfig::default().with_threshold(Arc::new(CrashThreshold));
+        match RoundElection::for_round(0, &units, &crash_config) {
+            Ok(ElectionResult::ElectedImmediately(head)) => assert_eq!(head, candidate),
+            _ => panic!("should have elected"),
+        }
+        let election = match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::Pending(election)) => election,
+            _ => panic!("should not have elected yet"),
+        };
+        assert_eq!(
+            add_voters_until_elected(election, last_round, &mut units),
+            Some(candidate)
+        );
+    }
+
+    #[test]
+    fn unit_weights_match_unweighted_election() {
+        let n_members = NodeCount(4);
+        let rounds = random_full_parent_reconstrusted_units_up_to(4, n_members, 43);
+        let mut units = Units::new(n_members);
+        for unit in rounds.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let weights = NodeMap::from_hashmap(
+            n_members,
+            n_members
+                .into_iterator()
+                .map(|id| (id, Weight(1)))
+                .collect(),
+        );
+        let weighted_config = ElectionConfig::default().with_weights(weights);
+        match (
+            RoundElection::for_round(0, &units, &ElectionConfig::default()),
+            RoundElection::for_round(0, &units, &weighted_config),
+        ) {
+            (
+                Ok(ElectionResult::ElectedImmediately(head)),
+                Ok(ElectionResult::ElectedImmediately(weighted_head)),
+            ) => {
+                assert_eq!(head, weighted_head)
+            }
+            _ => panic!("should have elected"),
+        }
+    }
+
+    #[test]
+    fn heavy_minority_decides_election() {
+        let n_members = NodeCount(4);
+        // The last node alone holds more than half of the total weight.
+        let weights = NodeMap::from_hashmap(
+            n_members,
+            [1, 1, 1, 6]
+                .into_iter()
+                .enumerate()
+                .map(|(id, weight)| (NodeIndex(id), Weight(weight)))
+                .collect(),
+        );
+        let mut rounds = dag_with_parents(
+            n_members,
+            vec![
+                // Only the heavy node votes for the candidate,
+                [vec![vec![1, 2, 3]; 3], vec![vec![0, 1, 2, 3]; 1]].concat(),
+                // one unit sees only against votes, the rest take the common vote,
+                [vec![vec![0, 1, 2]; 1], vec![vec![0, 1, 2, 3]; 3]].concat(),
+                // the heavy node and one more see only for votes, the rest take the common vote which is against,
+                [vec![vec![0, 1, 2, 3]; 2], vec![vec![1, 2, 3]; 2]].concat(),
+                // and now every unit sees two votes for, but holding a supermajority of the weight.
+                vec![vec![0, 1, 2, 3]; 4],
+                vec![vec![0, 1, 2, 3]; 4],
+            ],
+        );
+        let candidate = rounds[0][0].hash();
+        let last_round = rounds.pop().expect("last round present");
+        let mut units = Units::new(n_members);
+        for unit in rounds.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let weighted_config = ElectionConfig::default().with_weights(weights);
+        match RoundElection::for_round(0, &units, &weighted_config) {
+            Ok(ElectionResult::ElectedImmediately(head)) => assert_eq!(head, candidate),
+            _ => panic!("should have elected"),
+        }
+        let election = match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::Pending(election)) => election,
+            _ => panic!("should not have elected yet"),
+        };
+        assert_eq!(
+            add_voters_until_elected(election, last_round, &mut units),
+            Some(candidate)
+        );
+    }
+
+    #[test]
+    fn reports_stalled_election_after_budget() {
+        let n_members = NodeCount(4);
+        let budget = 5;
+        let mut dag =
+            random_full_parent_reconstrusted_units_up_to(budget + 1, n_members, 43).into_iter();
+        let mut units = Units::new(n_members);
+        for unit in dag.by_ref().take(budget as usize + 1).flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        // All the votes are for the candidate, but the common vote never is, so the election never ends.
+        let config = ElectionConfig::default()
+            .with_common_vote(Arc::new(AlwaysAgainstCommonVote))
+            .with_stall_budget(Some(budget));
+        let mut election =
+            match RoundElection::for_round(0, &units, &config).expect("we have enough rounds") {
+                ElectionResult::Pending(election) => election,
+                _ => panic!("should be pending within budget"),
+            };
+        for voter in dag.next().expect("last round present") {
+            units.add_unit(voter.clone()).expect("unit is valid");
+            election = match election.add_voter(&voter, &units) {
+                ElectionResult::Stalled {
+                    election,
+                    rounds_waited,
+                } => {
+                    assert_eq!(rounds_waited, budget + 1);
+                    election
+                }
+                _ => panic!("should be stalled after budget"),
+            };
+        }
+        match RoundElection::for_round(0, &units, &config.with_stall_budget(None))
+            .expect("we have enough rounds")
+        {
+            ElectionResult::Pending(_) => (),
+            _ => panic!("should be pending without a budget"),
+        }
+    }
+
+    #[test]
+    fn refuses_to_elect_in_missing_round() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        // There is a gap at the election round.
+        for (round, round_units) in random_full_parent_reconstrusted_units_up_to(6, n_members, 43)
+            .into_iter()
+            .enumerate()
+        {
+            if round == 2 {
+                continue;
+            }
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        assert!(matches!(
+            RoundElection::for_round(2, &units, &ElectionConfig::default()),
+            Err(ElectionStartError::NoCandidates)
+        ));
+    }
+
+    #[test]
+    fn reports_no_head_when_all_candidates_eliminated() {
+        let n_members = NodeCount(4);
+        let rounds = dag_with_parents(
+            n_members,
+            vec![
+                // Every candidate is missing one vote,
+                vec![vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]],
+                // so all the units take the common vote, which is against,
+                vec![vec![0, 1, 2, 3]; 4],
+                // and then unanimously vote against.
+                vec![vec![0, 1, 2, 3]; 4],
+            ],
+        );
+        let mut units = Units::new(n_members);
+        for unit in rounds.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let config = ElectionConfig::default().with_common_vote(Arc::new(AlwaysAgainstCommonVote));
+        let (result, events) = RoundElection::for_round_with_events(0, &units, &config)
+            .expect("we have enough rounds");
+        assert!(matches!(result, ElectionResult::NoHead));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, ElectionEvent::CandidateEliminated { .. }))
+                .count(),
+            n_members.0
+        );
+    }
+
+    #[test]
+    fn candidate_order_decides_head() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        for round_units in random_full_parent_reconstrusted_units_up_to(4, n_members, 43) {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        let candidates = units.in_round(0).expect("just added");
+        let lowest_hash = candidates
+            .iter()
+            .map(|candidate| candidate.hash())
+            .min()
+            .expect("there are candidates");
+        let first_creator = candidates
+            .iter()
+            .find(|candidate| candidate.creator() == NodeIndex(0))
+            .expect("there is a unit of the first creator")
+            .hash();
+        match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::ElectedImmediately(head)) => assert_eq!(head, lowest_hash),
+            _ => panic!("should have elected"),
+        }
+        let config = ElectionConfig::default().with_candidate_order(Arc::new(CreatorOrder));
+        match RoundElection::for_round(0, &units, &config) {
+            Ok(ElectionResult::ElectedImmediately(head)) => assert_eq!(head, first_creator),
+            _ => panic!("should have elected"),
+        }
+    }
+
+    #[test]
+    fn fast_path_defers_to_general_election_with_custom_threshold() {
+        use ElectionResult::*;
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(1, n_members, 43);
+        let mut units = Units::new(n_members);
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        // Enough parents to be a valid unit, but not to vote under a unanimous threshold.
+        let under_parented =
+            random_reconstructed_unit_with_parents(NodeIndex(0), &dag[1][..3].to_vec());
+        let under_parented_hash = under_parented.hash();
+        units.add_unit(under_parented).expect("unit is valid");
+        let mut previous = vec![units
+            .get(&under_parented_hash)
+            .expect("just added")
+            .into_owned()];
+        previous.extend(
+            n_members
+                .into_iterator()
+                .skip(1)
+                .map(|creator| random_reconstructed_unit_with_parents(creator, &dag[1])),
+        );
+        for unit in &previous[1..] {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        for _ in 3..=4 {
+            let current: Vec<_> = n_members
+                .into_iterator()
+                .map(|creator| random_reconstructed_unit_with_parents(creator, &previous))
+                .collect();
+            for unit in &current {
+                units.add_unit(unit.clone()).expect("unit is valid");
+            }
+            previous = current;
+        }
+        let config = ElectionConfig::default().with_threshold(Arc::new(UnanimousThreshold));
+        let candidates: Vec<_> = RoundElection::candidate_order(0, &units, &config)
+            .into_iter()
+            .rev()
             .collect();
-        candidate_hashes.sort();
-        let inactive_node = units
-            .get(&candidate_hashes[0])
-            .expect("we just got it")
-            .creator();
-        for round in 1..=max_round {
-            let parents: Vec<TestingDagUnit> = units
-                .in_round(round - 1)
-                .expect("created in order")
+        let rules = VotingRules::new(&config, n_members);
+        assert!(RoundElection::fast_path(0, &candidates, &units, &rules).is_none());
+        match RoundElection::for_round(0, &units, &config) {
+            Ok(Aborted(VoteError::InsufficientParentVotes { voter, .. })) => {
+                assert_eq!(voter, under_parented_hash)
+            }
+            Ok(Pending(_) | Elected(_) | ElectedImmediately(_) | Stalled { .. } | NoHead)
+            | Err(_) => panic!("should have aborted on the under-parented voter"),
+        }
+        // The default threshold accepts the voter, so the fast path applies as before.
+        let config = ElectionConfig::default();
+        let rules = VotingRules::new(&config, n_members);
+        assert!(RoundElection::fast_path(0, &candidates, &units, &rules).is_some());
+    }
+
+    #[test]
+    fn fast_path_matches_general_election() {
+        let n_members = NodeCount(7);
+        let dag = random_full_parent_reconstrusted_units_up_to(6, n_members, 43);
+        let mut units = Units::new(n_members);
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let config = ElectionConfig::default();
+        for round in 0..=2 {
+            let (result, events) = RoundElection::for_round_with_events(round, &units, &config)
+                .expect("enough rounds");
+            let fast_head = match result {
+                ElectionResult::ElectedImmediately(head) => head,
+                _ => panic!("should have elected immediately"),
+            };
+            let candidates: Vec<_> = RoundElection::candidate_order(round, &units, &config)
                 .into_iter()
-                .filter(|unit| unit.creator() != inactive_node)
-                .cloned()
+                .rev()
                 .collect();
-            for creator in n_members
-                .into_iterator()
-                .filter(|node_id| node_id != &inactive_node)
-            {
-                units.add_unit(random_reconstructed_unit_with_parents(creator, &parents));
+            let rules = VotingRules::new(&config, n_members);
+            assert!(RoundElection::fast_path(round, &candidates, &units, &rules).is_some());
+            let mut general_events = Vec::new();
+            let general_head = match RoundElection::start_next_candidate(
+                RemainingCandidates {
+                    hashes: candidates,
+                    warmed: HashMap::new(),
+                },
+                0,
+                &units,
+                rules,
+                &mut general_events,
+            ) {
+                ElectionResult::Elected(head) => head,
+                _ => panic!("should have elected"),
+            };
+            assert_eq!(fast_head, general_head);
+            assert_eq!(events, general_events);
+        }
+        // When units skip parents the fast path does not apply, so the general procedure is used.
+        let mut sparse_units = Units::new(n_members);
+        for unit in dag[0].iter() {
+            sparse_units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let mut previous: Vec<_> = dag[0].clone();
+        for round_units in &dag[1..] {
+            let current: Vec<_> = round_units
+                .iter()
+                .map(|unit| {
+                    let parents: Vec<_> = previous
+                        .iter()
+                        .filter(|parent| {
+                            parent.creator() != NodeIndex(6) || unit.creator() == NodeIndex(6)
+                        })
+                        .cloned()
+                        .collect();
+                    random_reconstructed_unit_with_parents(unit.creator(), &parents)
+                })
+                .collect();
+            for unit in &current {
+                sparse_units.add_unit(unit.clone()).expect("unit is valid");
             }
+            previous = current;
+        }
+        let candidates: Vec<_> = RoundElection::candidate_order(0, &sparse_units, &config)
+            .into_iter()
+            .rev()
+            .collect();
+        let rules = VotingRules::new(&config, n_members);
+        assert!(RoundElection::fast_path(0, &candidates, &sparse_units, &rules).is_none());
+    }
+
+    #[test]
+    fn exposed_candidate_order_matches_pop_order() {
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(3, n_members, 43);
+        let mut units = Units::new(n_members);
+        for unit in dag.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
         }
-        let election = RoundElection::for_round(0, &units).expect("we have enough rounds");
-        match election {
-            Pending(_) => panic!("should have elected"),
-            Elected(head) => {
-                // This should be the second unit in order, as the first was not popular.
-                assert_eq!(head, candidate_hashes[1]);
+        let creator_order = ElectionConfig::default().with_candidate_order(Arc::new(CreatorOrder));
+        for config in [ElectionConfig::default(), creator_order] {
+            let order = RoundElection::candidate_order(0, &units, &config);
+            assert_eq!(order.len(), n_members.0);
+            let mut election = match RoundElection::for_round(0, &units, &config) {
+                Ok(ElectionResult::Pending(election)) => election,
+                _ => panic!("should not have elected yet"),
+            };
+            let mut reversed_order = order.clone();
+            reversed_order.reverse();
+            let mut stored = election.candidates.hashes.clone();
+            stored.push(election.current_candidate());
+            assert_eq!(stored, reversed_order);
+            let mut popped = vec![election.current_candidate()];
+            while let Some(candidate) = election.candidates.hashes.pop() {
+                popped.push(candidate);
             }
+            assert_eq!(popped, order);
+        }
+        assert!(RoundElection::candidate_order(4, &units, &ElectionConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn adding_voters_stops_after_decision() {
+        let n_members = NodeCount(4);
+        let mut dag = random_full_parent_reconstrusted_units_up_to(4, n_members, 43);
+        let last_round = dag.pop().expect("last round present");
+        let mut units = Units::new(n_members);
+        for unit in dag.into_iter().flatten() {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let election = match RoundElection::for_round(0, &units, &ElectionConfig::default()) {
+            Ok(ElectionResult::Pending(election)) => election,
+            _ => panic!("should not have elected yet"),
+        };
+        let voters: Vec<_> = last_round.iter().map(|unit| unit.hash()).collect();
+        for unit in last_round {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let mut processed = 0;
+        let result = election.add_voters(voters.into_iter().inspect(|_| processed += 1), &units);
+        assert!(matches!(result, ElectionResult::Elected(_)));
+        // The first voter already sees enough votes to decide.
+        assert_eq!(processed, 1);
+    }
+
+    #[test]
+    fn runs_all_ready_elections_at_once() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 30;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut units = Units::new(n_members);
+        let mut extender = Extender::new(n_members);
+        let mut incremental = Vec::new();
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+            for batch in extender.add_unit(unit.clone()) {
+                let head = batch.last().expect("batches are not empty");
+                incremental.push((head.round(), head.hash()));
+            }
+        }
+        let config = ElectionConfig::default();
+        let heads = RoundElection::run_all_ready(0, &units, &config);
+        assert!(heads.len() > 20);
+        assert_eq!(heads, incremental);
+        assert_eq!(
+            RoundElection::run_all_ready(5, &units, &config),
+            incremental[5..]
+        );
+        // The last rounds are still pending.
+        assert!(RoundElection::run_all_ready(max_round, &units, &config).is_empty());
+    }
+
+    #[cfg(feature = "vote_diagnostics")]
+    #[test]
+    fn reports_vote_anomalies() {
+        use crate::extension::election::VoteAnomaly;
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(5, n_members, 2137);
+        let mut units = Units::new(n_members);
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        // Fewer parents, so that the fork surely differs from the original unit.
+        let fork = random_reconstructed_unit_with_parents(NodeIndex(1), &dag[0][..3].to_vec());
+        units.add_unit(fork.clone()).expect("unit is valid");
+        let config = ElectionConfig::default();
+        let vectors = RoundElection::vote_vectors(1, &units, &config);
+        assert_eq!(vectors.len(), 4 * n_members.0);
+        assert!(vectors.values().all(|votes| votes.len() == n_members.0 + 1));
+        assert!(RoundElection::vote_anomalies(1, &vectors, &units, &config).is_empty());
+
+        let voter = dag[2][0].hash();
+        let set_vote = |vectors: &mut HashMap<_, Vec<_>>, candidate, vote| {
+            let votes = vectors.get_mut(&voter).expect("voter voted");
+            votes
+                .iter_mut()
+                .find(|(voted_on, _)| *voted_on == candidate)
+                .expect("voted on every candidate")
+                .1 = vote;
+        };
+
+        // Voting against a parent.
+        let mut inconsistent = vectors.clone();
+        set_vote(&mut inconsistent, dag[1][2].hash(), false);
+        assert_eq!(
+            RoundElection::vote_anomalies(1, &inconsistent, &units, &config),
+            vec![VoteAnomaly::InconsistentWithParents {
+                voter,
+                candidate: dag[1][2].hash(),
+                vote: false,
+            }]
+        );
+
+        // Voting for both units of the forker, even though only one of them is a parent.
+        let mut forked = vectors.clone();
+        set_vote(&mut forked, fork.hash(), true);
+        let candidates: Vec<_> = forked[&voter]
+            .iter()
+            .map(|(candidate, _)| *candidate)
+            .filter(|candidate| [dag[1][1].hash(), fork.hash()].contains(candidate))
+            .collect();
+        assert_eq!(
+            RoundElection::vote_anomalies(1, &forked, &units, &config),
+            vec![
+                VoteAnomaly::InconsistentWithParents {
+                    voter,
+                    candidate: fork.hash(),
+                    vote: true,
+                },
+                VoteAnomaly::ForkedVotes {
+                    voter,
+                    creator: NodeIndex(1),
+                    candidates,
+                },
+            ]
+        );
+    }
+
+    // Elects the head of round 0 twice with the given hasher, returning the head.
+    fn elect_twice_with_hasher<H: Hasher>(n_members: NodeCount) -> H::Hash {
+        let mut units = Units::new(n_members);
+        for unit in full_parent_reconstructed_units_with_hasher_up_to::<H>(4, n_members, 43)
+            .into_iter()
+            .flatten()
+        {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let lowest_hash = units
+            .in_round(0)
+            .expect("just added")
+            .iter()
+            .map(|candidate| candidate.hash())
+            .min()
+            .expect("there are candidates");
+        // Repeated elections on the same units agree.
+        for _ in 0..2 {
+            let head = RoundElection::for_round(0, &units, &ElectionConfig::default())
+                .expect("we have enough rounds")
+                .expect_elected_immediately("should have elected");
+            assert_eq!(head, lowest_hash);
+        }
+        lowest_hash
+    }
+
+    #[test]
+    fn elects_consistently_with_runtime_hashers() {
+        let n_members = NodeCount(4);
+        let heads = [
+            elect_twice_with_hasher::<RuntimeHasher<Sha256Algo>>(n_members),
+            elect_twice_with_hasher::<RuntimeHasher<Blake2b256Algo>>(n_members),
+            elect_twice_with_hasher::<RuntimeHasher<Blake2b512Algo>>(n_members),
+        ];
+        let hash_lengths: Vec<_> = heads.iter().map(|head| head.as_ref().len()).collect();
+        assert_eq!(hash_lengths, vec![32, 32, 64]);
+        // The same units get different hashes with every algorithm.
+        assert_ne!(heads[0], heads[1]);
+        assert_ne!(heads[0], heads[2]);
+        assert_ne!(heads[1], heads[2]);
+    }
+
+    #[test]
+    fn refuses_to_elect_at_round_limit() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        for unit in random_full_parent_reconstrusted_units_up_to(4, n_members, 43)
+            .into_iter()
+            .flatten()
+        {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        for round in [Round::MAX - 3, Round::MAX - 2, Round::MAX] {
+            assert!(matches!(
+                RoundElection::for_round(round, &units, &ElectionConfig::default()),
+                Err(ElectionStartError::NotEnoughRounds {
+                    have: 4,
+                    need: Round::MAX
+                })
+            ));
+        }
+    }
+
+    // Counts how many times each unit was loaded.
+    struct CountingStorage {
+        units: HashMap<Hash64, TestingDagUnit>,
+        loads: Rc<RefCell<HashMap<Hash64, usize>>>,
+    }
+
+    impl UnitStorage<TestingDagUnit> for CountingStorage {
+        fn insert(&mut self, unit: TestingDagUnit) {
+            self.units.insert(unit.hash(), unit);
+        }
+
+        fn get(&self, hash: &Hash64) -> Option<Cow<'_, TestingDagUnit>> {
+            *self.loads.borrow_mut().entry(*hash).or_default() += 1;
+            self.units.get(hash).cloned().map(Cow::Owned)
+        }
+
+        fn remove(&mut self, hash: &Hash64) -> Option<TestingDagUnit> {
+            self.units.remove(hash)
+        }
+    }
+
+    #[test]
+    fn cached_votes_avoid_loading_voters() {
+        use ElectionResult::*;
+        let n_members = NodeCount(4);
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(4, n_members, session_id);
+        let loads = Rc::new(RefCell::new(HashMap::new()));
+        let mut units = Units::with_storage(
+            CountingStorage {
+                units: HashMap::new(),
+                loads: loads.clone(),
+            },
+            n_members,
+        );
+        for round_units in &dag[..4] {
+            for unit in round_units {
+                units.add_unit(unit.clone()).expect("unit is valid");
+            }
+        }
+        let config = ElectionConfig::default();
+        let election = match RoundElection::for_round(0, &units, &config) {
+            Ok(Pending(election)) => election,
+            _ => panic!("should be pending without units of round 4"),
+        };
+        // Every voter got loaded once to compute its vote.
+        for unit in dag[1..4].iter().flatten() {
+            assert_eq!(loads.borrow().get(&unit.hash()), Some(&1));
+        }
+        let snapshot = election.snapshot();
+        for unit in &dag[4] {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        loads.borrow_mut().clear();
+        match RoundElection::from_snapshot(snapshot, &units, &config) {
+            Ok(Elected(_)) => (),
+            _ => panic!("should have elected"),
+        }
+        // Votes of the old voters are cached, so only the new ones got loaded.
+        for unit in dag[1..4].iter().flatten() {
+            assert_eq!(loads.borrow().get(&unit.hash()), None);
+        }
+        for unit in &dag[4] {
+            assert!(loads.borrow().get(&unit.hash()).copied().unwrap_or(0) <= 1);
         }
     }
 }
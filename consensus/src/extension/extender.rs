@@ -1,29 +1,146 @@
 use crate::{
     extension::{
-        election::{ElectionResult, RoundElection},
-        units::Units,
+        election::{
+            ElectionConfig, ElectionResult, ElectionStartError, RoundElection, VoteWarmer, Weight,
+        },
+        units::{InvalidUnit, UnitStorage, Units},
     },
-    units::UnitWithParents,
-    Round,
+    units::{HashFor, UnitWithParents},
+    NodeCount, Receiver, Round, Sender, SessionId,
 };
+use futures::{channel::mpsc, future, stream, Stream, StreamExt};
+use log::{debug, error, warn};
+use std::collections::{HashMap, HashSet};
 
+const LOG_TARGET: &str = "AlephBFT-extender";
+
+/// The state of ordering at the end of a session, from which it can be continued.
+pub struct SessionBoundary<U: UnitWithParents> {
+    /// The last elected head, if any.
+    pub last_head: Option<HashFor<U>>,
+    /// The round of the next head to elect.
+    pub round: Round,
+    /// The units that were not ordered yet, in order of rounds.
+    pub units: Vec<U>,
+}
+
+/// Units appended to the ordered sequence when a head got elected, identified by their hashes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderDiff<H> {
+    /// The position of the first of the units in the ordered sequence.
+    pub start_index: u64,
+    /// The hashes of the units, in order.
+    pub hashes: Vec<H>,
+}
+
+impl<H: Clone> OrderDiff<H> {
+    /// The position right after the last of the units in the ordered sequence.
+    pub fn end_index(&self) -> u64 {
+        self.start_index + self.hashes.len() as u64
+    }
+
+    /// Put the hashes at their positions in the sequence, replacing anything already there.
+    /// Returns `false` and leaves the sequence untouched if the diff starts past its end.
+    pub fn apply(&self, order: &mut Vec<H>) -> bool {
+        let start_index = self.start_index as usize;
+        if start_index > order.len() {
+            return false;
+        }
+        order.truncate(start_index);
+        order.extend(self.hashes.iter().cloned());
+        true
+    }
+}
+
+/// Orders units by electing heads of consecutive rounds, returning the batch of units ordered with every head.
 pub struct Extender<U: UnitWithParents> {
     election: Option<RoundElection<U>>,
+    // Only present if warming up votes is enabled.
+    warmer: Option<VoteWarmer<U>>,
     units: Units<U>,
     round: Round,
+    last_head: Option<HashFor<U>>,
+    config: ElectionConfig,
+    reported_stall: Round,
+    // The round whose election ended without a head, which only a new candidate of that round can change.
+    stuck_round: Option<Round>,
+    // Positions of the ordered units in the finalized sequence.
+    order_indices: HashMap<HashFor<U>, u64>,
+    diff_subscribers: Vec<Sender<OrderDiff<HashFor<U>>>>,
 }
 
 impl<U: UnitWithParents> Extender<U> {
-    /// Create a new extender with no units.
-    pub fn new() -> Self {
+    /// Create a new extender with no units, for a committee with `n_members` members.
+    pub fn new(n_members: NodeCount) -> Self {
+        Self::with_config(n_members, ElectionConfig::default())
+    }
+
+    /// Create a new extender with no units, for a committee with `n_members` members,
+    /// using the provided configuration in all elections.
+    pub fn with_config(n_members: NodeCount, config: ElectionConfig) -> Self {
         Extender {
             election: None,
-            units: Units::new(),
+            warmer: None,
+            units: Units::new(n_members),
             round: 0,
+            last_head: None,
+            config,
+            reported_stall: 0,
+            stuck_round: None,
+            order_indices: HashMap::new(),
+            diff_subscribers: Vec::new(),
+        }
+    }
+
+    /// Reject units of sessions other than the given one.
+    pub fn with_session(self, session_id: SessionId) -> Self {
+        Extender {
+            units: self.units.with_session(session_id),
+            ..self
+        }
+    }
+
+    /// Create a new extender continuing the ordering from where the previous one finished,
+    /// using the provided configuration in all elections. Elections resume with the next added unit.
+    pub fn resume_from(
+        boundary: SessionBoundary<U>,
+        n_members: NodeCount,
+        config: ElectionConfig,
+    ) -> Self {
+        let SessionBoundary {
+            last_head,
+            round,
+            units,
+        } = boundary;
+        let mut extender = Self::with_config(n_members, config);
+        extender.round = round;
+        extender.last_head = last_head;
+        // Reject the same late units the finished extender would have.
+        if let Some(head_round) = extender.highest_ordered_round() {
+            extender.prune(head_round);
+        }
+        for unit in units {
+            if let Err(e) = extender.units.add_unit(unit) {
+                warn!(target: LOG_TARGET, "Dropping a unit when resuming: {}", e);
+            }
+        }
+        extender
+    }
+
+    /// Stop ordering, dropping any ongoing election, and return the state needed to resume it.
+    pub fn finalize_session(&mut self) -> SessionBoundary<U> {
+        self.election = None;
+        self.warmer = None;
+        self.stuck_round = None;
+        let n_members = self.units.n_members();
+        SessionBoundary {
+            last_head: self.last_head,
+            round: self.round,
+            units: std::mem::replace(&mut self.units, Units::new(n_members)).into_units(),
         }
     }
 
-    fn handle_election_result(&mut self, result: ElectionResult<U>) -> Option<Vec<U>> {
+    fn handle_election_result(&mut self, result: ElectionResult<U>) -> Option<(Round, Vec<U>)> {
         use ElectionResult::*;
         match result {
             // Wait for more voters for this election.
@@ -31,38 +148,350 @@ impl<U: UnitWithParents> Extender<U> {
                 self.election = Some(election);
                 None
             }
+            // Keep waiting, but let the operator know, once per round.
+            Stalled {
+                election,
+                rounds_waited,
+            } => {
+                if rounds_waited > self.reported_stall {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Election for round {} still undecided after {} rounds.",
+                        self.round,
+                        rounds_waited
+                    );
+                    self.reported_stall = rounds_waited;
+                }
+                self.election = Some(election);
+                None
+            }
+            // Reported when trying to restart the election.
+            NoHead | Aborted(_) => None,
             // Advance to the next round and return the ordered batch.
-            Elected(head) => {
-                self.round += 1;
-                Some(self.units.remove_batch(&head))
+            Elected(head) | ElectedImmediately(head) => {
+                let head_round = self.round;
+                self.round = self.round.saturating_add(1);
+                self.reported_stall = 0;
+                self.last_head = Some(head);
+                let batch = self
+                    .units
+                    .remove_batch_with_order(&head, self.config.batch_order());
+                self.prune(head_round);
+                let start_index = self.order_indices.len() as u64;
+                for unit in &batch {
+                    let index = self.order_indices.len() as u64;
+                    self.order_indices.insert(unit.hash(), index);
+                }
+                self.publish_diff(OrderDiff {
+                    start_index,
+                    hashes: batch.iter().map(|unit| unit.hash()).collect(),
+                });
+                Some((head_round, batch))
             }
         }
     }
 
+    fn publish_diff(&mut self, diff: OrderDiff<HashFor<U>>) {
+        self.diff_subscribers
+            .retain(|subscriber| subscriber.unbounded_send(diff.clone()).is_ok());
+    }
+
+    /// A stream of the diffs of the ordered sequence, one for every batch ordered from now on.
+    /// Applying them in order to the sequence ordered so far reconstructs the ordered sequence.
+    pub fn subscribe_diffs(&mut self) -> Receiver<OrderDiff<HashFor<U>>> {
+        let (diffs_tx, diffs_rx) = mpsc::unbounded();
+        self.diff_subscribers.push(diffs_tx);
+        diffs_rx
+    }
+
+    fn prune(&mut self, head_round: Round) {
+        if let Some(depth) = self.config.pruning_depth() {
+            self.units.prune_below(head_round.saturating_sub(depth));
+        }
+    }
+
+    /// The round of the most recently elected head, or `None` if no head was elected yet.
+    pub fn highest_ordered_round(&self) -> Option<Round> {
+        // Heads are elected round by round, so the last one is from the round before the current election.
+        self.last_head.map(|_| self.round - 1)
+    }
+
+    /// The zero-based position of the unit in the sequence of units ordered by this extender,
+    /// or `None` if the unit was not ordered yet.
+    pub fn order_index(&self, hash: HashFor<U>) -> Option<u64> {
+        self.order_indices.get(&hash).copied()
+    }
+
+    /// The candidate currently being voted on, or `None` if no election is pending.
+    pub fn current_candidate(&self) -> Option<HashFor<U>> {
+        self.election
+            .as_ref()
+            .map(|election| election.current_candidate())
+    }
+
+    /// The weights of votes for and against the current candidate among the units of the highest known round,
+    /// or `None` if no election is pending.
+    pub fn tally(&self) -> Option<(Weight, Weight)> {
+        self.election
+            .as_ref()
+            .map(|election| election.tally(&self.units))
+    }
+
+    /// The heads, together with their rounds, that would be elected given the units added so far,
+    /// in the order they would be elected. Does not modify the extender.
+    /// Elections run whenever a unit is added, so this is only nonempty after resuming from a session boundary.
+    pub fn peek_orderable(&self) -> Vec<(Round, HashFor<U>)> {
+        // These elections do not really happen, so they should not be reported.
+        let config = self.config.clone().without_status_monitor();
+        #[cfg(feature = "metrics")]
+        let config = config.without_metrics();
+        RoundElection::run_all_ready(self.round, &self.units, &config)
+    }
+
     /// Add a unit to the extender. Might return several batches of ordered units as a result.
     pub fn add_unit(&mut self, u: U) -> Vec<Vec<U>> {
+        self.add_unit_with_heads(u)
+            .into_iter()
+            .map(|(_, batch)| batch)
+            .collect()
+    }
+
+    /// Like [`Extender::add_unit`], but returns every batch together with the round of its head,
+    /// which is not necessarily the last unit of the batch.
+    pub fn add_unit_with_heads(&mut self, u: U) -> Vec<(Round, Vec<U>)> {
         let hash = u.hash();
-        self.units.add_unit(u);
+        match self.units.add_unit(u) {
+            Ok(()) => (),
+            // Too late to be ordered, which happens to units of very slow nodes.
+            Err(InvalidUnit::Pruned(coord, round)) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Dropping unit of round {}, units below round {} were pruned.",
+                    coord.round(),
+                    round
+                );
+                return Vec::new();
+            }
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Rejecting a unit: {}", e);
+                return Vec::new();
+            }
+        }
         let unit = self.units.get(&hash).expect("just added");
         let mut result = Vec::new();
-        // If we have an ongoing election try to finish it.
-        if let Some(election) = self.election.take() {
-            if let Some(batch) = self.handle_election_result(election.add_voter(unit, &self.units))
-            {
-                result.push(batch);
+        if let Some(round) = self.stuck_round {
+            // The failed election already considered all the voters, so only a new candidate can change its result.
+            if round != unit.round() {
+                return result;
+            }
+            self.stuck_round = None;
+            // The warmer missed the units added in the meantime.
+            self.warmer = None;
+        }
+        // If we have an ongoing election try to finish it. Only units above its round can vote in it.
+        if unit.round() > self.round {
+            if let Some(election) = self.election.take() {
+                if let Some(batch) =
+                    self.handle_election_result(election.add_voter(&unit, &self.units))
+                {
+                    result.push(batch);
+                }
+            }
+        }
+        // While waiting for the next election to become possible, prepare the votes for it.
+        if self.config.vote_warmup() && self.election.is_none() {
+            let unit = self.units.get(&hash).expect("just added");
+            match &mut self.warmer {
+                Some(warmer) if warmer.round() == self.round => warmer.add_unit(&unit, &self.units),
+                // Starts with all the units present, including this one.
+                _ => self.warmer = Some(VoteWarmer::new(self.round, &self.units, &self.config)),
             }
         }
         // Try finding another election to be working on.
         while self.election.is_none() {
-            match RoundElection::for_round(self.round, &self.units) {
+            let election = match &mut self.warmer {
+                Some(warmer) => {
+                    RoundElection::for_round_warmed(self.round, &self.units, &self.config, warmer)
+                }
+                None => RoundElection::for_round(self.round, &self.units, &self.config),
+            };
+            match election {
+                // Too many faulty nodes, so we cannot make progress.
+                Ok(ElectionResult::NoHead) => {
+                    error!(target: LOG_TARGET, "All candidates for round {} eliminated.", self.round);
+                    self.stuck_round = Some(self.round);
+                    break;
+                }
+                // A malformed unit, we cannot make progress.
+                Ok(ElectionResult::Aborted(e)) => {
+                    error!(target: LOG_TARGET, "Election for round {} aborted: {}", self.round, e);
+                    self.stuck_round = Some(self.round);
+                    break;
+                }
                 Ok(election_result) => {
                     if let Some(batch) = self.handle_election_result(election_result) {
                         result.push(batch);
                     }
                 }
                 // Not enough voters yet.
-                Err(()) => break,
+                Err(ElectionStartError::NotEnoughRounds { .. }) => break,
+                // Units are added in order, so this should never happen.
+                Err(e @ ElectionStartError::NoCandidates) => {
+                    match self.units.check_contiguous() {
+                        Ok(()) => {
+                            warn!(target: LOG_TARGET, "Unable to start election for round {}: {}", self.round, e)
+                        }
+                        Err(gap) => {
+                            warn!(target: LOG_TARGET, "Unable to start election for round {}: {} {}", self.round, e, gap)
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// Turn the extender into a stream of hashes of the ordered units, consuming the provided units.
+    /// Every item is a batch ordered when a head got elected, in the order the units were ordered in.
+    pub fn ordered_stream<S: Stream<Item = U>>(
+        self,
+        units: S,
+    ) -> impl Stream<Item = Vec<HashFor<U>>> {
+        units
+            .scan(self, |extender, unit| {
+                future::ready(Some(extender.add_unit(unit)))
+            })
+            .flat_map(stream::iter)
+            .map(|batch| batch.iter().map(|unit| unit.hash()).collect())
+    }
+}
+
+/// Orders units kept in a store it does not own, e.g. one that also backs the Dag intake, so the units
+/// are not duplicated. Unlike [`Extender`] it never removes units from the store, which has to keep all
+/// the units added so far, including the ordered ones, and it only runs elections when asked to.
+pub struct SharedExtender<U: UnitWithParents> {
+    election: Option<RoundElection<U>>,
+    // Units of rounds above the current election that already voted in it.
+    voters: HashSet<HashFor<U>>,
+    round: Round,
+    last_head: Option<HashFor<U>>,
+    config: ElectionConfig,
+    ordered: HashSet<HashFor<U>>,
+    // The round whose election ended without a head and the number of its candidates at that point,
+    // as only a new candidate of that round can change the result.
+    stuck: Option<(Round, usize)>,
+}
+
+impl<U: UnitWithParents> Default for SharedExtender<U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<U: UnitWithParents> SharedExtender<U> {
+    /// Create a new extender that has not ordered anything yet.
+    pub fn new() -> Self {
+        Self::with_config(ElectionConfig::default())
+    }
+
+    /// Create a new extender, using the provided configuration in all elections.
+    pub fn with_config(config: ElectionConfig) -> Self {
+        SharedExtender {
+            election: None,
+            voters: HashSet::new(),
+            round: 0,
+            last_head: None,
+            config,
+            ordered: HashSet::new(),
+            stuck: None,
+        }
+    }
+
+    /// The round of the most recently elected head, or `None` if no head was elected yet.
+    pub fn highest_ordered_round(&self) -> Option<Round> {
+        self.last_head.map(|_| self.round - 1)
+    }
+
+    /// Forget everything about the current session, including the votes computed so far,
+    /// to order the units of the next session, which come in a new store, from scratch.
+    pub fn start_new_session(&mut self) {
+        *self = Self::with_config(self.config.clone());
+    }
+
+    // Units of rounds above the current election that did not vote in it yet, in order of rounds.
+    fn new_voters<S: UnitStorage<U>>(&self, units: &Units<U, S>) -> Vec<HashFor<U>> {
+        (self.round.saturating_add(1)..=units.highest_round())
+            .filter_map(|round| units.hashes_in_round(round))
+            .flatten()
+            .filter(|hash| !self.voters.contains(*hash))
+            .cloned()
+            .collect()
+    }
+
+    fn candidate_count<S: UnitStorage<U>>(units: &Units<U, S>, round: Round) -> usize {
+        units.hashes_in_round(round).map_or(0, Vec::len)
+    }
+
+    fn start_election<S: UnitStorage<U>>(
+        &mut self,
+        units: &Units<U, S>,
+    ) -> Result<ElectionResult<U>, ElectionStartError> {
+        // The election starts with all the units present.
+        self.voters = self.new_voters(units).into_iter().collect();
+        RoundElection::for_round(self.round, units, &self.config)
+    }
+
+    /// Order whatever the units currently in the store allow, e.g. after adding units to it.
+    /// Might return several batches of ordered units.
+    pub fn extend<S: UnitStorage<U>>(&mut self, units: &Units<U, S>) -> Vec<Vec<U>> {
+        use ElectionResult::*;
+        let mut result = Vec::new();
+        if let Some((round, candidates)) = self.stuck {
+            if Self::candidate_count(units, round) == candidates {
+                return result;
             }
+            self.stuck = None;
+        }
+        let mut next = match self.election.take() {
+            Some(election) => {
+                let voters = self.new_voters(units);
+                self.voters.extend(voters.iter().cloned());
+                Ok(election.add_voters(voters, units))
+            }
+            None => self.start_election(units),
+        };
+        loop {
+            match next {
+                Ok(Pending(election) | Stalled { election, .. }) => {
+                    self.election = Some(election);
+                    break;
+                }
+                Ok(Elected(head) | ElectedImmediately(head)) => {
+                    let batch =
+                        units.batch_with_order(&head, &self.ordered, self.config.batch_order());
+                    self.ordered.extend(batch.iter().map(|unit| unit.hash()));
+                    self.round = self.round.saturating_add(1);
+                    self.last_head = Some(head);
+                    result.push(batch);
+                }
+                Ok(NoHead) => {
+                    error!(target: LOG_TARGET, "All candidates for round {} eliminated.", self.round);
+                    self.stuck = Some((self.round, Self::candidate_count(units, self.round)));
+                    break;
+                }
+                Ok(Aborted(e)) => {
+                    error!(target: LOG_TARGET, "Election for round {} aborted: {}", self.round, e);
+                    self.stuck = Some((self.round, Self::candidate_count(units, self.round)));
+                    break;
+                }
+                Err(ElectionStartError::NotEnoughRounds { .. }) => break,
+                Err(e @ ElectionStartError::NoCandidates) => {
+                    warn!(target: LOG_TARGET, "Unable to start election for round {}: {}", self.round, e);
+                    break;
+                }
+            }
+            next = self.start_election(units);
         }
         result
     }
@@ -70,15 +499,35 @@ impl<U: UnitWithParents> Extender<U> {
 
 #[cfg(test)]
 mod test {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    };
+
     use crate::{
-        extension::extender::Extender, units::random_full_parent_reconstrusted_units_up_to,
+        extension::{
+            election::{ElectionConfig, Weight},
+            extender::{Extender, OrderDiff, SessionBoundary, SharedExtender},
+            testing::{
+                dag_with_parents, AlwaysAgainstCommonVote, AlwaysForCommonVote, UnanimousThreshold,
+            },
+            units::{BatchOrder, InvalidUnit, RoundCreatorBatchOrder, RoundHashBatchOrder, Units},
+        },
+        testing::{capture_logs, CapturedRecord},
+        units::{
+            random_full_parent_reconstrusted_units_up_to, TestingDagUnit, Unit, UnitWithParents,
+        },
         NodeCount, Round,
     };
+    use codec::Encode;
+    use futures::{stream, StreamExt};
+    use log::Level;
+    use parking_lot::RwLock;
 
     #[test]
     fn easy_elections() {
-        let mut extender = Extender::new();
         let n_members = NodeCount(4);
+        let mut extender = Extender::new(n_members);
         let max_round: Round = 43;
         let session_id = 2137;
         let mut batches = Vec::new();
@@ -95,4 +544,575 @@ mod test {
             assert_eq!(batch.len(), n_members.0);
         }
     }
+
+    #[test]
+    fn shared_units_give_identical_batches() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 43;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let hashes = |batches: Vec<Vec<TestingDagUnit>>| -> Vec<Vec<_>> {
+            batches
+                .iter()
+                .map(|batch| batch.iter().map(|unit| unit.hash()).collect())
+                .collect()
+        };
+        let mut extender = Extender::new(n_members);
+        let mut expected = Vec::new();
+        for unit in dag.iter().flatten() {
+            expected.append(&mut extender.add_unit(unit.clone()));
+        }
+
+        let units = Arc::new(RwLock::new(Units::new(n_members)));
+        let (added_tx, added_rx) = std::sync::mpsc::channel();
+        let writer = {
+            let units = units.clone();
+            let dag = dag.clone();
+            std::thread::spawn(move || {
+                for unit in dag.into_iter().flatten() {
+                    units.write().add_unit(unit).expect("unit is valid");
+                    added_tx.send(()).expect("reader is running");
+                }
+            })
+        };
+        let mut shared_extender = SharedExtender::new();
+        let mut batches = Vec::new();
+        while added_rx.recv().is_ok() {
+            batches.append(&mut shared_extender.extend(&units.read()));
+        }
+        writer.join().expect("writer finished");
+
+        assert!(!expected.is_empty());
+        assert_eq!(hashes(batches), hashes(expected));
+        assert_eq!(
+            shared_extender.highest_ordered_round(),
+            extender.highest_ordered_round()
+        );
+        // Nothing got removed from the shared units.
+        for unit in dag.iter().flatten() {
+            assert!(units.read().get(&unit.hash()).is_some());
+        }
+    }
+
+    #[test]
+    fn shared_extender_orders_next_session_from_scratch() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let mut extender = SharedExtender::new();
+        let mut expected = Vec::new();
+        for session_id in [1, 2] {
+            let dag =
+                random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+            let mut units = Units::new(n_members).with_session(session_id);
+            let mut batches = Vec::new();
+            for unit in dag.iter().flatten() {
+                units.add_unit(unit.clone()).expect("unit is valid");
+                batches.append(&mut extender.extend(&units));
+            }
+            let mut fresh = Extender::new(n_members);
+            let fresh_batches: Vec<_> = dag
+                .into_iter()
+                .flatten()
+                .flat_map(|unit| fresh.add_unit(unit))
+                .collect();
+            assert!(!batches.is_empty());
+            assert_eq!(batches, fresh_batches);
+            expected.push(extender.highest_ordered_round());
+            extender.start_new_session();
+            assert_eq!(extender.highest_ordered_round(), None);
+        }
+        assert_eq!(expected[0], expected[1]);
+    }
+
+    #[test]
+    fn prunes_units_below_ordered_heads() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 43;
+        let depth = 2;
+        let config = ElectionConfig::default().with_pruning_depth(Some(depth));
+        let mut pruning_extender = Extender::with_config(n_members, config);
+        let mut extender = Extender::with_config(
+            n_members,
+            ElectionConfig::default().with_pruning_depth(None),
+        );
+        let mut pruned_batches = Vec::new();
+        let mut batches = Vec::new();
+        for round_units in random_full_parent_reconstrusted_units_up_to(max_round, n_members, 2137)
+        {
+            for unit in round_units {
+                pruned_batches.append(&mut pruning_extender.add_unit(unit.clone()));
+                batches.append(&mut extender.add_unit(unit));
+            }
+            if let Some(round) = pruning_extender.highest_ordered_round() {
+                assert_eq!(
+                    pruning_extender.units.lowest_round(),
+                    round.saturating_sub(depth)
+                );
+            }
+        }
+        assert_eq!(pruned_batches, batches);
+        assert_eq!(extender.units.lowest_round(), 0);
+    }
+
+    #[test]
+    fn reports_pending_election() {
+        let n_members = NodeCount(4);
+        let mut extender = Extender::new(n_members);
+        let dag = random_full_parent_reconstrusted_units_up_to(3, n_members, 2137);
+        assert_eq!(extender.current_candidate(), None);
+        assert_eq!(extender.tally(), None);
+        for unit in dag.iter().flatten() {
+            assert!(extender.add_unit(unit.clone()).is_empty());
+        }
+        let first_candidate = dag[0]
+            .iter()
+            .map(|candidate| candidate.hash())
+            .min()
+            .expect("there are candidates");
+        assert_eq!(extender.current_candidate(), Some(first_candidate));
+        assert_eq!(extender.tally(), Some((n_members.into(), Weight(0))));
+    }
+
+    #[test]
+    fn order_indices_follow_emission_order() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let session_id = 2137;
+        let mut extender = Extender::new(n_members);
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut ordered = Vec::new();
+        for unit in dag.iter().flatten() {
+            assert_eq!(extender.order_index(unit.hash()), None);
+            for batch in extender.add_unit(unit.clone()) {
+                ordered.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        assert!(!ordered.is_empty());
+        for (index, hash) in ordered.iter().enumerate() {
+            assert_eq!(extender.order_index(*hash), Some(index as u64));
+        }
+        let unordered = dag
+            .iter()
+            .flatten()
+            .filter(|unit| !ordered.contains(&unit.hash()));
+        for unit in unordered {
+            assert_eq!(extender.order_index(unit.hash()), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn ordered_stream_matches_batches() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let session_id = 2137;
+        let units: Vec<_> =
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+                .into_iter()
+                .flatten()
+                .collect();
+        let mut extender = Extender::new(n_members);
+        let mut expected = Vec::new();
+        for unit in units.clone() {
+            for batch in extender.add_unit(unit) {
+                expected.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        let batches: Vec<_> = Extender::new(n_members)
+            .ordered_stream(stream::iter(units.clone()))
+            .collect()
+            .await;
+        assert_eq!(batches.len(), (max_round - 3).into());
+        let ordered: Vec<_> = batches.into_iter().flatten().collect();
+        assert_eq!(ordered, expected);
+        // Parents are always ordered before their children.
+        let positions: HashMap<_, _> = ordered
+            .iter()
+            .enumerate()
+            .map(|(position, hash)| (*hash, position))
+            .collect();
+        for unit in units {
+            if let Some(position) = positions.get(&unit.hash()) {
+                for parent in unit.parents().values() {
+                    assert!(positions[parent] < *position);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn order_diffs_reconstruct_ordered_units() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let session_id = 2137;
+        let mut extender = Extender::new(n_members);
+        let mut diffs = extender.subscribe_diffs();
+        let mut expected = Vec::new();
+        for unit in random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+            .into_iter()
+            .flatten()
+        {
+            for batch in extender.add_unit(unit) {
+                expected.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        drop(extender);
+
+        let mut ordered = Vec::new();
+        let mut end_index = 0;
+        while let Ok(Some(diff)) = diffs.try_next() {
+            assert_eq!(diff.start_index, end_index);
+            end_index = diff.end_index();
+            assert!(diff.apply(&mut ordered));
+        }
+        assert!(!ordered.is_empty());
+        assert_eq!(ordered, expected);
+
+        let gap = OrderDiff {
+            start_index: end_index + 1,
+            hashes: expected[..1].to_vec(),
+        };
+        assert!(!gap.apply(&mut ordered));
+        assert_eq!(ordered, expected);
+    }
+
+    // Checks that ordering in two sessions split at the boundary round gives the same result as ordering in one.
+    fn check_ordering_across_session_boundary(config: ElectionConfig) {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let boundary_round = 10;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut extender = Extender::with_config(n_members, config.clone());
+        let mut expected = Vec::new();
+        for unit in dag.iter().flatten() {
+            for batch in extender.add_unit(unit.clone()) {
+                expected.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        let mut ordered = Vec::new();
+        let mut extender = Extender::with_config(n_members, config.clone());
+        for unit in dag.iter().take(boundary_round + 1).flatten() {
+            for batch in extender.add_unit(unit.clone()) {
+                ordered.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        let boundary = extender.finalize_session();
+        assert_eq!(boundary.last_head, ordered.last().cloned());
+        assert_eq!(
+            Some(boundary.round - 1),
+            config.last_head_round(boundary_round as Round)
+        );
+        let mut extender = Extender::resume_from(boundary, n_members, config);
+        for unit in dag.iter().skip(boundary_round + 1).flatten() {
+            for batch in extender.add_unit(unit.clone()) {
+                ordered.extend(batch.iter().map(|unit| unit.hash()));
+            }
+        }
+        let unique: HashSet<_> = ordered.iter().collect();
+        assert_eq!(unique.len(), ordered.len());
+        assert_eq!(ordered, expected);
+    }
+
+    #[test]
+    fn orders_across_session_boundary() {
+        check_ordering_across_session_boundary(ElectionConfig::default());
+    }
+
+    #[test]
+    fn resumes_pruning_where_it_finished() {
+        check_ordering_across_session_boundary(
+            ElectionConfig::default().with_pruning_depth(Some(2)),
+        );
+        let n_members = NodeCount(4);
+        let config = ElectionConfig::default().with_pruning_depth(Some(2));
+        let dag = random_full_parent_reconstrusted_units_up_to(10, n_members, 2137);
+        let mut extender = Extender::with_config(n_members, config.clone());
+        for unit in dag.iter().flatten() {
+            extender.add_unit(unit.clone());
+        }
+        let head_round = extender
+            .highest_ordered_round()
+            .expect("heads were elected");
+        let mut extender = Extender::resume_from(extender.finalize_session(), n_members, config);
+        assert_eq!(extender.highest_ordered_round(), Some(head_round));
+        let late_unit = dag[1][0].clone();
+        assert_eq!(
+            extender.units.add_unit(late_unit.clone()),
+            Err(InvalidUnit::Pruned(late_unit.coord(), head_round - 2))
+        );
+    }
+
+    #[test]
+    fn resumes_with_provided_config() {
+        // Elects heads a round earlier than the default, so the resumed extender has to keep using it.
+        check_ordering_across_session_boundary(
+            ElectionConfig::default().with_common_vote(Arc::new(AlwaysForCommonVote)),
+        );
+    }
+
+    #[test]
+    fn peeking_matches_ordering() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let peek_round = 10;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        // Elections only run when units are added, so resuming leaves elected heads to peek at.
+        let mut extender = Extender::resume_from(
+            SessionBoundary {
+                last_head: None,
+                round: 0,
+                units: dag.iter().take(peek_round + 1).flatten().cloned().collect(),
+            },
+            n_members,
+            ElectionConfig::default(),
+        );
+        let peeked = extender.peek_orderable();
+        assert_eq!(peeked.len(), peek_round - 3);
+        assert_eq!(extender.peek_orderable(), peeked);
+        let mut elected = Vec::new();
+        for unit in dag.iter().skip(peek_round + 1).flatten() {
+            for batch in extender.add_unit(unit.clone()) {
+                let head = batch.last().expect("batches are not empty");
+                elected.push((head.round(), head.hash()));
+            }
+        }
+        assert_eq!(elected[..peeked.len()], peeked);
+        assert!(extender.peek_orderable().is_empty());
+    }
+
+    #[test]
+    fn same_batch_order_gives_identical_batches() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut canonical = Vec::new();
+        let mut extender = Extender::new(n_members);
+        for unit in dag.iter().flatten() {
+            canonical.append(&mut extender.add_unit(unit.clone()));
+        }
+        let encode = |batches: &Vec<Vec<TestingDagUnit>>| -> Vec<Vec<u8>> {
+            batches
+                .iter()
+                .map(|batch| {
+                    batch
+                        .iter()
+                        .map(|unit| unit.hash())
+                        .collect::<Vec<_>>()
+                        .encode()
+                })
+                .collect()
+        };
+        let orders: [Arc<dyn BatchOrder>; 2] = [
+            Arc::new(RoundCreatorBatchOrder),
+            Arc::new(RoundHashBatchOrder),
+        ];
+        for order in orders {
+            let config = ElectionConfig::default().with_batch_order(order);
+            let mut node = Extender::with_config(n_members, config.clone());
+            let mut other_node = Extender::with_config(n_members, config);
+            let (mut batches, mut other_batches) = (Vec::new(), Vec::new());
+            // The nodes receive the units in different orders.
+            for round_units in &dag {
+                for unit in round_units {
+                    batches.append(&mut node.add_unit(unit.clone()));
+                }
+                for unit in round_units.iter().rev() {
+                    other_batches.append(&mut other_node.add_unit(unit.clone()));
+                }
+            }
+            assert_eq!(encode(&batches), encode(&other_batches));
+            assert_ne!(encode(&batches), encode(&canonical));
+            for (batch, canonical_batch) in batches.iter().zip(&canonical) {
+                let hashes: HashSet<_> = batch.iter().map(|unit| unit.hash()).collect();
+                let canonical_hashes: HashSet<_> =
+                    canonical_batch.iter().map(|unit| unit.hash()).collect();
+                assert_eq!(hashes, canonical_hashes);
+                assert!(batch
+                    .windows(2)
+                    .all(|pair| pair[0].round() <= pair[1].round()));
+            }
+        }
+    }
+
+    #[test]
+    fn no_elections_without_candidates() {
+        let n_members = NodeCount(4);
+        let mut extender = Extender::new(n_members);
+        let max_round: Round = 10;
+        let session_id = 2137;
+        let mut batches = Vec::new();
+        // The units of the first election round are missing.
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+                .into_iter()
+                .skip(1)
+        {
+            for unit in round_units {
+                batches.append(&mut extender.add_unit(unit));
+            }
+        }
+        assert!(batches.is_empty());
+    }
+
+    #[test]
+    fn reports_eliminating_all_candidates_once() {
+        let full = vec![0, 1, 2, 3];
+        let rounds = dag_with_parents(
+            NodeCount(4),
+            vec![
+                // Every candidate is missing one vote, so with the common vote against all of them get eliminated.
+                vec![vec![1, 2, 3], vec![0, 2, 3], vec![0, 1, 3], vec![0, 1, 2]],
+                vec![full.clone(); 4],
+                vec![full.clone(); 4],
+                vec![full.clone(); 4],
+                vec![full; 4],
+            ],
+        );
+        let config = ElectionConfig::default().with_common_vote(Arc::new(AlwaysAgainstCommonVote));
+        let mut extender = Extender::with_config(NodeCount(4), config);
+        let (batches, records) = capture_logs(|| {
+            rounds
+                .into_iter()
+                .flatten()
+                .flat_map(|unit| extender.add_unit(unit))
+                .collect::<Vec<_>>()
+        });
+        assert!(batches.is_empty());
+        assert_eq!(
+            error_messages(&records),
+            vec!["All candidates for round 0 eliminated."]
+        );
+    }
+
+    // Round 2 contains a unit with too few parents to vote with the unanimous threshold, followed by a few rounds more.
+    fn rounds_with_malformed_voter() -> Vec<Vec<TestingDagUnit>> {
+        let full = vec![0, 1, 2, 3];
+        dag_with_parents(
+            NodeCount(4),
+            vec![
+                vec![full.clone(); 4],
+                vec![full.clone(), full.clone(), full.clone(), vec![0, 1, 2]],
+                vec![full.clone(); 4],
+                vec![full.clone(); 4],
+                vec![full; 4],
+            ],
+        )
+    }
+
+    fn error_messages(records: &[CapturedRecord]) -> Vec<&str> {
+        records
+            .iter()
+            .filter(|record| record.level == Level::Error)
+            .map(|record| record.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn reports_aborted_election_once() {
+        let config = ElectionConfig::default().with_threshold(Arc::new(UnanimousThreshold));
+        let mut extender = Extender::with_config(NodeCount(4), config);
+        let (batches, records) = capture_logs(|| {
+            rounds_with_malformed_voter()
+                .into_iter()
+                .flatten()
+                .flat_map(|unit| extender.add_unit(unit))
+                .collect::<Vec<_>>()
+        });
+        assert!(batches.is_empty());
+        let errors = error_messages(&records);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Election for round 0 aborted"));
+    }
+
+    #[test]
+    fn shared_extender_reports_aborted_election_once() {
+        let config = ElectionConfig::default().with_threshold(Arc::new(UnanimousThreshold));
+        let mut extender = SharedExtender::with_config(config);
+        let mut units = Units::new(NodeCount(4));
+        let (batches, records) = capture_logs(|| {
+            let mut batches = Vec::new();
+            for unit in rounds_with_malformed_voter().into_iter().flatten() {
+                units.add_unit(unit).expect("unit is valid");
+                batches.append(&mut extender.extend(&units));
+            }
+            batches
+        });
+        assert!(batches.is_empty());
+        let errors = error_messages(&records);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Election for round 0 aborted"));
+    }
+
+    #[test]
+    fn warmed_up_votes_give_identical_batches() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let session_id = 2137;
+        let mut cold = Extender::new(n_members);
+        let mut warm =
+            Extender::with_config(n_members, ElectionConfig::default().with_vote_warmup(true));
+        let (mut cold_batches, mut warm_batches) = (Vec::new(), Vec::new());
+        for unit in random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+            .into_iter()
+            .flatten()
+        {
+            for batch in cold.add_unit(unit.clone()) {
+                cold_batches.push(batch.iter().map(|unit| unit.hash()).collect::<Vec<_>>());
+            }
+            for batch in warm.add_unit(unit) {
+                warm_batches.push(batch.iter().map(|unit| unit.hash()).collect::<Vec<_>>());
+            }
+        }
+        assert_eq!(cold_batches.len(), (max_round - 3).into());
+        assert_eq!(warm_batches, cold_batches);
+    }
+
+    #[test]
+    fn reports_highest_ordered_round() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let session_id = 2137;
+        let mut extender = Extender::new(n_members);
+        assert_eq!(extender.highest_ordered_round(), None);
+        let mut last_head_round = None;
+        for unit in random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+            .into_iter()
+            .flatten()
+        {
+            for batch in extender.add_unit(unit) {
+                let head = batch.last().expect("batches are not empty");
+                last_head_round = Some(head.round());
+            }
+            assert_eq!(extender.highest_ordered_round(), last_head_round);
+        }
+        assert_eq!(last_head_round, Some(max_round - 4));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn reports_metrics_of_real_elections() {
+        use crate::{extension::election::ElectionConfig, Metrics};
+
+        let n_members = NodeCount(4);
+        let max_round: Round = 20;
+        let session_id = 2137;
+        let metrics = Metrics::register(&prometheus::Registry::new()).expect("registry is empty");
+        let mut extender = Extender::with_config(
+            n_members,
+            ElectionConfig::default().with_metrics(metrics.clone()),
+        );
+        let mut batches = Vec::new();
+        for unit in random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+            .into_iter()
+            .flatten()
+        {
+            batches.append(&mut extender.add_unit(unit));
+        }
+        assert_eq!(metrics.heads_elected(), batches.len() as u64);
+        assert_eq!(metrics.candidates_eliminated(), 0);
+        // The election of the last round is still pending.
+        assert_eq!(metrics.elections_started(), batches.len() as u64 + 1);
+    }
 }
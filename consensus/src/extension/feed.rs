@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    extension::extender::Extender,
+    units::{HashFor, UnitWithParents},
+    Round,
+};
+
+/// Feeds units arriving in any order to an extender, which requires units to come after their parents.
+/// Units are held back until all their parents were passed on, so in particular units of rounds
+/// above the highest contiguous round plus one wait until the gap fills.
+pub struct OrderedUnitFeed<U: UnitWithParents> {
+    extender: Extender<U>,
+    // Units waiting for their parents, by round.
+    buffered: BTreeMap<Round, Vec<U>>,
+    passed: HashSet<HashFor<U>>,
+}
+
+impl<U: UnitWithParents> OrderedUnitFeed<U> {
+    /// Create a feed passing units to the given extender.
+    pub fn new(extender: Extender<U>) -> Self {
+        OrderedUnitFeed {
+            extender,
+            buffered: BTreeMap::new(),
+            passed: HashSet::new(),
+        }
+    }
+
+    /// Add a unit. Might return several batches of ordered units, if the unit allowed the extender to advance.
+    pub fn push(&mut self, unit: U) -> Vec<Vec<U>> {
+        if self.passed.contains(&unit.hash()) {
+            return Vec::new();
+        }
+        self.buffered.entry(unit.round()).or_default().push(unit);
+        self.advance()
+    }
+
+    /// The number of units waiting for their parents.
+    pub fn buffered_count(&self) -> usize {
+        self.buffered.values().map(Vec::len).sum()
+    }
+
+    fn is_ready(&self, unit: &U) -> bool {
+        unit.parents()
+            .values()
+            .all(|parent| self.passed.contains(parent))
+    }
+
+    // Passing units of a round can only make units of the following round ready,
+    // so a single pass in order of rounds passes on all the units that are ready.
+    fn advance(&mut self) -> Vec<Vec<U>> {
+        let mut batches = Vec::new();
+        let rounds: Vec<_> = self.buffered.keys().cloned().collect();
+        for round in rounds {
+            let units = self.buffered.remove(&round).unwrap_or_default();
+            let (ready, waiting): (Vec<_>, Vec<_>) =
+                units.into_iter().partition(|unit| self.is_ready(unit));
+            if !waiting.is_empty() {
+                self.buffered.insert(round, waiting);
+            }
+            for unit in ready {
+                self.passed.insert(unit.hash());
+                batches.extend(self.extender.add_unit(unit));
+            }
+        }
+        batches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        extension::{extender::Extender, feed::OrderedUnitFeed},
+        units::{random_full_parent_reconstrusted_units_up_to, RandomDag, Unit},
+        NodeCount, Round,
+    };
+
+    #[test]
+    fn orders_units_pushed_in_reverse() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut extender = Extender::new(n_members);
+        let expected: Vec<_> = dag
+            .iter()
+            .flatten()
+            .flat_map(|unit| extender.add_unit(unit.clone()))
+            .collect();
+        assert!(!expected.is_empty());
+
+        let mut feed = OrderedUnitFeed::new(Extender::new(n_members));
+        let mut units: Vec<_> = dag.into_iter().flatten().collect();
+        let last = units.remove(0);
+        let mut batches = Vec::new();
+        for unit in units.into_iter().rev() {
+            batches.extend(feed.push(unit));
+        }
+        assert!(batches.is_empty());
+        // Only the remaining units of round 0 have all their parents.
+        assert_eq!(feed.buffered_count(), usize::from(max_round) * n_members.0);
+        batches.extend(feed.push(last));
+        assert_eq!(feed.buffered_count(), 0);
+        assert_eq!(batches, expected);
+    }
+
+    #[test]
+    fn holds_back_units_with_missing_parents() {
+        let n_members = NodeCount(4);
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(1, n_members, session_id);
+        let mut feed = OrderedUnitFeed::new(Extender::new(n_members));
+        for unit in dag[0].iter().skip(1) {
+            assert!(feed.push(unit.clone()).is_empty());
+        }
+        for unit in &dag[1] {
+            assert!(feed.push(unit.clone()).is_empty());
+        }
+        assert_eq!(feed.buffered_count(), n_members.0);
+        feed.push(dag[0][0].clone());
+        assert_eq!(feed.buffered_count(), 0);
+    }
+
+    #[test]
+    fn orders_random_dags_consistently_regardless_of_arrival_order() {
+        for seed in 0..16u64 {
+            let n_members = NodeCount(4 + (seed % 4) as usize);
+            let max_round = 15;
+            let mut dag = RandomDag::new(seed, n_members, max_round, 0.0);
+            assert_eq!(
+                dag.rounds(),
+                RandomDag::new(seed, n_members, max_round, 0.0).rounds()
+            );
+            let mut all_heads = Vec::new();
+            for _ in 0..n_members.0 {
+                let mut feed = OrderedUnitFeed::new(Extender::new(n_members));
+                let heads: Vec<_> = dag
+                    .shuffled()
+                    .into_iter()
+                    .flat_map(|unit| feed.push(unit))
+                    .map(|batch| batch.last().expect("batches are not empty").hash())
+                    .collect();
+                assert_eq!(feed.buffered_count(), 0);
+                all_heads.push(heads);
+            }
+            assert!(!all_heads[0].is_empty(), "seed {}", seed);
+            for heads in &all_heads {
+                assert_eq!(heads, &all_heads[0], "seed {}", seed);
+            }
+        }
+    }
+}
@@ -1,14 +1,58 @@
 use crate::{
     dag::DagUnit,
     units::{Unit, WrappedUnit},
-    Data, FinalizationHandler, Hasher, MultiKeychain,
+    Data, FinalizationHandler, Hasher, MultiKeychain, NodeCount, NodeIndex, Round, SessionId,
 };
 
+mod certificate;
+#[cfg(feature = "dot")]
+mod dot;
 mod election;
 mod extender;
+mod feed;
+#[cfg(test)]
+mod testing;
 mod units;
+mod verify;
 
-use extender::Extender;
+pub use certificate::{certify_head, sign_head, HeadCertificate, HeadClaim, SignedHeadClaim};
+#[cfg(feature = "dot")]
+pub use dot::to_dot;
+pub use election::{
+    extend_once, finalization_rounds, ByzantineThreshold, CandidateOrder, CommonVote,
+    DefaultCommonVote, ElectionConfig, ElectionConfigError, ElectionResult, ElectionSnapshot,
+    HashOrder, RoundElection, SnapshotError, Threshold, Weight,
+};
+#[cfg(feature = "vote_diagnostics")]
+pub use election::{VoteAnomaly, VoteVectors};
+pub use extender::{Extender, OrderDiff, SessionBoundary, SharedExtender};
+pub use feed::OrderedUnitFeed;
+pub use units::{
+    BatchOrder, BatchPosition, CanonicalBatchOrder, InMemoryStorage, InvalidUnit, MergeReport,
+    RoundCreatorBatchOrder, RoundGap, RoundHashBatchOrder, UnitStorage, Units,
+};
+pub use verify::{elect_heads, verify_order, OrderMismatch};
+
+/// A unit in the order decided by the consensus, together with the data it carries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderedUnit<H: Hasher, D: Data> {
+    pub hash: H::Hash,
+    pub creator: NodeIndex,
+    pub round: Round,
+    pub data: Option<D>,
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> From<DagUnit<H, D, MK>> for OrderedUnit<H, D> {
+    fn from(unit: DagUnit<H, D, MK>) -> Self {
+        let unit = unit.unpack();
+        OrderedUnit {
+            hash: unit.hash(),
+            creator: unit.creator(),
+            round: unit.round(),
+            data: unit.as_signable().data().clone(),
+        }
+    }
+}
 
 /// A struct responsible for executing the Consensus protocol on a local copy of the Dag.
 /// It receives units which are guaranteed to eventually appear in the Dags
@@ -22,31 +66,159 @@ use extender::Extender;
 pub struct Ordering<H: Hasher, D: Data, MK: MultiKeychain, FH: FinalizationHandler<D>> {
     extender: Extender<DagUnit<H, D, MK>>,
     finalization_handler: FH,
+    // After the head of this round gets ordered nothing more can be.
+    last_head_round: Option<Round>,
 }
 
 impl<H: Hasher, D: Data, MK: MultiKeychain, FH: FinalizationHandler<D>> Ordering<H, D, MK, FH> {
-    pub fn new(finalization_handler: FH) -> Self {
-        let extender = Extender::new();
+    pub fn new(
+        n_members: NodeCount,
+        finalization_handler: FH,
+        config: ElectionConfig,
+        max_round: Round,
+    ) -> Self {
+        let last_head_round = config.last_head_round(max_round);
+        let extender = Extender::with_config(n_members, config);
         Ordering {
             extender,
             finalization_handler,
+            last_head_round,
+        }
+    }
+
+    /// Only order units of the given session.
+    pub fn with_session(self, session_id: SessionId) -> Self {
+        Ordering {
+            extender: self.extender.with_session(session_id),
+            ..self
         }
     }
 
-    fn handle_batch(&mut self, batch: Vec<DagUnit<H, D, MK>>) {
-        for unit in batch {
-            let unit = unit.unpack();
-            self.finalization_handler.unit_finalized(
-                unit.creator(),
-                unit.round(),
-                unit.as_signable().data().clone(),
-            )
+    fn handle_batch(&mut self, head_round: Round, batch: Vec<DagUnit<H, D, MK>>) {
+        for unit in batch.into_iter().map(OrderedUnit::from) {
+            self.finalization_handler
+                .unit_finalized(unit.creator, unit.round, unit.data)
+        }
+        if Some(head_round) == self.last_head_round {
+            self.finalization_handler.session_finished();
         }
     }
 
     pub fn add_unit(&mut self, unit: DagUnit<H, D, MK>) {
-        for batch in self.extender.add_unit(unit) {
-            self.handle_batch(batch);
+        for (head_round, batch) in self.extender.add_unit_with_heads(unit) {
+            self.handle_batch(head_round, batch);
+        }
+    }
+
+    /// The round of the most recently ordered head, or `None` if nothing was ordered yet.
+    pub fn highest_ordered_round(&self) -> Option<Round> {
+        self.extender.highest_ordered_round()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cmp::Ordering as CmpOrdering, sync::Arc};
+
+    use crate::{
+        dag::{DagUnit, ReconstructedUnit},
+        extension::{
+            extender::Extender,
+            units::{BatchOrder, BatchPosition},
+            ElectionConfig, OrderedUnit, Ordering,
+        },
+        units::{random_full_parent_units_up_to, Unit},
+        FinalizationHandler as FinalizationHandlerT, NodeCount, NodeMap, Round, Signed,
+    };
+    use aleph_bft_mock::{Data, FinalizationHandler, Hasher64, Keychain};
+    use parking_lot::Mutex;
+
+    fn signed_dag(
+        n_members: NodeCount,
+        max_round: Round,
+    ) -> Vec<DagUnit<Hasher64, Data, Keychain>> {
+        let mut dag = Vec::new();
+        let mut parents = NodeMap::with_size(n_members);
+        for units in random_full_parent_units_up_to(max_round, n_members, 0) {
+            let mut round_hashes = NodeMap::with_size(n_members);
+            for unit in units {
+                round_hashes.insert(unit.creator(), unit.hash());
+                let round = unit.round();
+                let keychain = Keychain::new(n_members, unit.creator());
+                let unit = Signed::sign(unit, &keychain);
+                dag.push(match round {
+                    0 => ReconstructedUnit::initial(unit),
+                    _ => ReconstructedUnit::with_parents(unit, parents.clone())
+                        .expect("correct parents"),
+                });
+            }
+            parents = round_hashes;
+        }
+        dag
+    }
+
+    #[test]
+    fn finalizes_data_in_order_of_heads() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let dag = signed_dag(n_members, max_round);
+        let mut extender = Extender::new(n_members);
+        let ordered: Vec<OrderedUnit<_, Data>> = dag
+            .iter()
+            .flat_map(|unit| extender.add_unit(unit.clone()))
+            .flatten()
+            .map(OrderedUnit::from)
+            .collect();
+        assert!(!ordered.is_empty());
+
+        let (finalization_handler, mut finalized_rx) = FinalizationHandler::new();
+        let mut ordering = Ordering::new(
+            n_members,
+            finalization_handler,
+            ElectionConfig::default(),
+            max_round,
+        );
+        for unit in dag {
+            ordering.add_unit(unit);
+        }
+        let mut finalized = Vec::new();
+        while let Ok(Some(data)) = finalized_rx.try_next() {
+            finalized.push(data);
+        }
+        let expected: Vec<_> = ordered.into_iter().filter_map(|unit| unit.data).collect();
+        assert_eq!(finalized, expected);
+    }
+
+    // Puts the head first, so it is not the last unit of its batch.
+    struct ReversedBatchOrder;
+
+    impl BatchOrder for ReversedBatchOrder {
+        fn compare(&self, a: &BatchPosition, b: &BatchPosition) -> CmpOrdering {
+            b.canonical_index.cmp(&a.canonical_index)
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct SessionEndCounter(Arc<Mutex<usize>>);
+
+    impl FinalizationHandlerT<Data> for SessionEndCounter {
+        fn data_finalized(&mut self, _data: Data) {}
+
+        fn session_finished(&mut self) {
+            *self.0.lock() += 1;
+        }
+    }
+
+    #[test]
+    fn finishes_session_with_non_canonical_batch_order() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let counter = SessionEndCounter::default();
+        let config = ElectionConfig::default().with_batch_order(Arc::new(ReversedBatchOrder));
+        let mut ordering = Ordering::new(n_members, counter.clone(), config, max_round);
+        for unit in signed_dag(n_members, max_round) {
+            ordering.add_unit(unit);
         }
+        assert_eq!(*counter.0.lock(), 1);
     }
 }
@@ -0,0 +1,133 @@
+use std::borrow::Cow;
+
+use crate::{
+    extension::{
+        election::{CommonVote, ElectionResult, RoundElection, Threshold, Weight},
+        units::Units,
+    },
+    units::{
+        random_full_parent_reconstrusted_units_up_to, random_reconstructed_unit_with_parents,
+        TestingDagUnit, Unit, UnitWithParents,
+    },
+    Hasher, NodeCount, NodeIndex, Round, SessionId,
+};
+use aleph_bft_mock::Hash64;
+
+pub struct AlwaysForCommonVote;
+
+impl CommonVote for AlwaysForCommonVote {
+    fn vote(&self, _relative_round: Round) -> bool {
+        true
+    }
+}
+
+pub struct AlwaysAgainstCommonVote;
+
+impl CommonVote for AlwaysAgainstCommonVote {
+    fn vote(&self, _relative_round: Round) -> bool {
+        false
+    }
+}
+
+// Stricter than the requirements on parents of units, so that units can have too few parents to vote.
+pub struct UnanimousThreshold;
+
+impl Threshold for UnanimousThreshold {
+    fn supermajority(&self, total: Weight) -> Weight {
+        total
+    }
+}
+
+impl<U: UnitWithParents> ElectionResult<U> {
+    pub fn expect_pending(self, msg: &str) -> RoundElection<U> {
+        match self {
+            ElectionResult::Pending(election) => election,
+            _ => panic!("{}", msg),
+        }
+    }
+
+    pub fn expect_elected(self, msg: &str) -> <U::Hasher as Hasher>::Hash {
+        match self {
+            ElectionResult::Elected(head) => head,
+            _ => panic!("{}", msg),
+        }
+    }
+
+    pub fn expect_elected_immediately(self, msg: &str) -> <U::Hasher as Hasher>::Hash {
+        match self {
+            ElectionResult::ElectedImmediately(head) => head,
+            _ => panic!("{}", msg),
+        }
+    }
+}
+
+// Creates a DAG starting with random initial units sorted by hash, so that the first of them is the first candidate.
+// Each layer lists the parents, as indices in the previous round, of the consecutive units of the next round.
+pub fn dag_with_parents(
+    n_members: NodeCount,
+    layers: Vec<Vec<Vec<usize>>>,
+) -> Vec<Vec<TestingDagUnit>> {
+    let mut initial_units = random_full_parent_reconstrusted_units_up_to(0, n_members, 43)
+        .pop()
+        .expect("initial round present");
+    initial_units.sort_by_key(|unit| unit.hash());
+    let mut result = vec![initial_units];
+    for layer in layers {
+        let round_units = result.last().expect("previous round present");
+        let next_round_units = layer
+            .iter()
+            .enumerate()
+            .map(|(creator, parents)| {
+                let parents = parents.iter().map(|i| round_units[*i].clone()).collect();
+                random_reconstructed_unit_with_parents(NodeIndex(creator), &parents)
+            })
+            .collect();
+        result.push(next_round_units);
+    }
+    result
+}
+
+// Creates units up to `max_round` in which the creator of the first candidate of round 0 goes silent after creating it,
+// so that this candidate gets eliminated. Returns the units together with the hashes of the candidates in order.
+pub fn units_with_unpopular_candidate(
+    n_members: NodeCount,
+    max_round: Round,
+    session_id: SessionId,
+) -> (Units<TestingDagUnit>, Vec<Hash64>) {
+    let mut units = Units::new(n_members);
+    for unit in random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)
+        .last()
+        .expect("just created")
+    {
+        units.add_unit(unit.clone()).expect("unit is valid");
+    }
+    let mut candidate_hashes: Vec<_> = units
+        .in_round(0)
+        .expect("just added these")
+        .iter()
+        .map(|candidate| candidate.hash())
+        .collect();
+    candidate_hashes.sort();
+    let inactive_node = units
+        .get(&candidate_hashes[0])
+        .expect("we just got it")
+        .creator();
+    for round in 1..=max_round {
+        let parents: Vec<TestingDagUnit> = units
+            .in_round(round - 1)
+            .expect("created in order")
+            .into_iter()
+            .filter(|unit| unit.creator() != inactive_node)
+            .map(Cow::into_owned)
+            .collect();
+        for creator in n_members
+            .into_iterator()
+            .filter(|node_id| node_id != &inactive_node)
+        {
+            units
+                .add_unit(random_reconstructed_unit_with_parents(creator, &parents))
+                .expect("unit is valid");
+        }
+    }
+    (units, candidate_hashes)
+}
@@ -1,71 +1,438 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::RangeInclusive,
+};
 
 use crate::{
-    units::{HashFor, UnitWithParents},
-    Round,
+    extension::election::ElectionConfig,
+    units::{HashFor, UnitCoord, UnitWithParents},
+    Hasher, NodeCount, NodeIndex, Round, SessionId,
 };
+use thiserror::Error;
 
-/// Units kept in a way optimized for easy batch extraction.
-pub struct Units<U: UnitWithParents> {
+/// A round in which there are no units, despite having units of both lower and higher rounds.
+#[derive(Eq, Error, Debug, PartialEq)]
+#[error(
+    "No units of round {round}, even though there are units of rounds both below and above it."
+)]
+pub struct RoundGap {
+    pub round: Round,
+}
+
+/// A unit that cannot take part in elections.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum InvalidUnit {
+    #[error("Unit of round {round} has only {} parents out of {}.", .parents.0, .n_members.0)]
+    NotEnoughParents {
+        round: Round,
+        parents: NodeCount,
+        n_members: NodeCount,
+    },
+    #[error("Unit {0} has a control hash not matching its parents.")]
+    WrongControlHash(UnitCoord),
+    #[error("Unit {coord} has parents for {} members, not {}.", .got.0, .expected.0)]
+    WrongMemberCount {
+        coord: UnitCoord,
+        expected: NodeCount,
+        got: NodeCount,
+    },
+    #[error("Unit {0} is below round {1}, whose units were already pruned.")]
+    Pruned(UnitCoord, Round),
+    #[error("Unit {coord} is from session {got}, not {expected}.")]
+    WrongSession {
+        coord: UnitCoord,
+        expected: SessionId,
+        got: SessionId,
+    },
+}
+
+/// Checks that the parents of the unit agree with its control hash, and that units above round 0
+/// have at least the supermajority of parents, as the voting assumes that.
+pub fn validate_parents<U: UnitWithParents>(
+    unit: &U,
+    n_members: NodeCount,
+) -> Result<(), InvalidUnit> {
+    if !unit.verify_control_hash() {
+        return Err(InvalidUnit::WrongControlHash(unit.coord()));
+    }
+    let parents = NodeCount(unit.parents().item_count());
+    match unit.round() {
+        0 => Ok(()),
+        round if !parents.is_supermajority(n_members) => Err(InvalidUnit::NotEnoughParents {
+            round,
+            parents,
+            n_members,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// What happened when merging another collection of units into ours.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MergeReport<H: Hasher> {
+    /// The number of units we did not have, which got added.
+    pub added: usize,
+    /// The number of units we already had.
+    pub skipped: usize,
+    /// Units we already had, but with different contents under the same hash, in which case we keep ours.
+    /// This means either a hash collision or corrupted data, so it should never happen.
+    pub conflicts: Vec<H::Hash>,
+    /// Coordinates with more than one unit after merging, at least one of them added during the merge.
+    /// All these units are kept.
+    pub equivocations: Vec<UnitCoord>,
+    /// The number of units from a session other than ours, which did not get added.
+    pub wrong_session: usize,
+    /// The number of units of rounds we already pruned, which did not get added.
+    pub pruned: usize,
+}
+
+/// A unit within a batch, as compared by a [`BatchOrder`].
+pub struct BatchPosition<'a> {
+    pub round: Round,
+    pub creator: NodeIndex,
+    pub hash: &'a [u8],
+    /// The position of the unit in the canonical order of the batch.
+    pub canonical_index: usize,
+}
+
+/// The order of the units within a batch, i.e. of the units below a newly elected head that were not ordered before.
+/// It has to be deterministic, so that all the nodes using it produce identical batches,
+/// and it should put every unit after its parents.
+pub trait BatchOrder: Send + Sync {
+    fn compare(&self, a: &BatchPosition, b: &BatchPosition) -> Ordering;
+}
+
+/// The canonical order of the protocol, the reversed breadth-first traversal of the batch starting at its head.
+pub struct CanonicalBatchOrder;
+
+impl BatchOrder for CanonicalBatchOrder {
+    fn compare(&self, a: &BatchPosition, b: &BatchPosition) -> Ordering {
+        a.canonical_index.cmp(&b.canonical_index)
+    }
+}
+
+/// Orders the units by rounds, and units of the same round by their creators.
+pub struct RoundCreatorBatchOrder;
+
+impl BatchOrder for RoundCreatorBatchOrder {
+    fn compare(&self, a: &BatchPosition, b: &BatchPosition) -> Ordering {
+        // Forks have the same round and creator, so they stay in the canonical order.
+        (a.round, a.creator, a.canonical_index).cmp(&(b.round, b.creator, b.canonical_index))
+    }
+}
+
+/// Orders the units by rounds, and units of the same round by their hashes.
+pub struct RoundHashBatchOrder;
+
+impl BatchOrder for RoundHashBatchOrder {
+    fn compare(&self, a: &BatchPosition, b: &BatchPosition) -> Ordering {
+        (a.round, a.hash).cmp(&(b.round, b.hash))
+    }
+}
+
+/// Where the units themselves are kept, e.g. on disk to load them only when needed.
+pub trait UnitStorage<U: UnitWithParents> {
+    /// Keep the unit.
+    fn insert(&mut self, unit: U);
+    /// The kept unit with the given hash, possibly loaded just now.
+    fn get(&self, hash: &HashFor<U>) -> Option<Cow<'_, U>>;
+    /// Stop keeping the unit with the given hash, returning it.
+    fn remove(&mut self, hash: &HashFor<U>) -> Option<U>;
+}
+
+/// Keeps all the units in memory.
+#[derive(Clone)]
+pub struct InMemoryStorage<U: UnitWithParents> {
     units: HashMap<HashFor<U>, U>,
+}
+
+impl<U: UnitWithParents> UnitStorage<U> for InMemoryStorage<U> {
+    fn insert(&mut self, unit: U) {
+        self.units.insert(unit.hash(), unit);
+    }
+
+    fn get(&self, hash: &HashFor<U>) -> Option<Cow<'_, U>> {
+        self.units.get(hash).map(Cow::Borrowed)
+    }
+
+    fn remove(&mut self, hash: &HashFor<U>) -> Option<U> {
+        self.units.remove(hash)
+    }
+}
+
+/// Units kept in a way optimized for easy batch extraction.
+/// Only the hashes of the units are indexed in memory, the units themselves are in the storage.
+#[derive(Clone)]
+pub struct Units<U: UnitWithParents, S: UnitStorage<U> = InMemoryStorage<U>> {
+    storage: S,
     by_round: HashMap<Round, Vec<HashFor<U>>>,
     highest_round: Round,
+    n_members: NodeCount,
+    session_id: Option<SessionId>,
+    // Units of rounds below this one were pruned.
+    pruned_below: Round,
 }
 
 impl<U: UnitWithParents> Units<U> {
-    /// Create empty unit store.
-    pub fn new() -> Self {
+    /// Create empty unit store for units of a committee with `n_members` members.
+    pub fn new(n_members: NodeCount) -> Self {
+        Self::with_storage(
+            InMemoryStorage {
+                units: HashMap::new(),
+            },
+            n_members,
+        )
+    }
+}
+
+impl<U: UnitWithParents, S: UnitStorage<U>> Units<U, S> {
+    /// Create a unit store keeping the units of a committee with `n_members` members in the provided storage,
+    /// which should be empty.
+    pub fn with_storage(storage: S, n_members: NodeCount) -> Self {
         Units {
-            units: HashMap::new(),
+            storage,
             by_round: HashMap::new(),
             highest_round: 0,
+            n_members,
+            session_id: None,
+            pruned_below: 0,
         }
     }
 
-    /// Add a unit to the store.
-    pub fn add_unit(&mut self, u: U) {
+    /// The size of the committee the units come from.
+    pub fn n_members(&self) -> NodeCount {
+        self.n_members
+    }
+
+    /// Only accept units of the given session, so units of other sessions never take part in its elections.
+    pub fn with_session(self, session_id: SessionId) -> Self {
+        Units {
+            session_id: Some(session_id),
+            ..self
+        }
+    }
+
+    /// Add a unit to the store, unless it is invalid.
+    pub fn add_unit(&mut self, u: U) -> Result<(), InvalidUnit> {
+        match self.session_id {
+            Some(expected) if u.session_id() != expected => {
+                return Err(InvalidUnit::WrongSession {
+                    coord: u.coord(),
+                    expected,
+                    got: u.session_id(),
+                })
+            }
+            _ => (),
+        }
+        if u.parents().size() != self.n_members {
+            return Err(InvalidUnit::WrongMemberCount {
+                coord: u.coord(),
+                expected: self.n_members,
+                got: u.parents().size(),
+            });
+        }
+        validate_parents(&u, self.n_members)?;
         let round = u.round();
+        if round < self.pruned_below {
+            return Err(InvalidUnit::Pruned(u.coord(), self.pruned_below));
+        }
         if round > self.highest_round {
             self.highest_round = round;
         }
 
         self.by_round.entry(round).or_default().push(u.hash());
-        self.units.insert(u.hash(), u);
+        self.storage.insert(u);
+        Ok(())
     }
 
-    pub fn get(&self, hash: &HashFor<U>) -> Option<&U> {
-        self.units.get(hash)
+    pub fn get(&self, hash: &HashFor<U>) -> Option<Cow<'_, U>> {
+        self.storage.get(hash)
     }
 
-    /// Get the list of unit hashes from the given round.
+    /// Add all the units from the other collection that we do not have yet, e.g. when reconciling
+    /// a local backup with units exported by a peer.
+    pub fn merge<T: UnitStorage<U>>(&mut self, other: Units<U, T>) -> MergeReport<U::Hasher>
+    where
+        U: PartialEq,
+    {
+        assert_eq!(
+            self.n_members, other.n_members,
+            "merging units of different committees"
+        );
+        let mut report = MergeReport {
+            added: 0,
+            skipped: 0,
+            conflicts: Vec::new(),
+            equivocations: Vec::new(),
+            wrong_session: 0,
+            pruned: 0,
+        };
+        for unit in other.into_units() {
+            if matches!(self.session_id, Some(session_id) if unit.session_id() != session_id) {
+                report.wrong_session += 1;
+                continue;
+            }
+            if unit.round() < self.pruned_below {
+                report.pruned += 1;
+                continue;
+            }
+            let hash = unit.hash();
+            if let Some(ours) = self.get(&hash) {
+                match *ours == unit {
+                    true => report.skipped += 1,
+                    false => report.conflicts.push(hash),
+                }
+                continue;
+            }
+            let coord = unit.coord();
+            if self
+                .units_in_round(coord.round())
+                .any(|ours| ours.creator() == coord.creator())
+                && !report.equivocations.contains(&coord)
+            {
+                report.equivocations.push(coord);
+            }
+            self.add_unit(unit)
+                .expect("units of the other collection passed the same validation");
+            report.added += 1;
+        }
+        report
+    }
+
+    /// Get the list of unit hashes from the given round, without loading the units.
+    pub fn hashes_in_round(&self, round: Round) -> Option<&Vec<HashFor<U>>> {
+        self.by_round.get(&round)
+    }
+
+    /// Get the list of units from the given round.
     /// Panics if called for a round greater or equal to the round
     /// of the highest head of a removed batch.
-    pub fn in_round(&self, round: Round) -> Option<Vec<&U>> {
+    pub fn in_round(&self, round: Round) -> Option<Vec<Cow<'_, U>>> {
         self.by_round.get(&round).map(|hashes| {
             hashes
                 .iter()
-                .map(|hash| self.units.get(hash).expect("we have all the units"))
+                .map(|hash| self.storage.get(hash).expect("we have all the units"))
                 .collect()
         })
     }
 
+    /// All the units created by the given creator, in order of rounds.
+    pub fn by_creator(&self, creator: NodeIndex) -> impl Iterator<Item = Cow<'_, U>> {
+        (self.lowest_round()..=self.highest_round())
+            .filter_map(|round| self.by_round.get(&round))
+            .flatten()
+            .filter_map(|hash| self.storage.get(hash))
+            .filter(move |unit| unit.creator() == creator)
+    }
+
+    fn units_in_round(&self, round: Round) -> impl Iterator<Item = Cow<'_, U>> {
+        self.by_round
+            .get(&round)
+            .into_iter()
+            .flatten()
+            .filter_map(|hash| self.storage.get(hash))
+    }
+
+    /// The number of distinct creators of units of the given round.
+    pub fn round_coverage(&self, round: Round) -> NodeCount {
+        let creators: HashSet<_> = self
+            .units_in_round(round)
+            .map(|unit| unit.creator())
+            .collect();
+        NodeCount(creators.len())
+    }
+
+    /// The average number of parents of units of the given round, or 0 if there are none.
+    pub fn round_density(&self, round: Round) -> f64 {
+        let (units, parents) = self
+            .units_in_round(round)
+            .fold((0, 0), |(units, parents), unit| {
+                (units + 1, parents + unit.parents().item_count())
+            });
+        match units {
+            0 => 0.0,
+            units => parents as f64 / units as f64,
+        }
+    }
+
     /// The highest round among all added units, or 0 if there are none.
     pub fn highest_round(&self) -> Round {
         self.highest_round
     }
 
+    /// The lowest round among all added units, or 0 if there are none.
+    pub fn lowest_round(&self) -> Round {
+        self.by_round.keys().min().cloned().unwrap_or(0)
+    }
+
+    /// The rounds for which an election with the given configuration can be started, i.e. the ones with units
+    /// of at least its start offset of rounds above them. Starts at the lowest round, since there are no candidates
+    /// below it. Empty if there are no such rounds.
+    pub fn electable_rounds(&self, config: &ElectionConfig) -> RangeInclusive<Round> {
+        match self.highest_round().checked_sub(config.start_offset()) {
+            Some(last) if !self.by_round.is_empty() => self.lowest_round()..=last,
+            _ => RangeInclusive::new(1, 0),
+        }
+    }
+
+    /// Checks whether we have units of all the rounds between the lowest and the highest one.
+    /// Returns the first round without units otherwise.
+    pub fn check_contiguous(&self) -> Result<(), RoundGap> {
+        if self.by_round.is_empty() {
+            return Ok(());
+        }
+        match (self.lowest_round()..=self.highest_round())
+            .find(|round| !self.by_round.contains_key(round))
+        {
+            Some(round) => Err(RoundGap { round }),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove all units of rounds lower than the given one, to bound memory usage.
+    /// The caller has to ensure that no units of these rounds will be needed anymore,
+    /// i.e. there are no pending elections for these rounds and none of these units
+    /// will be a part of a future batch. Units of these rounds added later are rejected.
+    pub fn prune_below(&mut self, round: Round) {
+        let pruned_rounds: Vec<_> = self
+            .by_round
+            .keys()
+            .filter(|unit_round| **unit_round < round)
+            .cloned()
+            .collect();
+        for pruned_round in pruned_rounds {
+            for hash in self.by_round.remove(&pruned_round).unwrap_or_default() {
+                self.storage.remove(&hash);
+            }
+        }
+        self.pruned_below = self.pruned_below.max(round);
+    }
+
+    /// All the remaining units, in order of rounds.
+    pub fn into_units(mut self) -> Vec<U> {
+        let mut rounds: Vec<_> = self.by_round.drain().collect();
+        rounds.sort_by_key(|(round, _)| *round);
+        rounds
+            .into_iter()
+            .flat_map(|(_, hashes)| hashes)
+            .filter_map(|hash| self.storage.remove(&hash))
+            .collect()
+    }
+
     /// Remove a batch of units, deterministically ordered based on the given head.
     pub fn remove_batch(&mut self, head: &HashFor<U>) -> Vec<U> {
         let mut batch = Vec::new();
         let mut queue = VecDeque::new();
         queue.push_back(
-            self.units
+            self.storage
                 .remove(head)
                 .expect("head is picked among units we have"),
         );
         while let Some(u) = queue.pop_front() {
             for u_hash in u.parents().clone().into_values() {
-                if let Some(v) = self.units.remove(&u_hash) {
+                if let Some(v) = self.storage.remove(&u_hash) {
                     queue.push_back(v);
                 }
             }
@@ -77,39 +444,118 @@ impl<U: UnitWithParents> Units<U> {
         batch.reverse();
         batch
     }
+
+    /// Remove a batch of units, like `remove_batch`, but ordered using the provided order.
+    pub fn remove_batch_with_order(&mut self, head: &HashFor<U>, order: &dyn BatchOrder) -> Vec<U> {
+        let batch = self.remove_batch(head);
+        Self::apply_order(batch, order)
+    }
+
+    /// The batch of units `remove_batch_with_order` would return if the units in `ordered` were already removed,
+    /// without removing anything, e.g. when the units are shared with someone else.
+    pub fn batch_with_order(
+        &self,
+        head: &HashFor<U>,
+        ordered: &HashSet<HashFor<U>>,
+        order: &dyn BatchOrder,
+    ) -> Vec<U> {
+        let mut batch = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(*head);
+        queue.push_back(
+            self.storage
+                .get(head)
+                .expect("head is picked among units we have")
+                .into_owned(),
+        );
+        while let Some(u) = queue.pop_front() {
+            for u_hash in u.parents().values() {
+                if ordered.contains(u_hash) || !visited.insert(*u_hash) {
+                    continue;
+                }
+                if let Some(v) = self.storage.get(u_hash) {
+                    queue.push_back(v.into_owned());
+                }
+            }
+            batch.push(u);
+        }
+        // Same traversal as in `remove_batch`, so the canonical order is the same.
+        batch.reverse();
+        Self::apply_order(batch, order)
+    }
+
+    fn apply_order(batch: Vec<U>, order: &dyn BatchOrder) -> Vec<U> {
+        let hashes: Vec<_> = batch.iter().map(|unit| unit.hash()).collect();
+        let mut positions: Vec<_> = batch
+            .iter()
+            .zip(&hashes)
+            .enumerate()
+            .map(|(canonical_index, (unit, hash))| BatchPosition {
+                round: unit.round(),
+                creator: unit.creator(),
+                hash: hash.as_ref(),
+                canonical_index,
+            })
+            .collect();
+        positions.sort_by(|a, b| order.compare(a, b));
+        let order: Vec<_> = positions
+            .into_iter()
+            .map(|position| position.canonical_index)
+            .collect();
+        let mut batch: Vec<_> = batch.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .map(|index| batch[index].take().expect("every index appears once"))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::borrow::Cow;
+
     use crate::{
-        extension::units::Units,
-        units::{random_full_parent_reconstrusted_units_up_to, TestingDagUnit, Unit},
-        NodeCount,
+        extension::{
+            election::{ElectionConfig, ElectionResult, RoundElection},
+            units::{CanonicalBatchOrder, InvalidUnit, MergeReport, RoundGap, Units},
+        },
+        units::{
+            random_full_parent_reconstrusted_units_up_to, random_reconstructed_unit_with_parents,
+            ControlHash, TestingDagUnit, Unit, UnitCoord, UnitWithParents,
+        },
+        NodeCount, NodeIndex, NodeMap, SessionId,
     };
+    use aleph_bft_mock::{Hash64, Hasher64};
 
     #[test]
     fn initially_empty() {
-        let units = Units::<TestingDagUnit>::new();
+        let units = Units::<TestingDagUnit>::new(NodeCount(4));
         assert!(units.in_round(0).is_none());
         assert_eq!(units.highest_round(), 0);
+        assert_eq!(units.lowest_round(), 0);
+        assert_eq!(units.check_contiguous(), Ok(()));
+        assert!(units
+            .electable_rounds(&ElectionConfig::default())
+            .is_empty());
     }
 
     #[test]
     fn accepts_unit() {
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let session_id = 2137;
         let unit = &random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)[0][0];
-        units.add_unit(unit.clone());
+        units.add_unit(unit.clone()).expect("unit is valid");
         assert_eq!(units.highest_round(), 0);
-        assert_eq!(units.in_round(0), Some(vec![unit]));
-        assert_eq!(units.get(&unit.hash()), Some(unit));
+        assert_eq!(units.in_round(0), Some(vec![Cow::Borrowed(unit)]));
+        assert_eq!(units.get(&unit.hash()), Some(Cow::Borrowed(unit)));
     }
 
     #[test]
     fn returns_batches_all_parents() {
-        let mut units = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
         let max_round = 43;
         let session_id = 2137;
         let mut heads = Vec::new();
@@ -120,7 +566,7 @@ mod test {
         {
             heads.push(round_units[round % n_members.0].clone());
             for unit in round_units {
-                units.add_unit(unit);
+                units.add_unit(unit).expect("unit is valid");
             }
         }
         assert_eq!(units.highest_round(), max_round);
@@ -133,9 +579,9 @@ mod test {
 
     #[test]
     fn batch_order_constant_with_different_insertion_order() {
-        let mut units = Units::new();
-        let mut units_but_backwards = Units::new();
         let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let mut units_but_backwards = Units::new(n_members);
         let max_round = 43;
         let session_id = 2137;
         let mut heads = Vec::new();
@@ -146,10 +592,10 @@ mod test {
         {
             heads.push(round_units[round % n_members.0].clone());
             for unit in &round_units {
-                units.add_unit(unit.clone());
+                units.add_unit(unit.clone()).expect("unit is valid");
             }
             for unit in round_units.into_iter().rev() {
-                units_but_backwards.add_unit(unit);
+                units_but_backwards.add_unit(unit).expect("unit is valid");
             }
         }
         for head in heads {
@@ -158,4 +604,439 @@ mod test {
             assert_eq!(batch1, batch2);
         }
     }
+
+    #[test]
+    fn canonical_batch_order_matches_plain_batches() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let dag = random_full_parent_reconstrusted_units_up_to(10, n_members, 2137);
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+        }
+        let mut other_units = units.clone();
+        for (round, round_units) in dag.iter().enumerate() {
+            let head = round_units[round % n_members.0].hash();
+            assert_eq!(
+                units.remove_batch(&head),
+                other_units.remove_batch_with_order(&head, &CanonicalBatchOrder)
+            );
+        }
+    }
+
+    #[test]
+    fn reports_round_gap() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 5;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+                .into_iter()
+                .skip(1)
+                .filter(|round_units| round_units[0].round() != 3)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        assert_eq!(units.lowest_round(), 1);
+        assert_eq!(units.highest_round(), max_round);
+        assert_eq!(units.check_contiguous(), Err(RoundGap { round: 3 }));
+    }
+
+    #[test]
+    fn contiguous_rounds_pass_check() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 5;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        assert_eq!(units.lowest_round(), 0);
+        assert_eq!(units.check_contiguous(), Ok(()));
+    }
+
+    #[test]
+    fn prunes_lower_rounds() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 10;
+        let prune_round = 4;
+        let session_id = 2137;
+        for round_units in
+            random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id)
+        {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+        }
+        let pruned_unit = units.in_round(prune_round - 1).expect("we have the units")[0].hash();
+        let kept_unit = units.in_round(prune_round).expect("we have the units")[0].hash();
+        units.prune_below(prune_round);
+        let kept_rounds = usize::from(max_round - prune_round + 1);
+        assert_eq!(units.storage.units.len(), kept_rounds * n_members.0);
+        assert_eq!(units.by_round.len(), kept_rounds);
+        assert!(units.in_round(prune_round - 1).is_none());
+        assert!(units.get(&pruned_unit).is_none());
+        assert!(units.get(&kept_unit).is_some());
+        assert_eq!(units.highest_round(), max_round);
+        assert_eq!(units.lowest_round(), prune_round);
+        let late_unit = random_full_parent_reconstrusted_units_up_to(1, n_members, session_id)
+            .pop()
+            .expect("round 1 present")
+            .pop()
+            .expect("units present");
+        assert_eq!(
+            units.add_unit(late_unit.clone()),
+            Err(InvalidUnit::Pruned(late_unit.coord(), prune_round))
+        );
+        match RoundElection::for_round(prune_round, &units, &ElectionConfig::default())
+            .expect("we have enough rounds")
+        {
+            ElectionResult::Pending(_)
+            | ElectionResult::Elected(_)
+            | ElectionResult::Stalled { .. }
+            | ElectionResult::NoHead
+            | ElectionResult::Aborted(_) => {
+                panic!("should have elected")
+            }
+            ElectionResult::ElectedImmediately(head) => {
+                assert_eq!(
+                    units.get(&head).expect("we have the head").round(),
+                    prune_round
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn returns_units_by_creator() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let max_round = 7;
+        let session_id = 2137;
+        let creator = NodeIndex(1);
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        for round_units in dag.iter().rev() {
+            for unit in round_units {
+                units.add_unit(unit.clone()).expect("unit is valid");
+            }
+        }
+        let expected: Vec<_> = dag
+            .iter()
+            .map(|round_units| round_units[1].clone())
+            .collect();
+        let created: Vec<_> = units.by_creator(creator).map(Cow::into_owned).collect();
+        assert_eq!(created, expected);
+        assert!(units.by_creator(NodeIndex(4)).next().is_none());
+    }
+
+    #[test]
+    fn reports_round_coverage_and_density() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let session_id = 2137;
+        let initial_units = random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)
+            .pop()
+            .expect("initial round present");
+        // The last creator is absent in the first round.
+        let first_round: Vec<_> = (0..3)
+            .map(|creator| {
+                random_reconstructed_unit_with_parents(NodeIndex(creator), &initial_units)
+            })
+            .collect();
+        let second_round: Vec<_> = n_members
+            .into_iterator()
+            .map(|creator| random_reconstructed_unit_with_parents(creator, &first_round))
+            .collect();
+        for unit in initial_units
+            .into_iter()
+            .chain(first_round)
+            .chain(second_round)
+        {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        assert_eq!(units.round_coverage(0), n_members);
+        assert_eq!(units.round_coverage(1), NodeCount(3));
+        assert_eq!(units.round_coverage(2), n_members);
+        assert_eq!(units.round_coverage(3), NodeCount(0));
+        assert_eq!(units.round_density(1), 4.0);
+        assert_eq!(units.round_density(2), 3.0);
+        assert_eq!(units.round_density(3), 0.0);
+    }
+
+    #[test]
+    fn lists_electable_rounds() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(7, n_members, session_id);
+        for (round, round_units) in dag.into_iter().enumerate() {
+            for unit in round_units {
+                units.add_unit(unit).expect("unit is valid");
+            }
+            if round < 3 {
+                assert!(units
+                    .electable_rounds(&ElectionConfig::default())
+                    .is_empty());
+            }
+        }
+        let config = ElectionConfig::default();
+        assert_eq!(units.electable_rounds(&config), 0..=4);
+        for round in units.electable_rounds(&config) {
+            assert!(RoundElection::for_round(round, &units, &config).is_ok());
+        }
+        assert!(RoundElection::for_round(5, &units, &config).is_err());
+        let later_config = ElectionConfig::default()
+            .with_start_offset(5)
+            .expect("offset is safe");
+        assert_eq!(units.electable_rounds(&later_config), 0..=2);
+        for round in units.electable_rounds(&later_config) {
+            assert!(RoundElection::for_round(round, &units, &later_config).is_ok());
+        }
+        assert!(RoundElection::for_round(3, &units, &later_config).is_err());
+        units.prune_below(2);
+        assert_eq!(units.electable_rounds(&config), 2..=4);
+    }
+
+    #[test]
+    fn rejects_unit_with_too_few_parents() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        let session_id = 2137;
+        let initial_units = random_full_parent_reconstrusted_units_up_to(0, n_members, session_id)
+            .pop()
+            .expect("initial round present");
+        let parents = initial_units[..2].to_vec();
+        let unit = random_reconstructed_unit_with_parents(NodeIndex(0), &parents);
+        for unit in initial_units {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        assert_eq!(
+            units.add_unit(unit.clone()),
+            Err(InvalidUnit::NotEnoughParents {
+                round: 1,
+                parents: NodeCount(2),
+                n_members,
+            })
+        );
+        assert!(units.get(&unit.hash()).is_none());
+        assert!(units.in_round(1).is_none());
+        assert_eq!(units.highest_round(), 0);
+    }
+
+    // A unit claiming parents other than the ones its control hash commits to.
+    #[derive(Clone, Debug, PartialEq)]
+    struct ReparentedUnit {
+        unit: TestingDagUnit,
+        parents: NodeMap<Hash64>,
+    }
+
+    impl Unit for ReparentedUnit {
+        type Hasher = Hasher64;
+
+        fn hash(&self) -> Hash64 {
+            self.unit.hash()
+        }
+
+        fn coord(&self) -> UnitCoord {
+            self.unit.coord()
+        }
+
+        fn control_hash(&self) -> &ControlHash<Hasher64> {
+            self.unit.control_hash()
+        }
+
+        fn session_id(&self) -> SessionId {
+            self.unit.session_id()
+        }
+    }
+
+    impl UnitWithParents for ReparentedUnit {
+        fn parents(&self) -> &NodeMap<Hash64> {
+            &self.parents
+        }
+    }
+
+    #[test]
+    fn rejects_unit_with_mis_sized_parents() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members);
+        // All the parents of a bigger committee are still a supermajority of ours.
+        let unit = random_full_parent_reconstrusted_units_up_to(1, NodeCount(5), 2137)
+            .pop()
+            .expect("round 1 present")
+            .pop()
+            .expect("units of round 1 present");
+        assert_eq!(
+            units.add_unit(unit.clone()),
+            Err(InvalidUnit::WrongMemberCount {
+                coord: unit.coord(),
+                expected: n_members,
+                got: NodeCount(5),
+            })
+        );
+        assert!(units.get(&unit.hash()).is_none());
+        assert!(units.in_round(1).is_none());
+    }
+
+    #[test]
+    fn rejects_unit_of_other_session() {
+        let n_members = NodeCount(4);
+        let mut units = Units::new(n_members).with_session(2);
+        let old_unit = random_full_parent_reconstrusted_units_up_to(0, n_members, 1)
+            .pop()
+            .expect("initial round present")
+            .pop()
+            .expect("initial units present");
+        assert_eq!(
+            units.add_unit(old_unit.clone()),
+            Err(InvalidUnit::WrongSession {
+                coord: old_unit.coord(),
+                expected: 2,
+                got: 1,
+            })
+        );
+        assert!(units.get(&old_unit.hash()).is_none());
+        assert!(units.in_round(0).is_none());
+        for unit in random_full_parent_reconstrusted_units_up_to(0, n_members, 2)
+            .pop()
+            .expect("initial round present")
+        {
+            units.add_unit(unit).expect("unit is valid");
+        }
+        assert_eq!(
+            units.in_round(0).expect("units of round 0").len(),
+            n_members.0
+        );
+    }
+
+    #[test]
+    fn accepts_unit_with_matching_control_hash() {
+        let dag = random_full_parent_reconstrusted_units_up_to(1, NodeCount(4), 2137);
+        let mut units = Units::new(NodeCount(4));
+        for unit in dag.into_iter().flatten() {
+            assert!(unit.verify_control_hash());
+            units.add_unit(unit).expect("unit is valid");
+        }
+        assert_eq!(units.highest_round(), 1);
+    }
+
+    #[test]
+    fn rejects_unit_with_tampered_parents() {
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(1, n_members, 2137);
+        let mut units = Units::new(n_members);
+        for unit in &dag[0] {
+            let unit = ReparentedUnit {
+                unit: unit.clone(),
+                parents: unit.parents().clone(),
+            };
+            units.add_unit(unit).expect("unit is valid");
+        }
+        let unit = dag[1][0].clone();
+        // Still enough parents, but one of them got replaced.
+        let mut parents = unit.parents().clone();
+        parents.insert(NodeIndex(3), dag[0][0].hash());
+        let tampered = ReparentedUnit { unit, parents };
+        assert!(!tampered.verify_control_hash());
+        assert_eq!(
+            units.add_unit(tampered.clone()),
+            Err(InvalidUnit::WrongControlHash(tampered.coord()))
+        );
+        assert!(units.get(&tampered.hash()).is_none());
+        assert_eq!(units.highest_round(), 0);
+    }
+
+    // A unit with a label not affecting its hash, to simulate hash collisions.
+    #[derive(Clone, Debug, PartialEq)]
+    struct LabelledUnit {
+        unit: TestingDagUnit,
+        label: u8,
+    }
+
+    impl Unit for LabelledUnit {
+        type Hasher = Hasher64;
+
+        fn hash(&self) -> Hash64 {
+            self.unit.hash()
+        }
+
+        fn coord(&self) -> UnitCoord {
+            self.unit.coord()
+        }
+
+        fn control_hash(&self) -> &ControlHash<Hasher64> {
+            self.unit.control_hash()
+        }
+
+        fn session_id(&self) -> SessionId {
+            self.unit.session_id()
+        }
+    }
+
+    impl UnitWithParents for LabelledUnit {
+        fn parents(&self) -> &NodeMap<Hash64> {
+            self.unit.parents()
+        }
+    }
+
+    #[test]
+    fn merges_overlapping_collections() {
+        let n_members = NodeCount(4);
+        let dag = random_full_parent_reconstrusted_units_up_to(3, n_members, 2137);
+        let labelled = |unit: &TestingDagUnit, label| LabelledUnit {
+            unit: unit.clone(),
+            label,
+        };
+        let mut ours = Units::new(n_members);
+        for unit in dag[..3].iter().flatten() {
+            ours.add_unit(labelled(unit, 0)).expect("unit is valid");
+        }
+        let mut theirs = Units::new(n_members);
+        let conflicting = dag[2][0].clone();
+        for unit in dag[1..].iter().flatten() {
+            let label = match unit.hash() == conflicting.hash() {
+                true => 1,
+                false => 0,
+            };
+            theirs
+                .add_unit(labelled(unit, label))
+                .expect("unit is valid");
+        }
+        // Fewer parents, so that the fork differs even if its data happens to be the same.
+        let fork = random_reconstructed_unit_with_parents(NodeIndex(1), &dag[1][..3].to_vec());
+        theirs.add_unit(labelled(&fork, 0)).expect("unit is valid");
+
+        let report = ours.merge(theirs);
+        assert_eq!(
+            report,
+            MergeReport {
+                added: n_members.0 + 1,
+                skipped: 2 * n_members.0 - 1,
+                conflicts: vec![conflicting.hash()],
+                equivocations: vec![UnitCoord::new(2, NodeIndex(1))],
+                wrong_session: 0,
+                pruned: 0,
+            }
+        );
+        // We keep our version of the conflicting unit, and both units of the equivocation.
+        assert_eq!(ours.get(&conflicting.hash()).expect("still there").label, 0);
+        assert_eq!(ours.in_round(2).expect("units of round 2").len(), 5);
+        assert!(ours.get(&fork.hash()).is_some());
+        assert_eq!(ours.highest_round(), 3);
+        // Units of pruned rounds are not added back.
+        ours.prune_below(2);
+        let mut late = Units::new(n_members);
+        for unit in dag[..2].iter().flatten() {
+            late.add_unit(labelled(unit, 0)).expect("unit is valid");
+        }
+        assert_eq!(ours.merge(late).pruned, 2 * n_members.0);
+        assert!(ours.in_round(1).is_none());
+    }
 }
@@ -0,0 +1,173 @@
+use crate::{
+    extension::{
+        election::{ElectionConfig, ElectionResult, ElectionStartError, RoundElection},
+        units::{UnitStorage, Units},
+    },
+    units::{HashFor, UnitWithParents},
+    Hasher, Round,
+};
+use std::ops::RangeInclusive;
+use thiserror::Error;
+
+/// The first place where a claimed order of heads diverges from the one dictated by the units.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum OrderMismatch<H: Hasher> {
+    /// A different head was elected in the round.
+    #[error("Wrong head claimed for round {round}.")]
+    WrongHead {
+        round: Round,
+        expected: H::Hash,
+        claimed: H::Hash,
+    },
+    /// The units do not suffice to elect a head in the round.
+    #[error("No head can be elected yet in round {round}.")]
+    Undecided { round: Round },
+    /// All the candidates of the round got eliminated, or there were none.
+    #[error("There is no head in round {round}.")]
+    NoHead { round: Round },
+    /// The election of the round got aborted because of a malformed voter.
+    #[error("The election of round {round} got aborted.")]
+    Aborted { round: Round },
+}
+
+/// Independently reruns the elections of consecutive rounds starting from 0 and checks that they elect
+/// exactly the claimed heads, reporting the first round where they diverge.
+/// The units have to contain all the units relevant to the elections, including those already ordered.
+pub fn verify_order<U: UnitWithParents, S: UnitStorage<U>>(
+    units: &Units<U, S>,
+    claimed_order: &[HashFor<U>],
+    config: &ElectionConfig,
+) -> Result<(), OrderMismatch<U::Hasher>> {
+    for (round, claimed) in (0..=Round::MAX).zip(claimed_order) {
+        use ElectionResult::*;
+        match RoundElection::for_round(round, units, config) {
+            Ok(Elected(expected) | ElectedImmediately(expected)) if expected == *claimed => (),
+            Ok(Elected(expected) | ElectedImmediately(expected)) => {
+                return Err(OrderMismatch::WrongHead {
+                    round,
+                    expected,
+                    claimed: *claimed,
+                })
+            }
+            Ok(Pending(_))
+            | Ok(Stalled { .. })
+            | Err(ElectionStartError::NotEnoughRounds { .. }) => {
+                return Err(OrderMismatch::Undecided { round })
+            }
+            Ok(NoHead) | Err(ElectionStartError::NoCandidates) => {
+                return Err(OrderMismatch::NoHead { round })
+            }
+            Ok(Aborted(_)) => return Err(OrderMismatch::Aborted { round }),
+        }
+    }
+    Ok(())
+}
+
+/// Elects the head of every round in the range independently, e.g. for displaying them.
+/// The head is `None` if the units do not suffice to decide the election yet, or if the round has no head.
+/// Heads are not checked to be ordered, i.e. that the heads of earlier rounds were decided as well.
+pub fn elect_heads<U: UnitWithParents, S: UnitStorage<U>>(
+    units: &Units<U, S>,
+    rounds: RangeInclusive<Round>,
+    config: &ElectionConfig,
+) -> Vec<(Round, Option<HashFor<U>>)> {
+    rounds
+        .map(|round| {
+            use ElectionResult::*;
+            let head = match RoundElection::for_round(round, units, config) {
+                Ok(Elected(head) | ElectedImmediately(head)) => Some(head),
+                _ => None,
+            };
+            (round, head)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        extension::{
+            election::ElectionConfig,
+            extender::Extender,
+            units::Units,
+            verify::{elect_heads, verify_order, OrderMismatch},
+        },
+        units::{random_full_parent_reconstrusted_units_up_to, Unit},
+        NodeCount, Round,
+    };
+
+    #[test]
+    fn verifies_order_of_heads() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 10;
+        let session_id = 2137;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, session_id);
+        let mut extender = Extender::new(n_members);
+        let mut units = Units::new(n_members);
+        let mut heads = Vec::new();
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+            for batch in extender.add_unit(unit.clone()) {
+                heads.push(batch.last().expect("batches are not empty").hash());
+            }
+        }
+        let config = ElectionConfig::default();
+        assert_eq!(verify_order(&units, &heads, &config), Ok(()));
+        assert_eq!(verify_order(&units, &heads[..3], &config), Ok(()));
+
+        let mut swapped = heads.clone();
+        let other = dag[3]
+            .iter()
+            .map(|unit| unit.hash())
+            .find(|hash| *hash != heads[3])
+            .expect("there are other units in the round");
+        swapped[3] = other;
+        assert_eq!(
+            verify_order(&units, &swapped, &config),
+            Err(OrderMismatch::WrongHead {
+                round: 3,
+                expected: heads[3],
+                claimed: other,
+            })
+        );
+
+        let mut too_long = heads.clone();
+        too_long.push(dag[heads.len()][0].hash());
+        assert_eq!(
+            verify_order(&units, &too_long, &config),
+            Err(OrderMismatch::Undecided {
+                round: heads.len() as Round
+            })
+        );
+    }
+
+    #[test]
+    fn elects_heads_of_decidable_rounds() {
+        let n_members = NodeCount(4);
+        let max_round: Round = 4;
+        let dag = random_full_parent_reconstrusted_units_up_to(max_round, n_members, 2137);
+        let mut extender = Extender::new(n_members);
+        let mut units = Units::new(n_members);
+        let mut heads = Vec::new();
+        for unit in dag.iter().flatten() {
+            units.add_unit(unit.clone()).expect("unit is valid");
+            for batch in extender.add_unit(unit.clone()) {
+                heads.push(batch.last().expect("batches are not empty").hash());
+            }
+        }
+        // Elections need units of a few rounds above, so the last rounds are not decidable yet.
+        assert!(!heads.is_empty() && heads.len() <= max_round as usize);
+
+        let elected = elect_heads(&units, 0..=max_round, &ElectionConfig::default());
+        assert_eq!(elected.len(), max_round as usize + 1);
+        for (round, head) in elected {
+            // Exactly the rounds the extender could order are decidable.
+            assert_eq!(head, heads.get(round as usize).copied());
+        }
+        // Rounds beyond the units cannot be decided either.
+        assert_eq!(
+            elect_heads(&units, 7..=8, &ElectionConfig::default()),
+            vec![(7, None), (8, None)]
+        );
+    }
+}
@@ -3,13 +3,22 @@
 //! gives appropriate access to the set of available data that we need to make consensus on.
 
 mod alerts;
+mod availability;
+mod builder;
+mod committee;
 mod config;
 mod creation;
 mod dag;
 mod extension;
 mod member;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod network;
+mod quorum;
+mod replay;
+mod round_clock;
 mod runway;
+mod status;
 mod terminator;
 mod units;
 
@@ -19,17 +28,44 @@ mod task_queue;
 mod testing;
 
 pub use aleph_bft_types::{
-    Data, DataProvider, FinalizationHandler, Hasher, IncompleteMultisignatureError, Index, Indexed,
-    Keychain, MultiKeychain, Multisigned, Network, NodeCount, NodeIndex, NodeMap, NodeSubset,
-    PartialMultisignature, PartiallyMultisigned, Recipient, Round, SessionId, Signable, Signature,
-    SignatureError, SignatureSet, Signed, SpawnHandle, TaskHandle, UncheckedSigned,
+    Data, DataAvailability, DataProvider, FinalizationHandler, Hasher,
+    IncompleteMultisignatureError, Index, Indexed, Keychain, MultiKeychain, Multisigned, Network,
+    NodeCount, NodeIndex, NodeMap, NodeSubset, PartialMultisignature, PartiallyMultisigned,
+    Recipient, Round, SessionId, Signable, Signature, SignatureError, SignatureSet, Signed,
+    SpawnHandle, TaskHandle, UncheckedSigned,
 };
+pub use availability::{check_availability, AvailabilityCheckingHandler};
+pub use builder::{BuiltMember, MemberBuilder, MemberBuilderError};
+pub use committee::{Committee, CommitteeError, CommitteeProvider};
 pub use config::{
     create_config, default_config, default_delay_config, exponential_slowdown, Config, DelayConfig,
+    ParentMode, Randomness, SyncMode,
 };
+pub use creation::{CreatorHandle, LivenessHandle};
+pub use dag::{DagObserver, DagUnit, ParentDiversity, UnitFilter};
+#[cfg(feature = "dot")]
+pub use extension::to_dot;
+pub use extension::{
+    certify_head, elect_heads, extend_once, finalization_rounds, sign_head, verify_order,
+    BatchOrder, BatchPosition, ByzantineThreshold, CandidateOrder, CanonicalBatchOrder, CommonVote,
+    DefaultCommonVote, ElectionConfig, ElectionConfigError, ElectionResult, ElectionSnapshot,
+    Extender, HashOrder, HeadCertificate, HeadClaim, InMemoryStorage, InvalidUnit, MergeReport,
+    OrderDiff, OrderMismatch, OrderedUnitFeed, RoundCreatorBatchOrder, RoundElection, RoundGap,
+    RoundHashBatchOrder, SessionBoundary, SharedExtender, SignedHeadClaim, SnapshotError,
+    Threshold, UnitStorage, Units, Weight,
+};
+#[cfg(feature = "vote_diagnostics")]
+pub use extension::{VoteAnomaly, VoteVectors};
 pub use member::{run_session, LocalIO};
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
 pub use network::NetworkData;
+pub use quorum::{Quorum, SupermajorityQuorum};
+pub use replay::{MessageRecorder, RecordedMessage, RecordingNetwork, ReplaySource};
+pub use round_clock::{Clock, RoundClock, SystemClock};
+pub use status::{ConsensusStatus, StatusMonitor};
 pub use terminator::{handle_task_termination, Terminator};
+pub use units::{ControlHash, FullUnit, HashFor, SignedUnit, Unit, UnitCoord, UnitWithParents};
 
 type Receiver<T> = futures::channel::mpsc::UnboundedReceiver<T>;
 type Sender<T> = futures::channel::mpsc::UnboundedSender<T>;
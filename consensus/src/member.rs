@@ -1,18 +1,18 @@
 use crate::{
     handle_task_termination,
-    member::Task::{CoordRequest, ParentsRequest, RequestNewest, UnitBroadcast},
+    member::Task::{CatchUpRequest, CoordRequest, ParentsRequest, RequestNewest, UnitBroadcast},
     network,
     runway::{
         self, NetworkIO, NewestUnitResponse, Request, Response, RunwayIO, RunwayNotificationIn,
         RunwayNotificationOut,
     },
     task_queue::TaskQueue,
-    units::{UncheckedSignedUnit, Unit, UnitCoord},
+    units::{decode_wire_unit, encode_wire_unit, UncheckedSignedUnit, Unit, UnitCoord},
     Config, Data, DataProvider, FinalizationHandler, Hasher, MultiKeychain, Network, NodeIndex,
     Receiver, Recipient, Round, Sender, Signature, SpawnHandle, Terminator, UncheckedSigned,
 };
 use aleph_bft_types::NodeMap;
-use codec::{Decode, Encode};
+use codec::{Compact, Decode, Encode, Input, Output};
 use futures::{channel::mpsc, pin_mut, AsyncRead, AsyncWrite, FutureExt, StreamExt};
 use futures_timer::Delay;
 use itertools::Itertools;
@@ -24,11 +24,15 @@ use std::{
     convert::TryInto,
     fmt::{self, Debug},
     marker::PhantomData,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+/// How often to request missing parents of a unit once the configured number of attempts is used up.
+const SLOW_PARENT_REQUEST_DELAY: Duration = Duration::from_secs(30);
+
 /// A message concerning units, either about new units or some requests for them.
-#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+/// The units are encoded in the versioned wire format.
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
     /// For disseminating newly created units.
     NewUnit(UncheckedSignedUnit<H, D, S>),
@@ -44,6 +48,104 @@ pub(crate) enum UnitMessage<H: Hasher, D: Data, S: Signature> {
     RequestNewest(NodeIndex, u64),
     /// Response to RequestNewest: (our index, maybe unit, salt) signed by us
     ResponseNewest(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
+    /// Request by a node that fell behind for the units of the rounds between the given ones, inclusive.
+    RequestCatchUp(NodeIndex, Round, Round),
+    /// Response to RequestCatchUp, limited in size, so possibly with only the lower rounds.
+    ResponseCatchUp(Vec<UncheckedSignedUnit<H, D, S>>),
+}
+
+fn encode_wire_units<H: Hasher, D: Data, S: Signature, T: Output + ?Sized>(
+    units: &[UncheckedSignedUnit<H, D, S>],
+    dest: &mut T,
+) {
+    Compact(units.len() as u32).encode_to(dest);
+    for unit in units {
+        encode_wire_unit(unit, dest);
+    }
+}
+
+fn decode_wire_units<H: Hasher, D: Data, S: Signature, I: Input>(
+    input: &mut I,
+) -> Result<Vec<UncheckedSignedUnit<H, D, S>>, codec::Error> {
+    let len = Compact::<u32>::decode(input)?.0;
+    // Not preallocated, as the length comes from the network.
+    let mut units = Vec::new();
+    for _ in 0..len {
+        units.push(decode_wire_unit(input)?);
+    }
+    Ok(units)
+}
+
+impl<H: Hasher, D: Data, S: Signature> Encode for UnitMessage<H, D, S> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        use UnitMessage::*;
+        match self {
+            NewUnit(unit) => {
+                dest.push_byte(0);
+                encode_wire_unit(unit, dest);
+            }
+            RequestCoord(node_id, coord) => {
+                dest.push_byte(1);
+                node_id.encode_to(dest);
+                coord.encode_to(dest);
+            }
+            ResponseCoord(unit) => {
+                dest.push_byte(2);
+                encode_wire_unit(unit, dest);
+            }
+            RequestParents(node_id, hash) => {
+                dest.push_byte(3);
+                node_id.encode_to(dest);
+                hash.encode_to(dest);
+            }
+            ResponseParents(hash, units) => {
+                dest.push_byte(4);
+                hash.encode_to(dest);
+                encode_wire_units(units, dest);
+            }
+            RequestNewest(node_id, salt) => {
+                dest.push_byte(5);
+                node_id.encode_to(dest);
+                salt.encode_to(dest);
+            }
+            ResponseNewest(response) => {
+                dest.push_byte(6);
+                response.encode_to(dest);
+            }
+            RequestCatchUp(node_id, from_round, to_round) => {
+                dest.push_byte(7);
+                node_id.encode_to(dest);
+                from_round.encode_to(dest);
+                to_round.encode_to(dest);
+            }
+            ResponseCatchUp(units) => {
+                dest.push_byte(8);
+                encode_wire_units(units, dest);
+            }
+        }
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> Decode for UnitMessage<H, D, S> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        use UnitMessage::*;
+        Ok(match input.read_byte()? {
+            0 => NewUnit(decode_wire_unit(input)?),
+            1 => RequestCoord(NodeIndex::decode(input)?, UnitCoord::decode(input)?),
+            2 => ResponseCoord(decode_wire_unit(input)?),
+            3 => RequestParents(NodeIndex::decode(input)?, H::Hash::decode(input)?),
+            4 => ResponseParents(H::Hash::decode(input)?, decode_wire_units(input)?),
+            5 => RequestNewest(NodeIndex::decode(input)?, u64::decode(input)?),
+            6 => ResponseNewest(UncheckedSigned::decode(input)?),
+            7 => RequestCatchUp(
+                NodeIndex::decode(input)?,
+                Round::decode(input)?,
+                Round::decode(input)?,
+            ),
+            8 => ResponseCatchUp(decode_wire_units(input)?),
+            _ => return Err("Unknown unit message variant.".into()),
+        })
+    }
 }
 
 impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
@@ -59,6 +161,11 @@ impl<H: Hasher, D: Data, S: Signature> UnitMessage<H, D, S> {
                 .collect(),
             UnitMessage::RequestNewest(_, _) => Vec::new(),
             UnitMessage::ResponseNewest(response) => response.as_signable().included_data(),
+            UnitMessage::RequestCatchUp(_, _, _) => Vec::new(),
+            UnitMessage::ResponseCatchUp(units) => units
+                .iter()
+                .flat_map(|uu| uu.as_signable().included_data())
+                .collect(),
         }
     }
 }
@@ -73,6 +180,8 @@ enum Task<H: Hasher, D: Data, S: Signature> {
     UnitBroadcast(UncheckedSignedUnit<H, D, S>),
     // Request the newest unit created by node itself.
     RequestNewest(u64),
+    // Request all the units of the given range of rounds, inclusive.
+    CatchUpRequest(Round, Round),
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -168,12 +277,14 @@ where
         let mut count_coord_request: usize = 0;
         let mut count_parents_request: usize = 0;
         let mut count_request_newest: usize = 0;
+        let mut count_catch_up_request: usize = 0;
         let mut count_rebroadcast: usize = 0;
         for task in self.task_queue.iter().map(|st| &st.task) {
             match task {
                 CoordRequest(_) => count_coord_request += 1,
                 ParentsRequest(_) => count_parents_request += 1,
                 RequestNewest(_) => count_request_newest += 1,
+                CatchUpRequest(_, _) => count_catch_up_request += 1,
                 UnitBroadcast(_) => count_rebroadcast += 1,
             }
         }
@@ -186,8 +297,12 @@ where
         write!(f, "task queue content: ")?;
         write!(
             f,
-            "CoordRequest - {}, ParentsRequest - {}, UnitBroadcast - {}, RequestNewest - {}",
-            count_coord_request, count_parents_request, count_rebroadcast, count_request_newest,
+            "CoordRequest - {}, ParentsRequest - {}, UnitBroadcast - {}, RequestNewest - {}, CatchUpRequest - {}",
+            count_coord_request,
+            count_parents_request,
+            count_rebroadcast,
+            count_request_newest,
+            count_catch_up_request,
         )?;
         if !self.not_resolved_coords.is_empty() {
             write!(
@@ -235,6 +350,7 @@ where
     not_resolved_parents: HashSet<H::Hash>,
     not_resolved_coords: HashSet<UnitCoord>,
     newest_unit_resolved: bool,
+    not_resolved_catch_up: Option<(Round, Round)>,
     peers: Vec<Recipient>,
     unit_messages_for_network: Sender<(UnitMessage<H, D, S>, Recipient)>,
     unit_messages_from_network: Receiver<UnitMessage<H, D, S>>,
@@ -272,6 +388,7 @@ where
             not_resolved_parents: HashSet::new(),
             not_resolved_coords: HashSet::new(),
             newest_unit_resolved: false,
+            not_resolved_catch_up: None,
             peers,
             unit_messages_for_network,
             unit_messages_from_network,
@@ -330,8 +447,20 @@ where
         self.trigger_tasks();
     }
 
+    fn on_request_catch_up(&mut self, from_round: Round, to_round: Round) {
+        trace!(target: "AlephBFT-member", "{:?} Dealing with catch-up request for rounds {}-{}.", self.index(), from_round, to_round);
+        self.not_resolved_catch_up = Some((from_round, to_round));
+        self.task_queue
+            .schedule_now(RepeatableTask::new(CatchUpRequest(from_round, to_round)));
+        self.trigger_tasks();
+    }
+
     fn trigger_tasks(&mut self) {
-        while let Some(mut task) = self.task_queue.pop_due_task() {
+        self.trigger_tasks_at(Instant::now());
+    }
+
+    fn trigger_tasks_at(&mut self, now: Instant) {
+        while let Some(mut task) = self.task_queue.pop_due_task(now) {
             match self.task_details(&task.task, task.counter) {
                 TaskDetails::Cancel => (),
                 TaskDetails::Perform {
@@ -344,7 +473,7 @@ where
                     }
 
                     task.counter += 1;
-                    self.task_queue.schedule_in(task, reschedule)
+                    self.task_queue.schedule(task, now + reschedule)
                 }
             }
         }
@@ -352,7 +481,7 @@ where
 
     fn random_peers(&self, n: usize) -> Vec<Recipient> {
         self.peers
-            .choose_multiple(&mut rand::thread_rng(), n)
+            .choose_multiple(&mut self.config.randomness().clone(), n)
             .cloned()
             .collect()
     }
@@ -377,6 +506,13 @@ where
     /// `Perform { message, recipient, reschedule }` if the task is to send `message` to `recipient` and it should
     /// be rescheduled after `reschedule`.
     fn task_details(&mut self, task: &Task<H, D, S>, counter: usize) -> TaskDetails<H, D, S> {
+        if let ParentsRequest(hash) = task {
+            if Some(counter) == self.config.parent_request_attempts()
+                && self.not_resolved_parents.contains(hash)
+            {
+                debug!(target: "AlephBFT-member", "{} Still missing parents after {} attempts, requesting them less often.", self.index().0, counter);
+            }
+        }
         match self.still_valid(task) {
             false => TaskDetails::Cancel,
             true => TaskDetails::Perform {
@@ -393,6 +529,9 @@ where
             ParentsRequest(hash) => UnitMessage::RequestParents(self.index(), *hash),
             UnitBroadcast(unit) => UnitMessage::NewUnit(unit.clone()),
             RequestNewest(salt) => UnitMessage::RequestNewest(self.index(), *salt),
+            CatchUpRequest(from_round, to_round) => {
+                UnitMessage::RequestCatchUp(self.index(), *from_round, *to_round)
+            }
         }
     }
 
@@ -408,6 +547,11 @@ where
                     counter,
                 ))
             }
+            CatchUpRequest(_, _) => {
+                self.random_peers((self.config.delay_config().coord_request_recipients)(
+                    counter,
+                ))
+            }
             UnitBroadcast(_) => vec![Recipient::Everyone],
             RequestNewest(_) => vec![Recipient::Everyone],
         }
@@ -418,6 +562,9 @@ where
             CoordRequest(coord) => self.not_resolved_coords.contains(coord),
             ParentsRequest(hash) => self.not_resolved_parents.contains(hash),
             RequestNewest(_) => !self.newest_unit_resolved,
+            CatchUpRequest(from_round, to_round) => {
+                self.not_resolved_catch_up == Some((*from_round, *to_round))
+            }
             UnitBroadcast(unit) => {
                 Some(&unit.as_signable().round())
                     == self.top_units.get(unit.as_signable().creator())
@@ -430,18 +577,27 @@ where
     /// The first exception is [Task::UnitBroadcast] - this one picks a random delay between
     /// `unit_rebroadcast_interval_min` and `unit_rebroadcast_interval_max`.
     ///
-    /// The other exception is [Task::CoordRequest] - this one uses the configurable
-    /// `coord_request_delay` schedule.
+    /// The other exception is [Task::CoordRequest] - this one, together with [Task::CatchUpRequest],
+    /// uses the configurable `coord_request_delay` schedule.
     fn delay(&self, task: &Task<H, D, S>, counter: usize) -> Duration {
         match task {
             UnitBroadcast(_) => {
                 let low = self.config.delay_config().unit_rebroadcast_interval_min;
                 let high = self.config.delay_config().unit_rebroadcast_interval_max;
-                let millis = rand::thread_rng().gen_range(low.as_millis()..high.as_millis());
+                let millis = self
+                    .config
+                    .randomness()
+                    .clone()
+                    .gen_range(low.as_millis()..high.as_millis());
                 Duration::from_millis(millis as u64)
             }
-            CoordRequest(_) => (self.config.delay_config().coord_request_delay)(counter),
-            ParentsRequest(_) => (self.config.delay_config().parent_request_delay)(counter),
+            CoordRequest(_) | CatchUpRequest(_, _) => {
+                (self.config.delay_config().coord_request_delay)(counter)
+            }
+            ParentsRequest(_) => match self.config.parent_request_attempts() {
+                Some(attempts) if counter + 1 >= attempts => SLOW_PARENT_REQUEST_DELAY,
+                _ => (self.config.delay_config().parent_request_delay)(counter),
+            },
             RequestNewest(_) => (self.config.delay_config().newest_request_delay)(counter),
         }
     }
@@ -454,6 +610,10 @@ where
                 Request::Coord(coord) => self.on_request_coord(coord),
                 Request::Parents(u_hash) => self.on_request_parents(u_hash),
                 Request::NewestUnit(salt) => self.on_request_newest(salt),
+                Request::CatchUp {
+                    from_round,
+                    to_round,
+                } => self.on_request_catch_up(from_round, to_round),
             },
             RunwayNotificationOut::Response(response, recipient) => match response {
                 Response::Coord(u) => {
@@ -469,6 +629,10 @@ where
                     let message = UnitMessage::ResponseNewest(response);
                     self.send_unit_message(message, Recipient::Node(requester))
                 }
+                Response::CatchUp(units) => {
+                    let message = UnitMessage::ResponseCatchUp(units);
+                    self.send_unit_message(message, Recipient::Node(recipient))
+                }
             },
         }
     }
@@ -511,6 +675,11 @@ where
                         Request::NewestUnit(_) => {
                             self.newest_unit_resolved = true;
                         }
+                        Request::CatchUp { from_round, to_round } => {
+                            if self.not_resolved_catch_up == Some((from_round, to_round)) {
+                                self.not_resolved_catch_up = None;
+                            }
+                        }
                     },
                     None => {
                         error!(target: "AlephBFT-member", "{:?} Resolved-requests stream from Runway closed.", self.index());
@@ -706,10 +875,12 @@ pub async fn run_session<
 mod tests {
     use super::*;
     use crate::{
+        dag::{Dag, DagUnit},
         testing::{gen_config, gen_delay_config},
-        DelayConfig,
+        units::{random_full_parent_units_up_to, UnitStore, Validator, WIRE_VERSION},
+        DelayConfig, Signed,
     };
-    use aleph_bft_mock::{Hasher64, Signature};
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
     use aleph_bft_types::NodeCount;
     use futures::channel::mpsc::unbounded;
     use itertools::Itertools;
@@ -737,6 +908,159 @@ mod tests {
         )
     }
 
+    type MessagesForNetwork = Receiver<(UnitMessage<Hasher64, u32, Signature>, Recipient)>;
+
+    fn mock_member_with_network(
+        config: Config,
+    ) -> (Member<Hasher64, u32, Signature>, MessagesForNetwork) {
+        let (unit_messages_for_network_sx, unit_messages_for_network_rx) = unbounded();
+        let (_, unit_messages_from_network_rx) = unbounded();
+        let (notifications_for_runway_sx, _) = unbounded();
+        let (_, notifications_from_runway_rx) = unbounded();
+        let (_, resolved_requests_rx) = unbounded();
+
+        let member = Member::new(
+            config,
+            unit_messages_for_network_sx,
+            unit_messages_from_network_rx,
+            notifications_for_runway_sx,
+            notifications_from_runway_rx,
+            resolved_requests_rx,
+        );
+        (member, unit_messages_for_network_rx)
+    }
+
+    fn parent_requests_sent(network: &mut MessagesForNetwork) -> usize {
+        let mut count = 0;
+        while let Ok(Some((message, _))) = network.try_next() {
+            if let UnitMessage::RequestParents(_, _) = message {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn backoff_delay_config() -> DelayConfig {
+        let mut delay_config = gen_delay_config();
+        // 10, 20, 40, 80, ...
+        delay_config.parent_request_delay =
+            Arc::new(|t| Duration::from_millis(10 * 2u64.pow(t as u32)));
+        delay_config
+    }
+
+    #[test]
+    fn repeats_lost_parent_request_until_resolved() {
+        let config = gen_config(NodeIndex(0), NodeCount(4), backoff_delay_config());
+        let (mut member, mut network) = mock_member_with_network(config);
+        let hash = Hasher64::hash(&[0x0]);
+
+        member.on_request_parents(hash);
+        let start = Instant::now();
+        // The first request gets lost, nothing else happens until the delay passes.
+        assert_eq!(parent_requests_sent(&mut network), 1);
+        member.trigger_tasks_at(start);
+        assert_eq!(parent_requests_sent(&mut network), 0);
+        member.trigger_tasks_at(start + Duration::from_millis(15));
+        assert_eq!(parent_requests_sent(&mut network), 1);
+
+        // The parents arrived, possibly in response to some other request.
+        member.not_resolved_parents.remove(&hash);
+        let later = start + Duration::from_millis(40);
+        member.trigger_tasks_at(later);
+        assert_eq!(parent_requests_sent(&mut network), 0);
+        assert!(member.task_queue.pop_due_task(later).is_none());
+    }
+
+    #[test]
+    fn slows_down_parent_request_after_attempts() {
+        let config = gen_config(NodeIndex(0), NodeCount(4), backoff_delay_config())
+            .with_parent_request_attempts(3);
+        let (mut member, mut network) = mock_member_with_network(config);
+        let hash = Hasher64::hash(&[0x0]);
+
+        member.on_request_parents(hash);
+        let start = Instant::now();
+        // Attempts after 0, 10 and 30 ms, and none after 70 ms.
+        for step in 1..=12 {
+            member.trigger_tasks_at(start + Duration::from_millis(10 * step));
+        }
+        assert!(member.not_resolved_parents.contains(&hash));
+        assert_eq!(parent_requests_sent(&mut network), 3);
+
+        // The parents are still requested, just rarely.
+        let slow_attempt = start + Duration::from_millis(35) + SLOW_PARENT_REQUEST_DELAY;
+        member.trigger_tasks_at(slow_attempt);
+        assert_eq!(parent_requests_sent(&mut network), 1);
+        member.trigger_tasks_at(slow_attempt + SLOW_PARENT_REQUEST_DELAY / 2);
+        assert_eq!(parent_requests_sent(&mut network), 0);
+        member.trigger_tasks_at(slow_attempt + SLOW_PARENT_REQUEST_DELAY);
+        assert_eq!(parent_requests_sent(&mut network), 1);
+    }
+
+    #[test]
+    fn gets_parents_over_lossy_network_after_slowing_down() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let units: Vec<Vec<_>> = random_full_parent_units_up_to(1, node_count, session_id)
+            .into_iter()
+            .map(|units| {
+                units
+                    .into_iter()
+                    .map(|unit| {
+                        let keychain = &keychains[unit.creator().0];
+                        Signed::sign(unit, keychain).into_unchecked()
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(Validator::new(session_id, keychains[0], 2137));
+        let dependent = units[1][1].clone();
+        let dependent_hash = dependent.as_signable().hash();
+        assert!(dag.add_unit(dependent, &store).units.is_empty());
+
+        let attempts = 3;
+        let config = gen_config(NodeIndex(0), node_count, backoff_delay_config())
+            .with_parent_request_attempts(attempts);
+        let (mut member, mut network) = mock_member_with_network(config);
+        member.on_request_parents(dependent_hash);
+        let start = Instant::now();
+
+        // The network loses all the requests until the member slows down.
+        let mut requests_sent = 0;
+        let mut step = 0;
+        while store.unit(&dependent_hash).is_none() {
+            let now = start + Duration::from_millis(10 * step);
+            assert!(
+                now < start + 2 * SLOW_PARENT_REQUEST_DELAY,
+                "the parents never arrived"
+            );
+            member.trigger_tasks_at(now);
+            while let Ok(Some((message, _))) = network.try_next() {
+                if let UnitMessage::RequestParents(_, hash) = message {
+                    requests_sent += 1;
+                    if requests_sent <= attempts {
+                        continue;
+                    }
+                    assert!(now >= start + SLOW_PARENT_REQUEST_DELAY);
+                    for unit in dag.add_parents(hash, units[0].clone(), &store).units {
+                        store.insert(unit);
+                    }
+                    // What the runway reports once the unit is in the Dag.
+                    member.not_resolved_parents.remove(&hash);
+                }
+            }
+            step += 1;
+        }
+        assert_eq!(requests_sent, attempts + 1);
+        member.trigger_tasks_at(start + 3 * SLOW_PARENT_REQUEST_DELAY);
+        assert_eq!(parent_requests_sent(&mut network), 0);
+    }
+
     #[test]
     fn delay_for_coord_request() {
         let mut delay_config = gen_delay_config();
@@ -836,4 +1160,65 @@ mod tests {
 
         assert_eq!(recipients, vec![]);
     }
+
+    #[test]
+    fn identically_seeded_members_make_identical_random_choices() {
+        use crate::Randomness;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let recipients = || {
+            let mut delay_config = gen_delay_config();
+            delay_config.parent_request_recipients = Arc::new(|_| 5);
+            let config = gen_config(NodeIndex(7), NodeCount(20), delay_config)
+                .with_randomness(Randomness::from_rng(StdRng::seed_from_u64(2137)));
+            let member = mock_member_with_network(config).0;
+            let request = ParentsRequest(Hasher64::hash(&[0x0]));
+            (0..10)
+                .map(|counter| member.recipients(&request, counter))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(recipients(), recipients());
+    }
+
+    #[test]
+    fn encodes_units_in_messages_in_wire_format() {
+        use crate::units::{full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to};
+        use aleph_bft_mock::Keychain;
+
+        let n_members = NodeCount(4);
+        let units: Vec<_> = random_full_parent_units_up_to(2, n_members, 43)
+            .into_iter()
+            .flatten()
+            .map(|unit| {
+                let keychain = Keychain::new(n_members, unit.creator());
+                full_unit_to_unchecked_signed_unit(unit, &keychain)
+            })
+            .collect();
+        let hash = units[5].as_signable().hash();
+        let messages: Vec<UnitMessage<Hasher64, u32, Signature>> = vec![
+            UnitMessage::NewUnit(units[0].clone()),
+            UnitMessage::RequestCoord(NodeIndex(1), units[1].as_signable().coord()),
+            UnitMessage::ResponseCoord(units[1].clone()),
+            UnitMessage::RequestParents(NodeIndex(2), hash),
+            UnitMessage::ResponseParents(hash, units[..4].to_vec()),
+            UnitMessage::RequestNewest(NodeIndex(3), 2137),
+            UnitMessage::RequestCatchUp(NodeIndex(0), 1, 2),
+            UnitMessage::ResponseCatchUp(units.clone()),
+        ];
+        for message in messages {
+            let encoded = message.encode();
+            let decoded = UnitMessage::decode(&mut &encoded[..]).expect("just encoded");
+            assert_eq!(decoded, message);
+        }
+
+        // The unit follows the variant, prefixed with its length and then the version of the format.
+        let encoded = UnitMessage::<Hasher64, u32, Signature>::NewUnit(units[0].clone()).encode();
+        let wire_unit = Vec::<u8>::decode(&mut &encoded[1..]).expect("the unit is embedded");
+        assert_eq!(wire_unit[0], WIRE_VERSION);
+        let mut unknown_version = encoded.clone();
+        unknown_version[encoded.len() - wire_unit.len()] = WIRE_VERSION + 1;
+        assert!(
+            UnitMessage::<Hasher64, u32, Signature>::decode(&mut &unknown_version[..]).is_err()
+        );
+    }
 }
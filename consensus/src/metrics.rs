@@ -0,0 +1,123 @@
+use crate::Round;
+use prometheus::{linear_buckets, Histogram, HistogramOpts, IntCounter, Registry};
+
+/// Counters describing the progress of head elections, registered in a caller-supplied
+/// Prometheus registry.
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    elections_started: IntCounter,
+    candidates_eliminated: IntCounter,
+    heads_elected: IntCounter,
+    decision_relative_round: Histogram,
+    votes_cached: IntCounter,
+    votes_computed: IntCounter,
+}
+
+impl Metrics {
+    /// Create the metrics and register them in the provided registry.
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Metrics {
+            elections_started: IntCounter::new(
+                "aleph_bft_elections_started_total",
+                "Number of round elections started",
+            )?,
+            candidates_eliminated: IntCounter::new(
+                "aleph_bft_candidates_eliminated_total",
+                "Number of candidates eliminated in round elections",
+            )?,
+            heads_elected: IntCounter::new(
+                "aleph_bft_heads_elected_total",
+                "Number of heads elected",
+            )?,
+            // Heads are decided at relative round 3 at the earliest.
+            decision_relative_round: Histogram::with_opts(
+                HistogramOpts::new(
+                    "aleph_bft_head_decision_relative_round",
+                    "Round, relative to the round of the head, of the unit that decided the election",
+                )
+                .buckets(linear_buckets(3.0, 1.0, 10)?),
+            )?,
+            votes_cached: IntCounter::new(
+                "aleph_bft_election_votes_cached_total",
+                "Number of votes on candidates that were already computed when needed",
+            )?,
+            votes_computed: IntCounter::new(
+                "aleph_bft_election_votes_computed_total",
+                "Number of votes on candidates computed from the votes of parents",
+            )?,
+        };
+        registry.register(Box::new(metrics.elections_started.clone()))?;
+        registry.register(Box::new(metrics.candidates_eliminated.clone()))?;
+        registry.register(Box::new(metrics.heads_elected.clone()))?;
+        registry.register(Box::new(metrics.decision_relative_round.clone()))?;
+        registry.register(Box::new(metrics.votes_cached.clone()))?;
+        registry.register(Box::new(metrics.votes_computed.clone()))?;
+        Ok(metrics)
+    }
+
+    pub(crate) fn report_election_started(&self) {
+        self.elections_started.inc();
+    }
+
+    pub(crate) fn report_candidate_eliminated(&self) {
+        self.candidates_eliminated.inc();
+    }
+
+    pub(crate) fn report_head_elected(&self, relative_round: Round) {
+        self.heads_elected.inc();
+        self.decision_relative_round
+            .observe(f64::from(relative_round));
+    }
+
+    pub(crate) fn report_vote_cached(&self) {
+        self.votes_cached.inc();
+    }
+
+    pub(crate) fn report_vote_computed(&self) {
+        self.votes_computed.inc();
+    }
+
+    /// The number of round elections started.
+    pub fn elections_started(&self) -> u64 {
+        self.elections_started.get()
+    }
+
+    /// The number of candidates eliminated.
+    pub fn candidates_eliminated(&self) -> u64 {
+        self.candidates_eliminated.get()
+    }
+
+    /// The number of heads elected.
+    pub fn heads_elected(&self) -> u64 {
+        self.heads_elected.get()
+    }
+
+    /// The number of votes that were needed again after being computed, e.g. when resuming an election.
+    pub fn votes_cached(&self) -> u64 {
+        self.votes_cached.get()
+    }
+
+    /// The number of votes computed, which should happen at most once per voter and candidate.
+    pub fn votes_computed(&self) -> u64 {
+        self.votes_computed.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Metrics;
+    use prometheus::Registry;
+
+    #[test]
+    fn registers_in_registry() {
+        let registry = Registry::new();
+        let metrics = Metrics::register(&registry).expect("registry is empty");
+        metrics.report_election_started();
+        metrics.report_head_elected(4);
+        assert_eq!(registry.gather().len(), 6);
+        assert_eq!(metrics.elections_started(), 1);
+        assert_eq!(metrics.heads_elected(), 1);
+        // The same metrics cannot be registered twice.
+        assert!(Metrics::register(&registry).is_err());
+    }
+}
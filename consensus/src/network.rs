@@ -294,8 +294,8 @@ mod tests {
 
         let h = 43.using_encoded(Hasher64::hash);
         let p1 = test_unchecked_unit(5.into(), 43, 1729);
-        let p2 = test_unchecked_unit(13.into(), 43, 1729);
-        let p3 = test_unchecked_unit(17.into(), 43, 1729);
+        let p2 = test_unchecked_unit(3.into(), 43, 1729);
+        let p3 = test_unchecked_unit(6.into(), 43, 1729);
         let included_data: Vec<Data> = p1
             .as_signable()
             .included_data()
@@ -333,6 +333,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decoding_network_data_units_request_catch_up() {
+        use UnitMessage::RequestCatchUp;
+
+        let ni = 7.into();
+        let nd = TestNetworkData::new(Units(RequestCatchUp(ni, 3, 10)));
+        let decoded = TestNetworkData::decode(&mut &nd.encode()[..]);
+        assert!(decoded.is_ok(), "Bug in encode/decode for RequestCatchUp");
+        let decoded = decoded.unwrap();
+        assert!(
+            decoded.included_data().is_empty(),
+            "data returned from a catch-up request"
+        );
+        if let Units(RequestCatchUp(dni, from_round, to_round)) = decoded.0 {
+            assert!(
+                ni == dni && from_round == 3 && to_round == 10,
+                "decoded should equal encoded"
+            );
+        } else {
+            panic!("Decoded RequestCatchUp as something else");
+        }
+    }
+
     #[test]
     fn decoding_network_data_alert_fork_alert() {
         use AlertMessage::ForkAlert;
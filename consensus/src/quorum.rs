@@ -0,0 +1,65 @@
+#[cfg(test)]
+use crate::NodeIndex;
+use crate::{NodeCount, NodeSubset};
+use std::fmt::Debug;
+
+/// Decides whether the nodes present suffice to proceed, i.e. whether units created by them are enough
+/// parents for a new unit, or whether their votes are enough to decide an election.
+/// All the nodes of a session have to use the same quorum. It is required on top of the usual rules,
+/// i.e. parents created by more than two thirds of the nodes and votes reaching the election threshold.
+pub trait Quorum: Debug + Send + Sync {
+    /// Whether the present nodes form a quorum, the subset has the size of the whole committee.
+    fn is_quorum(&self, present: &NodeSubset) -> bool;
+}
+
+/// More than two thirds of the nodes, regardless of their weights. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SupermajorityQuorum;
+
+impl Quorum for SupermajorityQuorum {
+    fn is_quorum(&self, present: &NodeSubset) -> bool {
+        NodeCount(present.len()).is_supermajority(NodeCount(present.size()))
+    }
+}
+
+/// A supermajority that has to include a specific node.
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+pub struct MandatoryNodeQuorum(pub NodeIndex);
+
+#[cfg(test)]
+impl Quorum for MandatoryNodeQuorum {
+    fn is_quorum(&self, present: &NodeSubset) -> bool {
+        present.contains(self.0) && SupermajorityQuorum.is_quorum(present)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        quorum::MandatoryNodeQuorum, NodeCount, NodeIndex, NodeSubset, Quorum, SupermajorityQuorum,
+    };
+
+    #[test]
+    fn supermajority_counts_nodes() {
+        let mut present = NodeSubset::with_size(NodeCount(7));
+        for node_id in 0..4 {
+            present.insert(NodeIndex(node_id));
+        }
+        assert!(!SupermajorityQuorum.is_quorum(&present));
+        present.insert(NodeIndex(6));
+        assert!(SupermajorityQuorum.is_quorum(&present));
+    }
+
+    #[test]
+    fn mandatory_node_is_required() {
+        let mut present = NodeSubset::with_size(NodeCount(4));
+        for node_id in 1..4 {
+            present.insert(NodeIndex(node_id));
+        }
+        let quorum = MandatoryNodeQuorum(NodeIndex(0));
+        assert!(!quorum.is_quorum(&present));
+        present.insert(NodeIndex(0));
+        assert!(quorum.is_quorum(&present));
+    }
+}
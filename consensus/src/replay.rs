@@ -0,0 +1,192 @@
+use crate::{Network, Recipient};
+use codec::{Decode, Encode};
+use futures::{future::pending, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Future};
+use log::{error, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const LOG_TARGET: &str = "AlephBFT-replay";
+
+/// A message exchanged by a node, together with the time of recording.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct RecordedMessage<D> {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub data: D,
+}
+
+// Encodes the same way as a `RecordedMessage` with the current time.
+fn encode_record<D: Encode>(data: &D) -> Vec<u8> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0);
+    (timestamp, data).encode()
+}
+
+/// Appends SCALE-encoded messages, with the time of recording, to a writer.
+/// The resulting log can be fed back into a member with a [`ReplaySource`].
+pub struct MessageRecorder<W: AsyncWrite + Unpin> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> MessageRecorder<W> {
+    pub fn new(writer: W) -> Self {
+        MessageRecorder { writer }
+    }
+
+    /// Append the message to the log.
+    pub fn record<D: Encode>(&mut self, data: &D) -> impl Future<Output = io::Result<()>> + '_ {
+        self.write(encode_record(data))
+    }
+
+    async fn write(&mut self, record: Vec<u8>) -> io::Result<()> {
+        self.writer.write_all(&record).await?;
+        self.writer.flush().await
+    }
+}
+
+/// A network recording all the messages exchanged through the wrapped one. Messages sent by the node
+/// are recorded too, as they contain its own units, which are needed to reproduce its state.
+/// They get written to the log when the network is next polled for messages, before anything received later.
+pub struct RecordingNetwork<N, W: AsyncWrite + Unpin> {
+    network: N,
+    recorder: MessageRecorder<W>,
+    sent: Mutex<Vec<Vec<u8>>>,
+}
+
+impl<N, W: AsyncWrite + Unpin> RecordingNetwork<N, W> {
+    pub fn new(network: N, writer: W) -> Self {
+        RecordingNetwork {
+            network,
+            recorder: MessageRecorder::new(writer),
+            sent: Mutex::new(Vec::new()),
+        }
+    }
+
+    async fn record_sent(&mut self) {
+        let sent = std::mem::take(&mut *self.sent.lock());
+        for record in sent {
+            if let Err(e) = self.recorder.write(record).await {
+                error!(target: LOG_TARGET, "Unable to record a sent message: {}.", e);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<D, N, W> Network<D> for RecordingNetwork<N, W>
+where
+    D: Encode + Send + 'static,
+    N: Network<D>,
+    W: AsyncWrite + Send + Unpin,
+{
+    fn send(&self, data: D, recipient: Recipient) {
+        self.sent.lock().push(encode_record(&data));
+        self.network.send(data, recipient)
+    }
+
+    async fn next_event(&mut self) -> Option<D> {
+        self.record_sent().await;
+        let data = self.network.next_event().await?;
+        self.record_sent().await;
+        if let Err(e) = self.recorder.record(&data).await {
+            error!(target: LOG_TARGET, "Unable to record a message: {}.", e);
+        }
+        Some(data)
+    }
+}
+
+/// A network feeding the messages recorded by a [`MessageRecorder`] into a member, in the recorded order.
+/// Messages sent by the member are dropped. After the last recorded message no more messages arrive,
+/// but the network stays open, so that the state of the member can be inspected.
+pub struct ReplaySource<D, R: AsyncRead + Unpin> {
+    reader: Option<R>,
+    messages: VecDeque<RecordedMessage<D>>,
+}
+
+impl<D: Decode, R: AsyncRead + Unpin> ReplaySource<D, R> {
+    pub fn new(reader: R) -> Self {
+        ReplaySource {
+            reader: Some(reader),
+            messages: VecDeque::new(),
+        }
+    }
+
+    async fn load(&mut self, mut reader: R) {
+        let mut buf = Vec::new();
+        if let Err(e) = reader.read_to_end(&mut buf).await {
+            error!(target: LOG_TARGET, "Unable to read the recorded messages: {}.", e);
+        }
+        let input = &mut &buf[..];
+        while !input.is_empty() {
+            match RecordedMessage::decode(input) {
+                Ok(message) => self.messages.push_back(message),
+                Err(e) => {
+                    warn!(target: LOG_TARGET, "Skipping a malformed tail of the recorded messages: {}.", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<D, R> Network<D> for ReplaySource<D, R>
+where
+    D: Decode + Send + 'static,
+    R: AsyncRead + Send + Unpin,
+{
+    fn send(&self, _: D, _: Recipient) {}
+
+    async fn next_event(&mut self) -> Option<D> {
+        if let Some(reader) = self.reader.take() {
+            self.load(reader).await;
+        }
+        match self.messages.pop_front() {
+            Some(message) => Some(message.data),
+            None => pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        replay::{MessageRecorder, RecordedMessage, ReplaySource},
+        Network,
+    };
+    use aleph_bft_mock::{Loader, Saver};
+    use codec::Decode;
+    use futures::FutureExt;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn replays_recorded_messages_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut recorder = MessageRecorder::new(Saver::from(log.clone()));
+        let messages: Vec<_> = (0..5u32).map(|i| (i, vec![i as u8; i as usize])).collect();
+        for message in &messages {
+            recorder.record(message).await.expect("recording succeeds");
+        }
+
+        let bytes = log.lock().clone();
+        let first = RecordedMessage::<(u32, Vec<u8>)>::decode(&mut &bytes[..]).expect("recorded");
+        assert_eq!(first.data, messages[0]);
+        assert!(first.timestamp > 0);
+
+        let mut replay = ReplaySource::<(u32, Vec<u8>), _>::new(Loader::new(bytes));
+        let mut replayed = Vec::new();
+        for _ in 0..messages.len() {
+            replayed.push(replay.next_event().await.expect("the network stays open"));
+        }
+        assert_eq!(replayed, messages);
+        // The log is exhausted, but the network stays open.
+        assert!(replay.next_event().now_or_never().is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    dag::{DagObserver, DagUnit},
+    units::Unit,
+    Data, Hasher, MultiKeychain, Round,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// A source of the current time, so that it can be replaced in tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The clock of the operating system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Records the local time at which the first unit of every round got observed, to correlate rounds
+/// with wall-clock time. Only the most recent rounds, as many as the window, are remembered.
+pub struct RoundClock {
+    clock: Arc<dyn Clock>,
+    window: usize,
+    first_seen: Mutex<BTreeMap<Round, SystemTime>>,
+}
+
+impl RoundClock {
+    /// A round clock remembering the given number of most recent rounds, using the system clock.
+    pub fn new(window: usize) -> Self {
+        RoundClock {
+            clock: Arc::new(SystemClock),
+            window,
+            first_seen: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Use the given clock instead of the system one.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Note that the unit got observed now.
+    pub fn observe_unit<U: Unit>(&self, unit: &U) {
+        self.observe_round(unit.round())
+    }
+
+    fn observe_round(&self, round: Round) {
+        let mut first_seen = self.first_seen.lock();
+        if first_seen.contains_key(&round) {
+            return;
+        }
+        if let Some(lowest) = first_seen.keys().next() {
+            if round < *lowest && first_seen.len() >= self.window {
+                return;
+            }
+        }
+        first_seen.insert(round, self.clock.now());
+        while first_seen.len() > self.window {
+            first_seen.pop_first();
+        }
+    }
+
+    /// The estimated local time at which the first unit of the round was or will be observed.
+    /// Rounds between the observed ones are interpolated, rounds after the last observed one
+    /// are extrapolated using the current rate. Rounds before the window cannot be estimated.
+    pub fn estimate_time(&self, round: Round) -> Option<SystemTime> {
+        let first_seen = self.first_seen.lock();
+        if let Some(time) = first_seen.get(&round) {
+            return Some(*time);
+        }
+        let (before_round, before_time) = first_seen.range(..round).next_back()?;
+        match first_seen.range(round..).next() {
+            Some((after_round, after_time)) => {
+                let span = after_time.duration_since(*before_time).unwrap_or_default();
+                let fraction =
+                    f64::from(round - before_round) / f64::from(after_round - before_round);
+                Some(*before_time + span.mul_f64(fraction))
+            }
+            None => {
+                let rate = Self::rate(&first_seen);
+                if rate <= 0.0 {
+                    return None;
+                }
+                let rounds_ahead = f64::from(round - before_round);
+                Some(*before_time + Duration::from_secs_f64(rounds_ahead / rate))
+            }
+        }
+    }
+
+    /// The rate at which rounds progressed over the window, zero if it cannot be determined yet.
+    pub fn rounds_per_second(&self) -> f64 {
+        Self::rate(&self.first_seen.lock())
+    }
+
+    fn rate(first_seen: &BTreeMap<Round, SystemTime>) -> f64 {
+        let (Some((first_round, first_time)), Some((last_round, last_time))) =
+            (first_seen.first_key_value(), first_seen.last_key_value())
+        else {
+            return 0.0;
+        };
+        match last_time.duration_since(*first_time) {
+            Ok(elapsed) if !elapsed.is_zero() => {
+                f64::from(last_round - first_round) / elapsed.as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl Debug for RoundClock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RoundClock")
+            .field("window", &self.window)
+            .field("first seen", &self.first_seen.lock())
+            .finish()
+    }
+}
+
+impl<H: Hasher, D: Data, MK: MultiKeychain> DagObserver<H, D, MK> for RoundClock {
+    fn unit_added(&self, unit: &DagUnit<H, D, MK>) {
+        self.observe_unit(unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        round_clock::{Clock, RoundClock},
+        units::random_full_parent_reconstrusted_units_up_to,
+        NodeCount,
+    };
+    use parking_lot::Mutex;
+    use std::{
+        sync::Arc,
+        time::{Duration, SystemTime},
+    };
+
+    struct ManualClock {
+        now: Mutex<SystemTime>,
+    }
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            *self.now.lock() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> SystemTime {
+            *self.now.lock()
+        }
+    }
+
+    #[test]
+    fn estimates_rate_and_times_of_rounds() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let clock = Arc::new(ManualClock {
+            now: Mutex::new(start),
+        });
+        let round_clock = RoundClock::new(10).with_clock(clock.clone());
+        assert_eq!(round_clock.rounds_per_second(), 0.0);
+        assert_eq!(round_clock.estimate_time(0), None);
+        let dag = random_full_parent_reconstrusted_units_up_to(20, NodeCount(4), 2137);
+        for (round, units) in dag.iter().enumerate() {
+            // Rounds slowly get longer.
+            clock.advance(Duration::from_millis(200 + 2 * round as u64));
+            for unit in units {
+                round_clock.observe_unit(unit);
+                clock.advance(Duration::from_millis(1));
+            }
+        }
+        // The window contains rounds 11 to 20, which took 236ms each on average.
+        let rate = round_clock.rounds_per_second();
+        assert!((rate - 1000.0 / 236.0).abs() < 0.001, "rate {}", rate);
+        assert_eq!(round_clock.estimate_time(5), None);
+        let round_20 = round_clock.estimate_time(20).expect("observed");
+        let expected_round_21 = round_20 + Duration::from_secs_f64(1.0 / rate);
+        assert_eq!(round_clock.estimate_time(21), Some(expected_round_21));
+        let round_19 = round_clock.estimate_time(19).expect("observed");
+        assert!(round_19 < round_20);
+        assert!(round_20 < expected_round_21);
+    }
+
+    #[test]
+    fn interpolates_between_observed_rounds() {
+        let start = SystemTime::UNIX_EPOCH;
+        let clock = Arc::new(ManualClock {
+            now: Mutex::new(start),
+        });
+        let round_clock = RoundClock::new(10).with_clock(clock.clone());
+        let dag = random_full_parent_reconstrusted_units_up_to(4, NodeCount(4), 2137);
+        round_clock.observe_unit(&dag[0][0]);
+        clock.advance(Duration::from_secs(4));
+        round_clock.observe_unit(&dag[4][0]);
+        assert_eq!(
+            round_clock.estimate_time(1),
+            Some(start + Duration::from_secs(1))
+        );
+        assert_eq!(
+            round_clock.estimate_time(3),
+            Some(start + Duration::from_secs(3))
+        );
+        assert_eq!(round_clock.rounds_per_second(), 1.0);
+    }
+}
@@ -8,23 +8,16 @@ use codec::{Decode, Encode};
 use futures::{channel::oneshot, FutureExt, StreamExt};
 use futures_timer::Delay;
 use log::{debug, error, info, warn};
+use rand::RngCore;
 use std::{
     cmp::max,
-    collections::hash_map::DefaultHasher,
     fmt::{Display, Formatter, Result as FmtResult},
-    hash::{Hash, Hasher as _},
     time::Duration,
 };
 
 /// Salt uniquely identifying an initial unit collection instance.
 pub type Salt = u64;
 
-fn generate_salt() -> Salt {
-    let mut hasher = DefaultHasher::new();
-    std::time::Instant::now().hash(&mut hasher);
-    hasher.finish()
-}
-
 /// A response to the request for the newest unit.
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Decode, Encode)]
 pub struct NewestUnitResponse<H: Hasher, D: Data, S: Signature> {
@@ -138,9 +131,13 @@ pub struct Collection<'a, MK: Keychain> {
 
 impl<'a, MK: Keychain> Collection<'a, MK> {
     /// Create a new collection instance ready to collect responses.
-    /// The returned salt should be used to initiate newest unit requests.
-    pub fn new(keychain: &'a MK, validator: &'a Validator<MK>) -> (Self, Salt) {
-        let salt = generate_salt();
+    /// The returned salt, drawn from the provided randomness, should be used to initiate newest unit requests.
+    pub fn new(
+        keychain: &'a MK,
+        validator: &'a Validator<MK>,
+        randomness: &mut impl RngCore,
+    ) -> (Self, Salt) {
+        let salt = randomness.next_u64();
         let mut collected_starting_rounds = NodeMap::with_size(keychain.node_count());
         collected_starting_rounds.insert(keychain.index(), 0);
         (
@@ -324,6 +321,7 @@ mod tests {
         Index, NodeCount, NodeIndex, SessionId, Signed, UncheckedSigned,
     };
     use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use rand::thread_rng;
     use std::iter::{once, repeat};
 
     type Collection<'a> = GenericCollection<'a, Keychain>;
@@ -374,7 +372,7 @@ mod tests {
         let max_round = 2;
         let keychain = Keychain::new(n_members, creator_id);
         let validator = Validator::new(session_id, keychain, max_round);
-        let (collection, _) = Collection::new(&keychain, &validator);
+        let (collection, _) = Collection::new(&keychain, &validator, &mut thread_rng());
         assert_eq!(collection.status(), Pending);
     }
 
@@ -387,7 +385,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let responses = create_responses(
             keychains.iter().skip(1).take(3).zip(repeat(None)),
             salt,
@@ -408,7 +406,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let responses = create_responses(
             repeat(&keychains[1]).take(43).zip(repeat(None)),
             salt,
@@ -429,7 +427,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let responses = create_responses(
             keychains.iter().skip(1).take(4).zip(repeat(None)),
             salt,
@@ -455,7 +453,7 @@ mod tests {
         let keychain = &keychains[0];
         let creator = Creator::new(creator_id, n_members);
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, session_id, keychain);
         let responses = create_responses(
@@ -491,7 +489,7 @@ mod tests {
         let keychains = keychain_set(n_members);
         let keychain = &keychains[0];
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let other_salt = salt + 1;
         let responses = create_responses(
             keychains.iter().skip(1).zip(repeat(None)),
@@ -518,7 +516,7 @@ mod tests {
         let keychain = &keychains[0];
         let creator = Creator::new(creator_id, n_members);
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, wrong_session_id, keychain);
         let responses = create_responses(
@@ -546,7 +544,7 @@ mod tests {
         let keychain = &keychains[0];
         let creator = Creator::new(other_creator_id, n_members);
         let validator = Validator::new(session_id, *keychain, max_round);
-        let (mut collection, salt) = Collection::new(keychain, &validator);
+        let (mut collection, salt) = Collection::new(keychain, &validator, &mut thread_rng());
         let preunit = creator.create_unit(0).expect("Creation should succeed.");
         let unit = preunit_to_unchecked_signed_unit(preunit, session_id, &keychains[1]);
         let responses = create_responses(
@@ -1,8 +1,11 @@
 use crate::{
     alerts::{Alert, ForkingNotification, NetworkMessage},
     creation,
-    dag::{Dag, DagResult, DagStatus, DagUnit, Request as ReconstructionRequest},
-    extension::Ordering,
+    dag::{
+        Dag, DagObserver, DagResult, DagStatus, DagUnit, ParentDiversity,
+        Request as ReconstructionRequest, UnitFilter,
+    },
+    extension::{ElectionConfig, Ordering},
     handle_task_termination,
     member::UnitMessage,
     units::{
@@ -10,8 +13,8 @@ use crate::{
         UnitWithParents, Validator, WrappedUnit,
     },
     Config, Data, DataProvider, FinalizationHandler, Hasher, Index, Keychain, MultiKeychain,
-    NodeIndex, Receiver, Round, Sender, Signature, Signed, SpawnHandle, Terminator,
-    UncheckedSigned,
+    NodeIndex, Receiver, Round, RoundClock, Sender, Signature, Signed, SpawnHandle, StatusMonitor,
+    Terminator, UncheckedSigned,
 };
 use aleph_bft_types::Recipient;
 use futures::{
@@ -28,12 +31,13 @@ use std::{
     convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
     marker::PhantomData,
+    sync::Arc,
     time::Duration,
 };
 
 mod collection;
 
-use crate::backup::{BackupLoader, BackupSaver};
+use crate::backup::{BackupLoader, BackupSaver, RoundCounter};
 #[cfg(feature = "initial_unit_collection")]
 use collection::{Collection, IO as CollectionIO};
 pub use collection::{NewestUnitResponse, Salt};
@@ -43,12 +47,14 @@ pub enum Request<H: Hasher> {
     Coord(UnitCoord),
     Parents(H::Hash),
     NewestUnit(Salt),
+    CatchUp { from_round: Round, to_round: Round },
 }
 
 pub(crate) enum Response<H: Hasher, D: Data, S: Signature> {
     Coord(UncheckedSignedUnit<H, D, S>),
     Parents(H::Hash, Vec<UncheckedSignedUnit<H, D, S>>),
     NewestUnit(UncheckedSigned<NewestUnitResponse<H, D, S>, S>),
+    CatchUp(Vec<UncheckedSignedUnit<H, D, S>>),
 }
 
 pub(crate) enum RunwayNotificationOut<H: Hasher, D: Data, S: Signature> {
@@ -90,11 +96,44 @@ impl<H: Hasher, D: Data, S: Signature> TryFrom<UnitMessage<H, D, S>>
             UnitMessage::ResponseNewest(response) => {
                 RunwayNotificationIn::Response(Response::NewestUnit(response))
             }
+            UnitMessage::RequestCatchUp(node_id, from_round, to_round) => {
+                RunwayNotificationIn::Request(
+                    Request::CatchUp {
+                        from_round,
+                        to_round,
+                    },
+                    node_id,
+                )
+            }
+            UnitMessage::ResponseCatchUp(units) => {
+                RunwayNotificationIn::Response(Response::CatchUp(units))
+            }
         };
         Ok(result)
     }
 }
 
+/// How many rounds we have to be missing before requesting all of them at once instead of unit by unit.
+const CATCH_UP_THRESHOLD: Round = 5;
+/// The most units we send in response to a single catch-up request.
+const MAX_CATCH_UP_UNITS: usize = 1000;
+/// How many of the most recent rounds the round clock reported to the status monitor remembers.
+const ROUND_CLOCK_WINDOW: usize = 100;
+
+/// The round from which to request the rest of the rounds up to `to_round`, if a nonempty catch-up
+/// response ended before it, e.g. because it was truncated to `MAX_CATCH_UP_UNITS`. The truncation
+/// might have cut off some units of the highest included round, so that round is requested again.
+fn remaining_catch_up<H: Hasher, D: Data, S: Signature>(
+    units: &[UncheckedSignedUnit<H, D, S>],
+    to_round: Round,
+) -> Option<Round> {
+    units
+        .iter()
+        .map(|unit| unit.as_signable().round())
+        .max()
+        .filter(|highest_round| *highest_round < to_round)
+}
+
 type CollectionResponse<H, D, MK> = UncheckedSigned<
     NewestUnitResponse<H, D, <MK as Keychain>::Signature>,
     <MK as Keychain>::Signature,
@@ -109,6 +148,7 @@ where
 {
     missing_coords: HashSet<UnitCoord>,
     missing_parents: HashSet<H::Hash>,
+    pending_catch_up: Option<(Round, Round)>,
     store: UnitStore<DagUnit<H, D, MK>>,
     keychain: MK,
     dag: Dag<H, D, MK>,
@@ -122,7 +162,9 @@ where
     parents_for_creator: Sender<DagUnit<H, D, MK>>,
     backup_units_for_saver: Sender<DagUnit<H, D, MK>>,
     backup_units_from_saver: Receiver<DagUnit<H, D, MK>>,
-    new_units_from_creation: Receiver<SignedUnit<H, D, MK>>,
+    new_units_from_creation: mpsc::Receiver<SignedUnit<H, D, MK>>,
+    status_monitor: Option<StatusMonitor>,
+    creator_handle: creation::CreatorHandle,
     exiting: bool,
 }
 
@@ -217,7 +259,13 @@ struct RunwayConfig<H: Hasher, D: Data, FH: FinalizationHandler<D>, MK: MultiKey
     responses_for_collection: Sender<CollectionResponse<H, D, MK>>,
     parents_for_creator: Sender<DagUnit<H, D, MK>>,
     resolved_requests: Sender<Request<H>>,
-    new_units_from_creation: Receiver<SignedUnit<H, D, MK>>,
+    new_units_from_creation: mpsc::Receiver<SignedUnit<H, D, MK>>,
+    election_config: ElectionConfig,
+    status_monitor: Option<StatusMonitor>,
+    creator_handle: creation::CreatorHandle,
+    max_round: Round,
+    dag_observers: Vec<Arc<dyn DagObserver<H, D, MK>>>,
+    unit_filter: Option<Arc<dyn UnitFilter<H, D, MK>>>,
 }
 
 impl<H, D, FH, MK> Runway<H, D, FH, MK>
@@ -241,10 +289,27 @@ where
             parents_for_creator,
             resolved_requests,
             new_units_from_creation,
+            election_config,
+            status_monitor,
+            creator_handle,
+            max_round,
+            dag_observers,
+            unit_filter,
         } = config;
         let store = UnitStore::new(n_members);
-        let dag = Dag::new(validator);
-        let ordering = Ordering::new(finalization_handler);
+        let ordering = Ordering::new(n_members, finalization_handler, election_config, max_round)
+            .with_session(validator.session_id());
+        let mut dag = dag_observers
+            .into_iter()
+            .fold(Dag::new(validator), Dag::with_observer);
+        if let Some(unit_filter) = unit_filter {
+            dag = dag.with_filter(unit_filter);
+        }
+        if let Some(status_monitor) = &status_monitor {
+            let round_clock = Arc::new(RoundClock::new(ROUND_CLOCK_WINDOW));
+            status_monitor.report_round_clock(round_clock.clone());
+            dag = dag.with_observer(round_clock);
+        }
 
         Runway {
             store,
@@ -253,6 +318,7 @@ where
             ordering,
             missing_coords: HashSet::new(),
             missing_parents: HashSet::new(),
+            pending_catch_up: None,
             resolved_requests,
             alerts_for_alerter,
             notifications_from_alerter,
@@ -263,6 +329,8 @@ where
             backup_units_from_saver,
             responses_for_collection,
             new_units_from_creation,
+            status_monitor,
+            creator_handle,
             exiting: false,
         }
     }
@@ -289,6 +357,9 @@ where
                 self.exiting = true;
             }
         }
+        if let Some(status_monitor) = &self.status_monitor {
+            status_monitor.report_known_equivocators(self.dag.known_forkers().len());
+        }
     }
 
     fn on_unit_received(&mut self, unit: UncheckedSignedUnit<H, D, MK::Signature>) {
@@ -316,6 +387,13 @@ where
                     trace!(target: "AlephBFT-runway", "{:?} Newest unit request received {:?}.", self.index(), salt);
                     self.on_request_newest(node_id, salt)
                 }
+                Request::CatchUp {
+                    from_round,
+                    to_round,
+                } => {
+                    trace!(target: "AlephBFT-runway", "{:?} Catch-up request received for rounds {}-{}.", self.index(), from_round, to_round);
+                    self.on_request_catch_up(node_id, from_round, to_round)
+                }
             },
 
             RunwayNotificationIn::Response(res) => match res {
@@ -334,6 +412,10 @@ where
                         debug!(target: "AlephBFT-runway", "{:?} Could not send response to collection ({:?}).", self.index(), res)
                     }
                 }
+                Response::CatchUp(units) => {
+                    trace!(target: "AlephBFT-runway", "{:?} Catch-up response received with {} units.", self.index(), units.len());
+                    self.on_catch_up_response(units)
+                }
             },
         }
     }
@@ -410,6 +492,56 @@ where
         }
     }
 
+    fn on_request_catch_up(&mut self, node_id: NodeIndex, from_round: Round, to_round: Round) {
+        debug!(target: "AlephBFT-runway", "{:?} Received catch-up request for rounds {}-{} from {:?}.", self.index(), from_round, to_round, node_id);
+        let units = self
+            .dag
+            .export_rounds(from_round, to_round, MAX_CATCH_UP_UNITS, &self.store);
+        // Answer even without units, so that the requester does not wait for us.
+        self.send_message_for_network(RunwayNotificationOut::Response(
+            Response::CatchUp(units),
+            node_id,
+        ));
+    }
+
+    fn on_catch_up_response(&mut self, units: Vec<UncheckedSignedUnit<H, D, MK::Signature>>) {
+        let to_round = match self.pending_catch_up {
+            Some((_, to_round)) => to_round,
+            None => {
+                trace!(target: "AlephBFT-runway", "{:?} We got catch-up response but did not request one.", self.index());
+                return;
+            }
+        };
+        // Honest nodes never send more, so do not spend time validating the units.
+        if units.len() > MAX_CATCH_UP_UNITS {
+            warn!(target: "AlephBFT-runway", "{:?} Rejected catch-up response with {} units, more than the limit of {}.", self.index(), units.len(), MAX_CATCH_UP_UNITS);
+            return;
+        }
+        let remaining = remaining_catch_up(&units, to_round);
+        match self.dag.import(units, &self.store) {
+            Ok(result) => self.handle_dag_result(result),
+            Err(e) => {
+                warn!(target: "AlephBFT-runway", "{:?} Rejected catch-up response: {}.", self.index(), e);
+                return;
+            }
+        }
+        // An empty response also resolves the request, the next missing unit far enough ahead starts another.
+        self.resolve_catch_up();
+        if let Some(from_round) = remaining {
+            debug!(target: "AlephBFT-runway", "{:?} Catch-up response ended before round {}, requesting rounds {}-{}.", self.index(), to_round, from_round, to_round);
+            self.request_catch_up(from_round, to_round);
+        }
+    }
+
+    fn resolve_catch_up(&mut self) {
+        if let Some((from_round, to_round)) = self.pending_catch_up.take() {
+            self.send_resolved_request_notification(Request::CatchUp {
+                from_round,
+                to_round,
+            });
+        }
+    }
+
     fn on_parents_response(
         &mut self,
         u_hash: H::Hash,
@@ -432,6 +564,7 @@ where
 
     fn resolve_missing_parents(&mut self, u_hash: &H::Hash) {
         if self.missing_parents.remove(u_hash) {
+            self.report_pending_parent_requests();
             self.send_resolved_request_notification(Request::Parents(*u_hash));
         }
     }
@@ -462,6 +595,9 @@ where
         self.dag.finished_processing(&unit_hash);
         self.resolve_missing_parents(&unit_hash);
         self.resolve_missing_coord(&unit.coord());
+        if matches!(self.pending_catch_up, Some((_, to_round)) if unit.round() >= to_round) {
+            self.resolve_catch_up();
+        }
         if self
             .parents_for_creator
             .unbounded_send(unit.clone())
@@ -478,13 +614,29 @@ where
         if unit.creator() == self.index() {
             trace!(target: "AlephBFT-runway", "{:?} Sending a unit {:?}.", self.index(), unit.hash());
             self.send_message_for_network(RunwayNotificationOut::NewSelfUnit(unpacked_unit.into()));
+            if let Some(status_monitor) = &self.status_monitor {
+                status_monitor.report_created_round(unit.round());
+            }
         }
         self.ordering.add_unit(unit.clone());
+        if let Some(round) = self.ordering.highest_ordered_round() {
+            self.creator_handle.report_ordered_round(round);
+            if let Some(status_monitor) = &self.status_monitor {
+                status_monitor.report_ordered_round(round);
+            }
+        }
+    }
+
+    fn report_pending_parent_requests(&self) {
+        if let Some(status_monitor) = &self.status_monitor {
+            status_monitor.report_pending_parent_requests(self.missing_parents.len());
+        }
     }
 
     fn on_missing_coord(&mut self, coord: UnitCoord) {
         trace!(target: "AlephBFT-runway", "{:?} Dealing with missing coord notification {:?}.", self.index(), coord);
         if self.store.canonical_unit(coord).is_none() {
+            self.catch_up_if_behind(coord.round());
             let new_request = self.missing_coords.insert(coord);
             if new_request {
                 self.send_message_for_network(RunwayNotificationOut::Request(Request::Coord(
@@ -494,9 +646,28 @@ where
         }
     }
 
+    fn catch_up_if_behind(&mut self, round: Round) {
+        let from_round = self.store.status().top_round();
+        let threshold_round = from_round.saturating_add(CATCH_UP_THRESHOLD);
+        if self.pending_catch_up.is_some() || round < threshold_round {
+            return;
+        }
+        debug!(target: "AlephBFT-runway", "{:?} Behind by {} rounds, requesting catch-up.", self.index(), round - from_round);
+        self.request_catch_up(from_round, round);
+    }
+
+    fn request_catch_up(&mut self, from_round: Round, to_round: Round) {
+        self.pending_catch_up = Some((from_round, to_round));
+        self.send_message_for_network(RunwayNotificationOut::Request(Request::CatchUp {
+            from_round,
+            to_round,
+        }));
+    }
+
     fn on_wrong_control_hash(&mut self, u_hash: H::Hash) {
         trace!(target: "AlephBFT-runway", "{:?} Dealing with wrong control hash notification {:?}.", self.index(), u_hash);
         if self.missing_parents.insert(u_hash) {
+            self.report_pending_parent_requests();
             self.send_message_for_network(RunwayNotificationOut::Request(Request::Parents(u_hash)));
         }
     }
@@ -535,6 +706,16 @@ where
         info!(target: "AlephBFT-runway", "{}", self.status());
     }
 
+    // Goes over the whole Dag, so only done in debug builds.
+    fn check_dag_integrity(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        for violation in self.dag.check_integrity(&self.store) {
+            error!(target: "AlephBFT-runway", "{:?} Dag integrity violated: {}", self.index(), violation);
+        }
+    }
+
     async fn run(
         mut self,
         data_from_backup: oneshot::Receiver<Vec<UncheckedSignedUnit<H, D, MK::Signature>>>,
@@ -609,6 +790,7 @@ where
 
             if self.exiting {
                 debug!(target: "AlephBFT-runway", "{:?} Runway decided to exit.", index);
+                self.check_dag_integrity();
                 terminator.terminate_sync().await;
                 break;
             }
@@ -634,8 +816,9 @@ fn initial_unit_collection<'a, H: Hasher, D: Data, MK: MultiKeychain>(
     unit_collection_sender: oneshot::Sender<Round>,
     responses_from_runway: Receiver<CollectionResponse<H, D, MK>>,
     resolved_requests: Sender<Request<H>>,
+    randomness: &mut crate::Randomness,
 ) -> Result<impl Future<Output = ()> + 'a, ()> {
-    let (collection, salt) = Collection::new(keychain, validator);
+    let (collection, salt) = Collection::new(keychain, validator, randomness);
     let notification = RunwayNotificationOut::Request(Request::NewestUnit(salt));
 
     if let Err(e) = unit_messages_for_network.unbounded_send(notification) {
@@ -730,14 +913,26 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
         _phantom: _,
     } = runway_io;
 
-    let (new_units_for_runway, new_units_from_creation) = mpsc::unbounded();
+    let (new_units_for_runway, new_units_from_creation) =
+        mpsc::channel(config.outgoing_units_capacity());
 
     let (parents_for_creator, parents_from_runway) = mpsc::unbounded();
     let creation_terminator = terminator.add_offspring_connection("creator");
     let creation_config = config.clone();
     let (starting_round_sender, starting_round) = oneshot::channel();
+    let (rounds_for_saver, rounds_from_creator) = mpsc::unbounded();
 
+    let creator_handle = config
+        .creator_handle()
+        .map(creation::CreatorHandle::for_session)
+        .unwrap_or_default();
     let creation_keychain = keychain.clone();
+    let creation_creator_handle = creator_handle.clone();
+    let parent_diversity = Arc::new(ParentDiversity::new(config.n_members()));
+    if let Some(status_monitor) = config.status_monitor() {
+        status_monitor.report_parent_diversity(parent_diversity.clone());
+    }
+    let creation_parent_diversity = parent_diversity.clone();
     let creation_handle = spawn_handle
         .spawn_essential("runway/creation", async move {
             creation::run(
@@ -746,6 +941,9 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
                     outgoing_units: new_units_for_runway,
                     incoming_parents: parents_from_runway,
                     data_provider,
+                    handle: creation_creator_handle,
+                    round_counter: RoundCounter::new(rounds_for_saver),
+                    parent_diversity: creation_parent_diversity,
                 },
                 creation_keychain,
                 starting_round,
@@ -773,7 +971,9 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
         let mut backup_saver = BackupSaver::new(
             backup_units_from_runway,
             backup_units_for_runway,
+            rounds_from_creator,
             backup_write,
+            config.sync_mode(),
         );
         async move {
             backup_saver.run(backup_saver_terminator).await;
@@ -800,6 +1000,7 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
             alerts_from_units,
         },
         alerter_handler,
+        config.alert_cooldown(),
     );
 
     let mut alerter_handle = spawn_handle
@@ -817,7 +1018,8 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
 
     let backup_loading_handle = spawn_handle
         .spawn_essential("runway/loading", {
-            let mut backup_loader = BackupLoader::new(backup_read, index, session_id);
+            let mut backup_loader = BackupLoader::new(backup_read, index, session_id)
+                .with_starting_round(config.starting_round());
             async move {
                 backup_loader
                     .run(
@@ -839,6 +1041,7 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
         unit_collections_sender,
         responses_from_runway,
         network_io.resolved_requests.clone(),
+        &mut config.randomness().clone(),
     ) {
         Ok(handle) => handle.fuse(),
         Err(_) => return,
@@ -864,6 +1067,16 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
                 responses_for_collection,
                 resolved_requests: network_io.resolved_requests,
                 new_units_from_creation,
+                election_config: ElectionConfig::from(&config),
+                status_monitor: config.status_monitor().cloned(),
+                creator_handle,
+                max_round: config.max_round(),
+                dag_observers: config
+                    .dag_observers()
+                    .into_iter()
+                    .chain([parent_diversity as Arc<dyn DagObserver<H, D, MK>>])
+                    .collect(),
+                unit_filter: config.unit_filter(),
             };
             let runway_terminator = terminator.add_offspring_connection("AlephBFT-runway");
             let validator = validator.clone();
@@ -918,8 +1131,45 @@ pub(crate) async fn run<H, D, US, UL, MK, DP, FH, SH>(
 
 #[cfg(test)]
 mod tests {
-    use crate::runway::RunwayStatus;
-    use aleph_bft_mock::Hasher64;
+    use crate::{
+        dag::{Dag, DagUnit},
+        runway::{remaining_catch_up, RunwayStatus, MAX_CATCH_UP_UNITS},
+        units::{random_full_parent_units_up_to, Unit, UnitStore, Validator},
+        NodeCount, Signed,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain};
+
+    #[test]
+    fn requests_rest_of_truncated_catch_up_response() {
+        let node_count = NodeCount(4);
+        let session_id = 43;
+        let to_round = 300;
+        let keychains: Vec<_> = node_count
+            .into_iterator()
+            .map(|node_id| Keychain::new(node_count, node_id))
+            .collect();
+        let mut store = UnitStore::<DagUnit<Hasher64, Data, Keychain>>::new(node_count);
+        let mut dag = Dag::new(Validator::new(session_id, keychains[0], 2137));
+        for unit in random_full_parent_units_up_to(to_round, node_count, session_id)
+            .into_iter()
+            .flatten()
+        {
+            let unit = Signed::sign(unit.clone(), &keychains[unit.creator().0]);
+            for unit in dag.add_unit(unit.into(), &store).units {
+                store.insert(unit);
+            }
+        }
+
+        let units = dag.export_rounds(0, to_round, MAX_CATCH_UP_UNITS, &store);
+        assert_eq!(units.len(), MAX_CATCH_UP_UNITS);
+        let from_round = remaining_catch_up(&units, to_round).expect("the response was truncated");
+        // The units of the highest included round might be incomplete.
+        assert_eq!(from_round, (MAX_CATCH_UP_UNITS / node_count.0 - 1) as u16);
+        let rest = dag.export_rounds(from_round, to_round, MAX_CATCH_UP_UNITS, &store);
+        assert_eq!(remaining_catch_up(&rest, to_round), None);
+
+        assert_eq!(remaining_catch_up(&units[..0], to_round), None);
+    }
 
     #[test]
     pub fn formats_missing_coords() {
@@ -0,0 +1,195 @@
+use crate::{creation::LivenessHandle, dag::ParentDiversity, NodeIndex, Round, RoundClock};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+struct Progress {
+    highest_created_round: Option<Round>,
+    highest_ordered_round: Option<Round>,
+    pending_parent_requests: usize,
+    known_equivocators: usize,
+    votes_cached: u64,
+    votes_computed: u64,
+    liveness: Option<LivenessHandle>,
+    round_clock: Option<Arc<RoundClock>>,
+    parent_diversity: Option<Arc<ParentDiversity>>,
+}
+
+/// A handle collecting the progress of a running member. Pass a clone to the [`Config`](crate::Config)
+/// and poll it with [`ConsensusStatus::gather`] whenever the status is needed.
+#[derive(Clone, Debug, Default)]
+pub struct StatusMonitor {
+    progress: Arc<Mutex<Progress>>,
+}
+
+impl StatusMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn report_created_round(&self, round: Round) {
+        let mut progress = self.progress.lock();
+        progress.highest_created_round = progress.highest_created_round.max(Some(round));
+    }
+
+    pub(crate) fn report_ordered_round(&self, round: Round) {
+        let mut progress = self.progress.lock();
+        progress.highest_ordered_round = progress.highest_ordered_round.max(Some(round));
+    }
+
+    pub(crate) fn report_pending_parent_requests(&self, requests: usize) {
+        self.progress.lock().pending_parent_requests = requests;
+    }
+
+    pub(crate) fn report_known_equivocators(&self, equivocators: usize) {
+        self.progress.lock().known_equivocators = equivocators;
+    }
+
+    pub(crate) fn report_vote_cached(&self) {
+        self.progress.lock().votes_cached += 1;
+    }
+
+    pub(crate) fn report_vote_computed(&self) {
+        self.progress.lock().votes_computed += 1;
+    }
+
+    /// A view of which nodes appear to have stopped producing units, once the member started creating units.
+    pub fn liveness(&self) -> Option<LivenessHandle> {
+        self.progress.lock().liveness.clone()
+    }
+
+    /// The times at which rounds were first observed, once the member started adding units to the Dag.
+    pub fn round_clock(&self) -> Option<Arc<RoundClock>> {
+        self.progress.lock().round_clock.clone()
+    }
+
+    /// How often the nodes cite each other as parents, once the member started running.
+    pub fn parent_diversity(&self) -> Option<Arc<ParentDiversity>> {
+        self.progress.lock().parent_diversity.clone()
+    }
+
+    pub(crate) fn report_parent_diversity(&self, parent_diversity: Arc<ParentDiversity>) {
+        self.progress.lock().parent_diversity = Some(parent_diversity);
+    }
+
+    pub(crate) fn report_round_clock(&self, round_clock: Arc<RoundClock>) {
+        self.progress.lock().round_clock = Some(round_clock);
+    }
+
+    pub(crate) fn report_liveness(&self, liveness: LivenessHandle) {
+        self.progress.lock().liveness = Some(liveness);
+    }
+}
+
+/// A snapshot of the health of a member's consensus.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConsensusStatus {
+    /// The highest round of a unit created by this node, `None` before the first one.
+    pub highest_created_round: Option<Round>,
+    /// The round of the most recently ordered head, `None` before the first one.
+    pub highest_ordered_round: Option<Round>,
+    /// How many rounds the ordering is behind the creation, `None` until both are known.
+    pub finalization_lag: Option<Round>,
+    /// The number of units whose parents are currently being requested.
+    pub pending_parent_requests: usize,
+    /// The number of nodes known to have created forks.
+    pub known_equivocators: usize,
+    /// The fraction of votes in elections that were already computed when needed, `None` before any vote.
+    pub cache_hit_rate: Option<f64>,
+    /// The nodes that stopped producing units while others continued, with the rounds they are silent since.
+    pub silent_nodes: Vec<(NodeIndex, Round)>,
+    /// The recent rate of the rounds, zero until it can be determined.
+    pub rounds_per_second: f64,
+}
+
+impl ConsensusStatus {
+    /// Gather the current status from the monitor.
+    pub fn gather(monitor: &StatusMonitor) -> Self {
+        let progress = monitor.progress.lock();
+        let finalization_lag = match (
+            progress.highest_created_round,
+            progress.highest_ordered_round,
+        ) {
+            (Some(created), Some(ordered)) => Some(created.saturating_sub(ordered)),
+            _ => None,
+        };
+        let votes = progress.votes_cached + progress.votes_computed;
+        let cache_hit_rate = match votes {
+            0 => None,
+            votes => Some(progress.votes_cached as f64 / votes as f64),
+        };
+        ConsensusStatus {
+            highest_created_round: progress.highest_created_round,
+            highest_ordered_round: progress.highest_ordered_round,
+            finalization_lag,
+            pending_parent_requests: progress.pending_parent_requests,
+            known_equivocators: progress.known_equivocators,
+            cache_hit_rate,
+            silent_nodes: progress
+                .liveness
+                .as_ref()
+                .map(|liveness| liveness.silent_nodes())
+                .unwrap_or_default(),
+            rounds_per_second: progress
+                .round_clock
+                .as_ref()
+                .map(|round_clock| round_clock.rounds_per_second())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ConsensusStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut status = serializer.serialize_struct("ConsensusStatus", 8)?;
+        status.serialize_field("highest_created_round", &self.highest_created_round)?;
+        status.serialize_field("highest_ordered_round", &self.highest_ordered_round)?;
+        status.serialize_field("finalization_lag", &self.finalization_lag)?;
+        status.serialize_field("pending_parent_requests", &self.pending_parent_requests)?;
+        status.serialize_field("known_equivocators", &self.known_equivocators)?;
+        status.serialize_field("cache_hit_rate", &self.cache_hit_rate)?;
+        let silent_nodes: Vec<(usize, Round)> = self
+            .silent_nodes
+            .iter()
+            .map(|(node_id, round)| (node_id.0, *round))
+            .collect();
+        status.serialize_field("silent_nodes", &silent_nodes)?;
+        status.serialize_field("rounds_per_second", &self.rounds_per_second)?;
+        status.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ConsensusStatus, StatusMonitor};
+
+    #[test]
+    fn gathers_reported_progress() {
+        let monitor = StatusMonitor::new();
+        assert_eq!(
+            ConsensusStatus::gather(&monitor),
+            ConsensusStatus::default()
+        );
+
+        monitor.report_created_round(7);
+        monitor.report_created_round(5);
+        monitor.report_ordered_round(3);
+        monitor.report_pending_parent_requests(2);
+        monitor.report_known_equivocators(1);
+        monitor.report_vote_cached();
+        monitor.report_vote_computed();
+        monitor.report_vote_computed();
+        monitor.report_vote_computed();
+
+        let status = ConsensusStatus::gather(&monitor);
+        assert_eq!(status.highest_created_round, Some(7));
+        assert_eq!(status.highest_ordered_round, Some(3));
+        assert_eq!(status.finalization_lag, Some(4));
+        assert_eq!(status.pending_parent_requests, 2);
+        assert_eq!(status.known_equivocators, 1);
+        assert_eq!(status.cache_hit_rate, Some(0.25));
+        assert!(status.silent_nodes.is_empty());
+    }
+}
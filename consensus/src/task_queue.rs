@@ -67,12 +67,12 @@ impl<T: Eq> TaskQueue<T> {
         })
     }
 
-    /// Returns `Some(task)` if `task` is the most overdue task, and `None` if there are no overdue
-    /// tasks.
-    pub fn pop_due_task(&mut self) -> Option<T> {
+    /// Returns `Some(task)` if `task` is the most overdue task at `now`, and `None` if there are no
+    /// tasks overdue at that time.
+    pub fn pop_due_task(&mut self, now: time::Instant) -> Option<T> {
         let scheduled_task = self.queue.peek_mut()?;
 
-        if scheduled_task.scheduled_time <= time::Instant::now() {
+        if scheduled_task.scheduled_time <= now {
             Some(PeekMut::pop(scheduled_task).task)
         } else {
             None
@@ -88,19 +88,19 @@ impl<T: Eq> TaskQueue<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
 
     #[test]
     fn test_scheduling() {
         let mut q = TaskQueue::new();
-        q.schedule_now(1);
-        q.schedule_in(2, Duration::from_millis(5));
-        q.schedule_in(3, Duration::from_millis(30));
+        let start = time::Instant::now();
+        q.schedule(1, start);
+        q.schedule(2, start + Duration::from_millis(5));
+        q.schedule(3, start + Duration::from_millis(30));
 
-        thread::sleep(Duration::from_millis(10));
+        let now = start + Duration::from_millis(10);
 
-        assert_eq!(Some(1), q.pop_due_task());
-        assert_eq!(Some(2), q.pop_due_task());
-        assert_eq!(None, q.pop_due_task());
+        assert_eq!(Some(1), q.pop_due_task(now));
+        assert_eq!(Some(2), q.pop_due_task(now));
+        assert_eq!(None, q.pop_due_task(now));
     }
 }
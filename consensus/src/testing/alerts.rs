@@ -225,6 +225,7 @@ impl TestCase {
                 alerts_from_units,
             },
             alerter_handler,
+            Duration::from_secs(60),
         );
 
         tokio::spawn(async move {
@@ -499,3 +500,37 @@ async fn notifies_only_about_multisigned_alert() {
         .unexpected_notification(ForkingNotification::Units(Vec::new()));
     test_case.run(own_index).await;
 }
+
+#[tokio::test]
+async fn alerts_once_about_repeated_forks() {
+    let n_members = NodeCount(7);
+    let own_index = NodeIndex(0);
+    let forker = NodeIndex(6);
+    let other_forker = NodeIndex(5);
+    let mut test_case = TestCase::new(n_members);
+    let alert = test_case.alert(own_index, test_case.fork_proof(forker, 0));
+    let another_fork_proof = (
+        test_case.unchecked_signed_unit(forker, 0, 0),
+        test_case.unchecked_signed_unit(forker, 0, 2),
+    );
+    let repeated_alert = test_case.alert(own_index, another_fork_proof);
+    // Alerts are handled in order, so the alert about the other forker comes after the repeated one.
+    let other_alert = test_case.alert(own_index, test_case.fork_proof(other_forker, 0));
+    let signed_alert = test_case.unchecked_signed(alert.clone(), own_index);
+    let signed_repeated_alert = test_case.unchecked_signed(repeated_alert.clone(), own_index);
+    let signed_other_alert = test_case.unchecked_signed(other_alert.clone(), own_index);
+    test_case
+        .incoming_alert(alert)
+        .incoming_alert(repeated_alert)
+        .incoming_alert(other_alert)
+        .outgoing_message(AlertMessage::ForkAlert(signed_alert), Recipient::Everyone)
+        .outgoing_message(
+            AlertMessage::ForkAlert(signed_other_alert),
+            Recipient::Everyone,
+        )
+        .unexpected_message(
+            AlertMessage::ForkAlert(signed_repeated_alert),
+            Recipient::Everyone,
+        );
+    test_case.run(own_index).await;
+}
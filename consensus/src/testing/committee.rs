@@ -0,0 +1,83 @@
+use crate::{
+    extension::ElectionConfig,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config, HonestMember,
+    },
+    Committee, CommitteeProvider, NodeCount, NodeIndex, SessionId, SpawnHandle,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+
+// Every session is run by a larger committee.
+struct GrowingCommittee;
+
+impl CommitteeProvider for GrowingCommittee {
+    type PublicKey = NodeIndex;
+
+    fn committee_for(&self, session: SessionId) -> Committee<NodeIndex> {
+        let n_members = NodeCount(4 + 3 * session as usize);
+        Committee {
+            n_members,
+            public_keys: n_members.into_iterator().collect(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn sessions_run_with_their_committees() {
+    init_log();
+    let n_batches = 5;
+    let base_config = gen_config(NodeIndex(0), NodeCount(1), gen_delay_config());
+    let spawner = Spawner::new();
+
+    for (session_id, threshold) in [(0, 3), (1, 5)] {
+        let n_members = GrowingCommittee.committee_for(session_id).n_members;
+        let (net_hub, networks) = Router::new(n_members, 1.0);
+        spawner.spawn("network-hub", net_hub);
+
+        let mut members = Vec::new();
+        for (network, _) in networks {
+            let (config, committee) = GrowingCommittee
+                .session_config(base_config.clone(), session_id, network.index())
+                .expect("the committee is valid");
+            assert_eq!(committee.public_keys.len(), n_members.0);
+            assert_eq!(
+                ElectionConfig::from(&config)
+                    .threshold(config.n_members())
+                    .0,
+                threshold
+            );
+            members.push(spawn_honest_member_with_config(
+                spawner,
+                config,
+                vec![],
+                network,
+                DataProvider::new(),
+            ));
+        }
+
+        let mut batches = Vec::new();
+        for member in members.iter_mut() {
+            let mut member_batches = Vec::new();
+            for _ in 0..n_batches {
+                member_batches.push(
+                    member
+                        .finalization_rx
+                        .next()
+                        .await
+                        .expect("the member is running"),
+                );
+            }
+            batches.push(member_batches);
+        }
+        assert!(batches.windows(2).all(|pair| pair[0] == pair[1]));
+
+        for HonestMember {
+            exit_tx, handle, ..
+        } in members
+        {
+            let _ = exit_tx.send(());
+            let _ = handle.await;
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use crate::{
+    backup::decode_units,
     testing::{init_log, spawn_honest_member, HonestMember},
-    NodeCount, SpawnHandle,
+    units::Unit,
+    NodeCount, NodeIndex, SpawnHandle,
 };
-use aleph_bft_mock::{Router, Spawner};
+use aleph_bft_mock::{Data, Hasher64, Router, Signature, Spawner};
 use futures::StreamExt;
 use serial_test::serial;
+use std::time::Duration;
 
 async fn honest_members_agree_on_batches(
     n_members: NodeCount,
@@ -91,3 +94,54 @@ async fn medium_honest_ten_crashes() {
 async fn medium_honest_ten_crashes_unreliable_network() {
     honest_members_agree_on_batches(31.into(), 21.into(), 5, 0.9).await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn exiting_member_terminates_all_tasks() {
+    init_log();
+    let n_members = NodeCount(4);
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            spawn_honest_member(spawner, ix, n_members, vec![], network)
+        })
+        .collect();
+
+    for _ in 0..n_members.0 * 3 {
+        members[0].finalization_rx.next().await.unwrap();
+    }
+    let HonestMember {
+        saved_state,
+        exit_tx,
+        handle,
+        ..
+    } = members.remove(0);
+    let _ = exit_tx.send(());
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("all the tasks of the member should terminate")
+        .expect("the member should terminate cleanly");
+
+    // Every unit got written in full, and the member saved its own units of all the rounds it reached.
+    let saved_units = saved_state.lock().clone();
+    let mut own_rounds = Vec::new();
+    for unit in
+        decode_units::<Hasher64, Data, Signature>(&saved_units).expect("units are saved in full")
+    {
+        if unit.as_signable().creator() == NodeIndex(0) {
+            own_rounds.push(unit.as_signable().round());
+        }
+    }
+    assert!(!own_rounds.is_empty());
+    let expected_rounds: Vec<_> = (0..own_rounds.len() as u16).collect();
+    assert_eq!(own_rounds, expected_rounds);
+
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+}
@@ -1,10 +1,13 @@
 use crate::{
-    testing::{init_log, spawn_honest_member, HonestMember, Network, ReconnectSender},
-    units::{UncheckedSignedUnit, Unit, UnitCoord},
-    NodeCount, NodeIndex, SpawnHandle, TaskHandle,
+    backup::decode_units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_config, HonestMember, Network, ReconnectSender,
+    },
+    units::{Unit, UnitCoord},
+    Config, NodeCount, NodeIndex, SpawnHandle, TaskHandle,
 };
-use aleph_bft_mock::{Data, Hasher64, Router, Signature, Spawner};
-use codec::Decode;
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
 use futures::{
     channel::{mpsc, oneshot},
     StreamExt,
@@ -128,8 +131,7 @@ async fn reconnect_nodes(
 fn verify_backup(buf: &mut &[u8]) -> HashSet<UnitCoord> {
     let mut already_saved = HashSet::new();
 
-    while !buf.is_empty() {
-        let unit = <UncheckedSignedUnit<Hasher64, Data, Signature>>::decode(buf).unwrap();
+    for unit in decode_units::<Hasher64, Data, Signature>(buf).unwrap() {
         let full_unit = unit.as_signable();
         let coord = full_unit.coord();
         let parent_ids = &full_unit.as_pre_unit().control_hash().parents_mask;
@@ -145,6 +147,20 @@ fn verify_backup(buf: &mut &[u8]) -> HashSet<UnitCoord> {
     already_saved
 }
 
+/// Checks that no two different units of the same creator were saved for the same round in any of
+/// the backups.
+fn verify_no_forks(backups: &[&[u8]]) {
+    let mut saved = HashMap::new();
+    for backup in backups {
+        for unit in decode_units::<Hasher64, Data, Signature>(backup).unwrap() {
+            let full_unit = unit.as_signable();
+            if let Some(hash) = saved.insert(full_unit.coord(), full_unit.hash()) {
+                assert_eq!(hash, full_unit.hash(), "forked at {:?}", full_unit.coord());
+            }
+        }
+    }
+}
+
 /// Tests that finalization continues after some nodes restart.
 ///
 /// Performs the following steps:
@@ -157,6 +173,8 @@ fn verify_backup(buf: &mut &[u8]) -> HashSet<UnitCoord> {
 /// 5. Restarts the killed nodes.
 /// 6. Checks that (after some time) at least twice as many items are finalized and that all nodes
 ///    finalized the same items.
+/// 7. Checks that the restarted nodes did not create units conflicting with the ones saved before
+///    the crash.
 ///
 /// The reason it kills more than f nodes is that we want to check that (at least some of) the
 /// restarted nodes take part in finalization. As it stands, the system does not guarantee that a
@@ -228,6 +246,8 @@ async fn crashed_nodes_recover(n_members: NodeCount, n_batches: usize) {
 
         let saved_after_coords = verify_backup(&mut &saved_units.lock()[..]);
         assert!(saved_before_coords.is_subset(&saved_after_coords));
+        // The restarted node continued from its backup instead of recreating its units.
+        verify_no_forks(&[&saved_units_before, &saved_units.lock()]);
     }
 
     shutdown(node_data).await;
@@ -264,6 +284,57 @@ async fn saves_units_properly() {
     }
 }
 
+async fn run_single_member(
+    spawner: &Spawner,
+    config: Config,
+    units: Vec<u8>,
+    n_data: usize,
+) -> Vec<u8> {
+    let (net_hub, mut networks) = Router::new(config.n_members(), 1.0);
+    spawner.spawn("network-hub", net_hub);
+    let (network, _) = networks.pop().expect("there is a single network");
+    let HonestMember {
+        mut finalization_rx,
+        saved_state,
+        exit_tx,
+        handle,
+    } = spawn_honest_member_with_config(*spawner, config, units, network, DataProvider::new());
+    for _ in 0..n_data {
+        finalization_rx.next().await.expect("the member is running");
+    }
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+    let saved_units = saved_state.lock().clone();
+    saved_units
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn restarted_member_creates_from_configured_round() {
+    init_log();
+    let n_members = NodeCount(1);
+    let node_ix = NodeIndex(0);
+    let spawner = Spawner::new();
+    let config = gen_config(node_ix, n_members, gen_delay_config());
+
+    let saved_units = run_single_member(&spawner, config.clone(), vec![], 5).await;
+    let next_round = verify_backup(&mut &saved_units[..])
+        .iter()
+        .map(|coord| coord.round() + 1)
+        .max()
+        .expect("units were created");
+
+    let config = config.with_starting_round(next_round);
+    // The data of the units from the backup gets finalized again.
+    let resaved_units = run_single_member(&spawner, config, saved_units, 10).await;
+    let first_created_round = verify_backup(&mut &resaved_units[..])
+        .iter()
+        .map(|coord| coord.round())
+        .filter(|round| *round >= next_round)
+        .min();
+    assert_eq!(first_created_round, Some(next_round));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn small_node_crash_recovery_small() {
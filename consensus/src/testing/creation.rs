@@ -1,27 +1,52 @@
 use crate::{
-    creation::{run, IO},
-    testing::{gen_config, gen_delay_config},
-    units::{SignedUnit as GenericSignedUnit, Unit as GenericUnit},
-    NodeCount, Receiver, Round, Sender, Terminator,
+    backup::{RoundAdvance, RoundCounter},
+    creation::{run, CreatorHandle, IO},
+    dag::ParentDiversity,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_config, HonestMember,
+    },
+    units::{
+        random_full_parent_units_up_to, ControlHash, SignedUnit as GenericSignedUnit,
+        Unit as GenericUnit, UnitCoord,
+    },
+    ConsensusStatus, DelayConfig, NodeCount, NodeIndex, NodeMap, Receiver, Round, Sender,
+    SpawnHandle, StatusMonitor, Terminator,
 };
-use aleph_bft_mock::{Data, DataProvider, Hasher64, Keychain};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Keychain, Router, Spawner};
 use futures::{
     channel::{mpsc, oneshot},
     FutureExt, StreamExt,
 };
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 type SignedUnit = GenericSignedUnit<Hasher64, Data, Keychain>;
 
+// Persists every round immediately.
+fn instant_round_counter() -> RoundCounter {
+    let (rounds_for_saver, mut rounds_from_creator) = mpsc::unbounded::<RoundAdvance>();
+    tokio::spawn(async move {
+        while let Some((_, persisted)) = rounds_from_creator.next().await {
+            let _ = persisted.send(());
+        }
+    });
+    RoundCounter::new(rounds_for_saver)
+}
+
 struct TestController {
     max_round_per_creator: Vec<Round>,
     parents_for_creators: Sender<SignedUnit>,
-    units_from_creators: Receiver<SignedUnit>,
+    units_from_creators: mpsc::Receiver<SignedUnit>,
 }
 
 impl TestController {
     fn new(
         parents_for_creators: Sender<SignedUnit>,
-        units_from_creators: Receiver<SignedUnit>,
+        units_from_creators: mpsc::Receiver<SignedUnit>,
         n_members: NodeCount,
     ) -> Self {
         TestController {
@@ -56,10 +81,20 @@ struct TestSetup {
     handles: Vec<tokio::task::JoinHandle<()>>,
     units_from_controller: Receiver<SignedUnit>,
     units_for_creators: Vec<Sender<SignedUnit>>,
+    creator_handles: Vec<CreatorHandle>,
 }
 
 fn setup_test(n_members: NodeCount) -> TestSetup {
-    let (units_for_controller, units_from_creators) = mpsc::unbounded();
+    setup_test_with_delay_config(n_members, gen_delay_config)
+}
+
+fn setup_test_with_delay_config(
+    n_members: NodeCount,
+    delay_config: impl Fn() -> DelayConfig,
+) -> TestSetup {
+    let (units_for_controller, units_from_creators) = mpsc::channel(
+        gen_config(NodeIndex(0), n_members, delay_config()).outgoing_units_capacity(),
+    );
     let (units_for_creators, units_from_controller) = mpsc::unbounded();
 
     let test_controller = TestController::new(units_for_creators, units_from_creators, n_members);
@@ -67,16 +102,22 @@ fn setup_test(n_members: NodeCount) -> TestSetup {
     let mut handles = Vec::new();
     let mut killers = Vec::new();
     let mut units_for_creators = Vec::new();
+    let mut creator_handles = Vec::new();
 
     for node_ix in n_members.into_iterator() {
         let (parents_for_creator, parents_from_controller) = mpsc::unbounded();
+        let creator_handle = CreatorHandle::new();
+        creator_handles.push(creator_handle.clone());
 
         let io = IO {
             incoming_parents: parents_from_controller,
             outgoing_units: units_for_controller.clone(),
             data_provider: DataProvider::new(),
+            handle: creator_handle,
+            round_counter: instant_round_counter(),
+            parent_diversity: Arc::new(ParentDiversity::new(n_members)),
         };
-        let config = gen_config(node_ix, n_members, gen_delay_config());
+        let config = gen_config(node_ix, n_members, delay_config());
         let (starting_round_for_consensus, starting_round) = oneshot::channel();
 
         units_for_creators.push(parents_for_creator);
@@ -109,6 +150,7 @@ fn setup_test(n_members: NodeCount) -> TestSetup {
         handles,
         units_from_controller,
         units_for_creators,
+        creator_handles,
     }
 }
 
@@ -134,6 +176,7 @@ async fn synchronous_creators_should_create_dag() {
         handles,
         mut units_from_controller,
         units_for_creators,
+        ..
     } = setup_test(n_members);
     loop {
         futures::select! {
@@ -168,6 +211,7 @@ async fn disconnected_creators_should_create_dag() {
         handles,
         mut units_from_controller,
         units_for_creators,
+        ..
     } = setup_test(n_members);
     loop {
         futures::select! {
@@ -245,6 +289,7 @@ async fn late_creators_should_create_dag() {
         handles,
         mut units_from_controller,
         units_for_creators,
+        ..
     } = setup_test(n_members);
     let mut dropped_units = Vec::new();
     loop {
@@ -294,3 +339,341 @@ async fn late_creators_should_create_dag() {
     );
     finish(killers, handles).await;
 }
+
+// This test checks if a single creator waits between creating units for as long as the delay
+// schedule requires.
+#[tokio::test]
+async fn creator_respects_delay_schedule() {
+    let n_members = NodeCount(1);
+    let max_round: Round = 5;
+    // 20, 40, 60, 80, ...
+    let delay = |round: usize| Duration::from_millis(20 * (round as u64 + 1));
+    let start = Instant::now();
+    let TestSetup {
+        mut test_controller,
+        killers,
+        handles,
+        mut units_from_controller,
+        units_for_creators,
+        ..
+    } = setup_test_with_delay_config(n_members, || DelayConfig {
+        unit_creation_delay: Arc::new(delay),
+        ..gen_delay_config()
+    });
+    let mut expected_elapsed = Duration::ZERO;
+    for round in 0..max_round {
+        let unit = test_controller
+            .units_from_creators
+            .next()
+            .await
+            .expect("Creator output channel isn't closed.");
+        assert_eq!(unit.round(), round);
+        expected_elapsed += delay(round.into());
+        assert!(start.elapsed() >= expected_elapsed);
+        test_controller
+            .parents_for_creators
+            .unbounded_send(unit)
+            .expect("Creator input channel isn't closed.");
+        let unit = units_from_controller
+            .next()
+            .await
+            .expect("Channel from controller should be open.");
+        units_for_creators[0]
+            .unbounded_send(unit)
+            .expect("Channel to creator should be open");
+    }
+    finish(killers, handles).await;
+}
+
+// This test checks that a paused creator stops creating units while the others keep advancing
+// the dag, and that after resuming it continues with the next round on top of the newest units.
+#[tokio::test(flavor = "multi_thread", worker_threads = 3)]
+async fn paused_creator_resumes_at_next_round() {
+    let n_members = NodeCount(4);
+    let paused = NodeIndex(3);
+    let TestSetup {
+        mut test_controller,
+        killers,
+        handles,
+        units_for_creators,
+        creator_handles,
+        ..
+    } = setup_test(n_members);
+    let mut hashes = HashMap::new();
+    let mut last_round_before_resume = None;
+    let mut pausing_since = None;
+    loop {
+        let unit = test_controller
+            .units_from_creators
+            .next()
+            .await
+            .expect("Creator output channel isn't closed.");
+        hashes.insert(unit.coord(), unit.hash());
+        if unit.creator() == paused {
+            match pausing_since {
+                Some(round) => {
+                    // The creation of at most one unit could have been in progress when pausing.
+                    assert_eq!(last_round_before_resume, None);
+                    assert_eq!(unit.round(), round + 1);
+                    last_round_before_resume = Some(unit.round());
+                }
+                None if unit.round() == 5 => {
+                    creator_handles[paused.0].pause();
+                    pausing_since = Some(unit.round());
+                    last_round_before_resume = None;
+                }
+                None => (),
+            }
+        } else if unit.round() == 15 {
+            break;
+        }
+        for units_for_creator in &units_for_creators {
+            units_for_creator
+                .unbounded_send(unit.clone())
+                .expect("Channel to creator should be open");
+        }
+    }
+    let last_round = last_round_before_resume.or(pausing_since).expect("paused");
+    creator_handles[paused.0].resume();
+    // Keep the others going, so that the resumed creator has parents to choose from.
+    let resumed_unit = loop {
+        let unit = test_controller
+            .units_from_creators
+            .next()
+            .await
+            .expect("Creator output channel isn't closed.");
+        hashes.insert(unit.coord(), unit.hash());
+        if unit.creator() == paused {
+            break unit;
+        }
+        for units_for_creator in &units_for_creators {
+            units_for_creator
+                .unbounded_send(unit.clone())
+                .expect("Channel to creator should be open");
+        }
+    };
+    assert_eq!(resumed_unit.round(), last_round + 1);
+    let control_hash = resumed_unit.control_hash();
+    assert!(control_hash.parents_mask.contains(paused));
+    let mut parents = NodeMap::with_size(n_members);
+    for parent in control_hash.parents() {
+        let coord = UnitCoord::new(last_round, parent);
+        parents.insert(parent, *hashes.get(&coord).expect("parent was created"));
+    }
+    assert_eq!(
+        control_hash.combined_hash,
+        ControlHash::<Hasher64>::combine_hashes(&parents)
+    );
+    finish(killers, handles).await;
+}
+
+// This test checks that a creator whose units do not get dispatched stops creating new ones
+// once the outgoing channel is full, instead of buffering them, and continues once they get dispatched.
+#[tokio::test]
+async fn blocked_dispatch_stops_creator() {
+    let n_members = NodeCount(4);
+    let node_ix = NodeIndex(0);
+    let capacity = 2;
+    let max_round = 20;
+    let (parents_for_creator, parents_from_test) = mpsc::unbounded();
+    // All the parents are available upfront, so only the outgoing channel can hold the creator back.
+    for unit in random_full_parent_units_up_to(max_round, n_members, 0)
+        .into_iter()
+        .flatten()
+    {
+        parents_for_creator
+            .unbounded_send(unit)
+            .expect("Channel to creator should be open");
+    }
+    let (units_for_dispatch, mut units_from_creator) = mpsc::channel(capacity);
+    let io = IO {
+        incoming_parents: parents_from_test,
+        outgoing_units: units_for_dispatch,
+        data_provider: DataProvider::new(),
+        handle: CreatorHandle::new(),
+        round_counter: instant_round_counter(),
+        parent_diversity: Arc::new(ParentDiversity::new(n_members)),
+    };
+    let config =
+        gen_config(node_ix, n_members, gen_delay_config()).with_outgoing_units_capacity(capacity);
+    let (starting_round_for_consensus, starting_round) = oneshot::channel();
+    let (killer, exit) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
+        run(
+            config,
+            io,
+            Keychain::new(n_members, node_ix),
+            starting_round,
+            Terminator::create_root(exit, "AlephBFT-creator"),
+        )
+        .await
+    });
+    starting_round_for_consensus
+        .send(Some(0))
+        .expect("Sending the starting round should work.");
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let mut created = Vec::new();
+    while let Ok(Some(unit)) = units_from_creator.try_next() {
+        created.push(unit);
+    }
+    // The channel holds one additional unit for every sender.
+    assert_eq!(created.len(), capacity + 1);
+    // Having space in the channel again, the creator continues with the following rounds.
+    let mut expected_round = created.len() as Round;
+    while expected_round <= max_round {
+        let unit = units_from_creator
+            .next()
+            .await
+            .expect("Creator output channel isn't closed.");
+        assert_eq!(unit.round(), expected_round);
+        expected_round += 1;
+    }
+    drop(parents_for_creator);
+    finish(vec![killer], vec![handle]).await;
+}
+
+// This test checks that a creator stops once its units get too far ahead of the ordering,
+// and continues as the ordering catches up.
+#[tokio::test]
+async fn lagging_ordering_stops_creator() {
+    let n_members = NodeCount(4);
+    let node_ix = NodeIndex(0);
+    let max_lag = 3;
+    let max_round = 20;
+    let (parents_for_creator, parents_from_test) = mpsc::unbounded();
+    // All the parents are available upfront, so only the ordering can hold the creator back.
+    for unit in random_full_parent_units_up_to(max_round, n_members, 0)
+        .into_iter()
+        .flatten()
+    {
+        parents_for_creator
+            .unbounded_send(unit)
+            .expect("Channel to creator should be open");
+    }
+    let config = gen_config(node_ix, n_members, gen_delay_config())
+        .with_outgoing_units_capacity(max_round.into())
+        .with_max_finalization_lag(max_lag);
+    let (units_for_dispatch, mut units_from_creator) =
+        mpsc::channel(config.outgoing_units_capacity());
+    let creator_handle = CreatorHandle::new();
+    let io = IO {
+        incoming_parents: parents_from_test,
+        outgoing_units: units_for_dispatch,
+        data_provider: DataProvider::new(),
+        handle: creator_handle.clone(),
+        round_counter: instant_round_counter(),
+        parent_diversity: Arc::new(ParentDiversity::new(n_members)),
+    };
+    let (starting_round_for_consensus, starting_round) = oneshot::channel();
+    let (killer, exit) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
+        run(
+            config,
+            io,
+            Keychain::new(n_members, node_ix),
+            starting_round,
+            Terminator::create_root(exit, "AlephBFT-creator"),
+        )
+        .await
+    });
+    starting_round_for_consensus
+        .send(Some(0))
+        .expect("Sending the starting round should work.");
+
+    let mut expected_round = 0;
+    // Nothing got ordered yet, so the lag is counted from round 0.
+    for ordered_round in [None, Some(5), Some(max_round - max_lag)] {
+        if let Some(round) = ordered_round {
+            creator_handle.report_ordered_round(round);
+        }
+        let last_allowed = ordered_round.unwrap_or(0) + max_lag;
+        while expected_round <= last_allowed {
+            let unit = units_from_creator
+                .next()
+                .await
+                .expect("Creator output channel isn't closed.");
+            assert_eq!(unit.round(), expected_round);
+            expected_round += 1;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(units_from_creator.try_next().is_err());
+    }
+    drop(parents_for_creator);
+    finish(vec![killer], vec![handle]).await;
+}
+
+#[tokio::test]
+async fn member_pauses_and_resumes_creation_with_configured_handle() {
+    init_log();
+    let n_members = NodeCount(4);
+    let paused_node = NodeIndex(0);
+    let n_batches = 5;
+
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let spawner = Spawner::new();
+    spawner.spawn("network-hub", net_hub);
+
+    let creator_handle = CreatorHandle::new();
+    let monitor = StatusMonitor::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let member = match ix == paused_node {
+            true => {
+                let config = gen_config(ix, n_members, gen_delay_config())
+                    .with_creator_handle(creator_handle.clone())
+                    .with_status_monitor(monitor.clone());
+                spawn_honest_member_with_config(
+                    spawner,
+                    config,
+                    vec![],
+                    network,
+                    DataProvider::new(),
+                )
+            }
+            false => spawn_honest_member(spawner, ix, n_members, vec![], network),
+        };
+        members.push(member);
+    }
+    let created_round = || ConsensusStatus::gather(&monitor).highest_created_round;
+
+    for _ in 0..n_batches {
+        members[paused_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    creator_handle.pause();
+    // A unit whose creation already started might still get created.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let paused_at = created_round().expect("units were created");
+    // The paused member keeps ordering the units of the others.
+    for _ in 0..n_batches {
+        members[paused_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    assert_eq!(created_round(), Some(paused_at));
+
+    creator_handle.resume();
+    while created_round() == Some(paused_at) {
+        members[paused_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    assert!(created_round() > Some(paused_at));
+
+    for HonestMember {
+        exit_tx, handle, ..
+    } in members
+    {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+}
@@ -4,14 +4,17 @@ use crate::{
         Dag as GenericDag, DagResult, ReconstructedUnit as GenericReconstructedUnit,
         Request as GenericRequest,
     },
-    extension::Ordering,
+    extension::{ElectionConfig, Ordering},
+    testing::{gen_config, gen_delay_config, init_log, spawn_honest_member_with_config},
     units::{
         ControlHash, FullUnit, PreUnit, SignedUnit as GenericSignedUnit, Unit, UnitStore,
         UnitWithParents as _, Validator,
     },
-    FinalizationHandler, NodeCount, NodeIndex, NodeMap, NodeSubset, Round, Signed,
+    DagObserver, DagUnit, FinalizationHandler, NodeCount, NodeIndex, NodeMap, NodeSubset, Round,
+    Signed, SignedUnit as PublicSignedUnit, SpawnHandle, UnitFilter,
 };
-use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain};
+use aleph_bft_mock::{Data, DataProvider, Hash64, Hasher64, Keychain, Router, Spawner};
+use futures::StreamExt;
 use log::debug;
 use parking_lot::Mutex;
 use rand::{distributions::Open01, prelude::*};
@@ -224,13 +227,19 @@ impl FinalizationHandler<Data> for RecordingHandler {
 }
 
 fn run_consensus_on_dag(
+    n_members: NodeCount,
     units: Vec<UnitWithParents>,
     forker_units: HashMap<NodeIndex, Vec<UnitWithParents>>,
 ) -> Vec<Data> {
     let node_id = NodeIndex(0);
     let feeder = DagFeeder::new(node_id, units, forker_units);
     let (recording_handler, finalized) = RecordingHandler::new();
-    let mut ordering = Ordering::new(recording_handler);
+    let mut ordering = Ordering::new(
+        n_members,
+        recording_handler,
+        ElectionConfig::default(),
+        Round::MAX,
+    );
     for unit in feeder.feed() {
         ordering.add_unit(unit);
     }
@@ -361,7 +370,7 @@ async fn ordering_random_dag_consistency_under_permutations() {
         let n_members = NodeCount(rng.gen_range(1..11));
         let height = rng.gen_range(3..11);
         let (mut units, forker_units) = generate_random_dag(n_members, height, seed);
-        let finalized_data = run_consensus_on_dag(units.clone(), forker_units.clone());
+        let finalized_data = run_consensus_on_dag(n_members, units.clone(), forker_units.clone());
         debug!(target: "dag-test",
             "seed {:?} n_members {:?} height {:?} data_len {:?}",
             seed,
@@ -371,7 +380,8 @@ async fn ordering_random_dag_consistency_under_permutations() {
         );
         for i in 0..8 {
             units.shuffle(&mut rng);
-            let other_finalized_data = run_consensus_on_dag(units.clone(), forker_units.clone());
+            let other_finalized_data =
+                run_consensus_on_dag(n_members, units.clone(), forker_units.clone());
             if other_finalized_data != finalized_data {
                 debug!(target: "dag-test",
                     "seed {:?} n_members {:?} height {:?} i {:?}",
@@ -387,3 +397,121 @@ async fn ordering_random_dag_consistency_under_permutations() {
         }
     }
 }
+
+#[derive(Default)]
+struct RecordingObserver {
+    added: Mutex<Vec<(Hash64, NodeIndex, Vec<Hash64>)>>,
+}
+
+impl DagObserver<Hasher64, Data, Keychain> for RecordingObserver {
+    fn unit_added(&self, unit: &DagUnit<Hasher64, Data, Keychain>) {
+        let parents = unit.parents().values().cloned().collect();
+        self.added
+            .lock()
+            .push((unit.hash(), unit.creator(), parents));
+    }
+}
+
+#[tokio::test]
+async fn member_reports_units_added_to_dag_to_configured_observer() {
+    init_log();
+    let n_members = NodeCount(4);
+    let n_batches = 5;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+
+    let observer = Arc::new(RecordingObserver::default());
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let mut config = gen_config(ix, n_members, gen_delay_config());
+        if ix == NodeIndex(0) {
+            config = config.with_dag_observer::<Hasher64, Data, Keychain>(observer.clone());
+        }
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            network,
+            DataProvider::new(),
+        ));
+    }
+    for _ in 0..n_batches {
+        members[0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    let added = observer.added.lock();
+    assert!(!added.is_empty());
+    let mut seen = HashSet::new();
+    for (hash, _, parents) in added.iter() {
+        for parent in parents {
+            assert!(seen.contains(parent), "unit reported before its parent");
+        }
+        assert!(seen.insert(*hash), "unit reported twice");
+    }
+}
+
+struct CreatorFilter {
+    rejected: NodeIndex,
+}
+
+impl UnitFilter<Hasher64, Data, Keychain> for CreatorFilter {
+    fn accept(&self, unit: &PublicSignedUnit<Hasher64, Data, Keychain>) -> bool {
+        unit.as_signable().creator() != self.rejected
+    }
+}
+
+#[tokio::test]
+async fn members_drop_units_rejected_by_configured_filter() {
+    init_log();
+    let n_members = NodeCount(4);
+    let rejected = NodeIndex(3);
+    let n_batches = 5;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+
+    let observer = Arc::new(RecordingObserver::default());
+    let filter = Arc::new(CreatorFilter { rejected });
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let mut config = gen_config(ix, n_members, gen_delay_config())
+            .with_unit_filter::<Hasher64, Data, Keychain>(filter.clone());
+        if ix == NodeIndex(0) {
+            config = config.with_dag_observer::<Hasher64, Data, Keychain>(observer.clone());
+        }
+        members.push(spawn_honest_member_with_config(
+            spawner,
+            config,
+            vec![],
+            network,
+            DataProvider::new(),
+        ));
+    }
+    // The other three nodes are enough to keep finalizing without the rejected one.
+    for _ in 0..n_batches {
+        members[0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    for member in members {
+        let _ = member.exit_tx.send(());
+        let _ = member.handle.await;
+    }
+
+    let added = observer.added.lock();
+    assert!(!added.is_empty());
+    assert!(added.iter().all(|(_, creator, _)| *creator != rejected));
+}
@@ -0,0 +1,178 @@
+use crate::{
+    backup::decode_units,
+    create_config, run_session,
+    testing::{
+        gen_delay_config, init_log, spawn_honest_member_with_data_provider, HonestMember,
+        NetworkData,
+    },
+    units::Unit,
+    FinalizationHandler, LocalIO, NodeCount, Round, SpawnHandle, Terminator,
+};
+use aleph_bft_mock::{
+    Data, DataProvider, Hasher64, Keychain, Loader, Router, Saver, Signature, Spawner,
+};
+use futures::{
+    channel::{
+        mpsc::{self, UnboundedSender},
+        oneshot,
+    },
+    StreamExt,
+};
+use parking_lot::Mutex;
+use serial_test::serial;
+use std::{sync::Arc, time::Duration};
+
+async fn honest_members_finalize_all_data(n_members: NodeCount, n_data: usize) {
+    init_log();
+    let spawner = Spawner::new();
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut finalization_rxs = Vec::new();
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+
+    for (network, _) in networks {
+        let ix = network.index();
+        let HonestMember {
+            finalization_rx,
+            exit_tx,
+            handle,
+            ..
+        } = spawn_honest_member_with_data_provider(
+            spawner,
+            ix,
+            n_members,
+            vec![],
+            network,
+            DataProvider::new_finite(n_data),
+        );
+        finalization_rxs.push(finalization_rx);
+        exits.push(exit_tx);
+        handles.push(handle);
+    }
+
+    // Every member provides the same data items, so we expect all of them once per member.
+    let mut expected_data: Vec<_> = (1..=n_data as u32)
+        .flat_map(|data| std::iter::repeat(data).take(n_members.0))
+        .collect();
+    expected_data.sort();
+    let mut finalized = Vec::new();
+    for mut rx in finalization_rxs.drain(..) {
+        let mut finalized_per_ix = Vec::new();
+        for _ in 0..expected_data.len() {
+            finalized_per_ix.push(rx.next().await.expect("all data gets finalized"));
+        }
+        finalized.push(finalized_per_ix);
+    }
+
+    for finalized_per_ix in finalized.iter().skip(1) {
+        assert_eq!(&finalized[0], finalized_per_ix);
+    }
+    let mut finalized_data = finalized[0].clone();
+    finalized_data.sort();
+    assert_eq!(finalized_data, expected_data);
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn small_honest_finalize_all_data() {
+    honest_members_finalize_all_data(4.into(), 10).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn medium_honest_finalize_all_data() {
+    honest_members_finalize_all_data(16.into(), 5).await;
+}
+
+struct FinishingHandler {
+    finished_tx: UnboundedSender<()>,
+}
+
+impl FinalizationHandler<Data> for FinishingHandler {
+    fn data_finalized(&mut self, _data: Data) {}
+
+    fn session_finished(&mut self) {
+        self.finished_tx
+            .unbounded_send(())
+            .expect("receiver should be open");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn honest_members_finish_session_at_max_round() {
+    init_log();
+    let n_members = NodeCount(4);
+    let max_round: Round = 10;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::<NetworkData>::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+    let (finished_tx, mut finished_rx) = mpsc::unbounded();
+
+    let mut exits = Vec::new();
+    let mut handles = Vec::new();
+    let mut backups = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let config = create_config(
+            n_members,
+            ix,
+            0,
+            max_round,
+            gen_delay_config(),
+            Duration::ZERO,
+        )
+        .expect("Should always succeed with Duration::ZERO");
+        let backup = Arc::new(Mutex::new(Vec::new()));
+        let local_io = LocalIO::new(
+            DataProvider::new(),
+            FinishingHandler {
+                finished_tx: finished_tx.clone(),
+            },
+            Saver::from(backup.clone()),
+            Loader::new(Vec::new()),
+        );
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let keychain = Keychain::new(n_members, ix);
+        handles.push(spawner.spawn_essential(
+            "member",
+            run_session(
+                config,
+                local_io,
+                network,
+                keychain,
+                spawner,
+                Terminator::create_root(exit_rx, "AlephBFT-member"),
+            ),
+        ));
+        exits.push(exit_tx);
+        backups.push(backup);
+    }
+
+    for _ in 0..n_members.0 {
+        finished_rx.next().await.expect("all members finish");
+    }
+    for exit in exits {
+        let _ = exit.send(());
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+    for backup in backups {
+        let backup = backup.lock();
+        let mut highest_round = 0;
+        for unit in decode_units::<Hasher64, Data, Signature>(&backup).expect("backup is correct") {
+            highest_round = highest_round.max(unit.as_signable().round());
+        }
+        assert_eq!(highest_round, max_round);
+    }
+    // Every member finishes only once.
+    assert!(finished_rx.try_next().is_err());
+}
@@ -1,10 +1,17 @@
 #![cfg(test)]
 mod alerts;
 mod byzantine;
+mod committee;
 mod crash;
 mod crash_recovery;
 mod creation;
 mod dag;
+mod finalization;
+mod network;
+mod partition;
+mod randomness;
+mod replay;
+mod status;
 mod unreliable;
 
 use crate::{
@@ -16,19 +23,76 @@ use aleph_bft_mock::{
     PartialMultisignature, ReconnectSender as ReconnectSenderGeneric, Saver, Signature, Spawner,
 };
 use futures::channel::{mpsc::UnboundedReceiver, oneshot};
+use log::{Level, Log, Metadata, Record};
 use parking_lot::Mutex;
-use std::{sync::Arc, time::Duration};
+use std::{cell::RefCell, sync::Arc, time::Duration};
 
 pub type NetworkData = crate::NetworkData<Hasher64, Data, Signature, PartialMultisignature>;
 
 pub type Network = MockNetwork<NetworkData>;
 pub type ReconnectSender = ReconnectSenderGeneric<NetworkData>;
 
+/// A log record captured by `capture_logs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+thread_local! {
+    static CAPTURED_RECORDS: RefCell<Option<Vec<CapturedRecord>>> = RefCell::new(None);
+}
+
+// Passes records to env_logger, additionally capturing them for the threads running `capture_logs`.
+struct TestLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for TestLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        CAPTURED_RECORDS.with(|captured| {
+            if let Some(captured) = captured.borrow_mut().as_mut() {
+                captured.push(CapturedRecord {
+                    level: record.level(),
+                    target: record.target().to_string(),
+                    message: record.args().to_string(),
+                });
+            }
+        });
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
 pub fn init_log() {
-    let _ = env_logger::builder()
+    let inner = env_logger::builder()
         .filter_level(log::LevelFilter::max())
         .is_test(true)
-        .try_init();
+        .build();
+    if log::set_boxed_logger(Box::new(TestLogger { inner })).is_ok() {
+        log::set_max_level(log::LevelFilter::max());
+    }
+}
+
+/// Runs the function, returning the records it logged on the current thread together with its result.
+pub fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<CapturedRecord>) {
+    init_log();
+    CAPTURED_RECORDS.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    let result = f();
+    let records = CAPTURED_RECORDS
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default();
+    (result, records)
 }
 
 pub fn gen_delay_config() -> DelayConfig {
@@ -70,9 +134,37 @@ pub fn spawn_honest_member(
     units: Vec<u8>,
     network: impl 'static + NetworkT<NetworkData>,
 ) -> HonestMember {
-    let data_provider = DataProvider::new();
-    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
+    spawn_honest_member_with_data_provider(
+        spawner,
+        node_index,
+        n_members,
+        units,
+        network,
+        DataProvider::new(),
+    )
+}
+
+pub fn spawn_honest_member_with_data_provider(
+    spawner: Spawner,
+    node_index: NodeIndex,
+    n_members: NodeCount,
+    units: Vec<u8>,
+    network: impl 'static + NetworkT<NetworkData>,
+    data_provider: DataProvider,
+) -> HonestMember {
     let config = gen_config(node_index, n_members, gen_delay_config());
+    spawn_honest_member_with_config(spawner, config, units, network, data_provider)
+}
+
+pub fn spawn_honest_member_with_config(
+    spawner: Spawner,
+    config: Config,
+    units: Vec<u8>,
+    network: impl 'static + NetworkT<NetworkData>,
+    data_provider: DataProvider,
+) -> HonestMember {
+    let (node_index, n_members) = (config.node_ix(), config.n_members());
+    let (finalization_handler, finalization_rx) = FinalizationHandler::new();
     let (exit_tx, exit_rx) = oneshot::channel();
     let spawner_inner = spawner;
     let unit_loader = Loader::new(units);
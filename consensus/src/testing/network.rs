@@ -0,0 +1,57 @@
+use crate::{Network as NetworkT, NodeCount, NodeIndex, Recipient};
+use aleph_bft_mock::Router;
+
+#[tokio::test]
+async fn routes_messages_to_recipients() {
+    let (router, networks) = Router::<u32>::new(NodeCount(4), 1.0);
+    let router = tokio::spawn(router);
+    let mut networks: Vec<_> = networks.into_iter().map(|(network, _)| network).collect();
+
+    networks[0].send(7, Recipient::Node(NodeIndex(2)));
+    networks[1].send(8, Recipient::Everyone);
+    let mut received = vec![
+        networks[2].next_event().await,
+        networks[2].next_event().await,
+    ];
+    received.sort();
+    assert_eq!(received, vec![Some(7), Some(8)]);
+    assert_eq!(networks[0].next_event().await, Some(8));
+    assert_eq!(networks[3].next_event().await, Some(8));
+    // The broadcast does not reach the sender, so this is the first message it gets.
+    networks[0].send(9, Recipient::Node(NodeIndex(1)));
+    assert_eq!(networks[1].next_event().await, Some(9));
+
+    drop(networks);
+    router
+        .await
+        .expect("router should finish once all peers disconnect");
+}
+
+#[tokio::test]
+async fn partition_stops_messages_between_groups() {
+    let (router, networks) = Router::<u32>::new(NodeCount(4), 1.0);
+    let mut partitions = router.partitionable_network();
+    let router = tokio::spawn(router);
+    let mut networks: Vec<_> = networks.into_iter().map(|(network, _)| network).collect();
+
+    partitions.partition(vec![
+        vec![NodeIndex(0), NodeIndex(1)],
+        vec![NodeIndex(2), NodeIndex(3)],
+    ]);
+    networks[0].send(7, Recipient::Everyone);
+    networks[2].send(8, Recipient::Everyone);
+    assert_eq!(networks[1].next_event().await, Some(7));
+    assert_eq!(networks[3].next_event().await, Some(8));
+
+    partitions.heal();
+    networks[0].send(9, Recipient::Everyone);
+    // Only the message sent after healing crosses the groups.
+    assert_eq!(networks[2].next_event().await, Some(9));
+    assert_eq!(networks[3].next_event().await, Some(9));
+    assert_eq!(networks[1].next_event().await, Some(9));
+
+    drop(networks);
+    router
+        .await
+        .expect("router should finish once all peers disconnect");
+}
@@ -0,0 +1,85 @@
+use crate::{
+    testing::{init_log, spawn_honest_member, HonestMember},
+    NodeCount, NodeIndex, SpawnHandle,
+};
+use aleph_bft_mock::{Data, Router, Spawner};
+use futures::StreamExt;
+use std::time::Duration;
+
+fn drain_finalized(members: &mut [HonestMember], finalized: &mut [Vec<Data>]) -> usize {
+    let mut drained = 0;
+    for (member, finalized) in members.iter_mut().zip(finalized.iter_mut()) {
+        while let Ok(Some(data)) = member.finalization_rx.try_next() {
+            finalized.push(data);
+            drained += 1;
+        }
+    }
+    drained
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn finalization_resumes_after_partition_heals() {
+    init_log();
+    // With 7 members a supermajority is 5, so neither of the groups can proceed alone.
+    let n_members = NodeCount(7);
+    let n_batches = 5;
+    let spawner = Spawner::new();
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let mut partitions = net_hub.partitionable_network();
+    spawner.spawn("network-hub", net_hub);
+
+    let mut members: Vec<_> = networks
+        .into_iter()
+        .map(|(network, _)| {
+            let ix = network.index();
+            spawn_honest_member(spawner, ix, n_members, vec![], network)
+        })
+        .collect();
+    let mut finalized = vec![Vec::new(); n_members.0];
+    for (member, finalized) in members.iter_mut().zip(finalized.iter_mut()) {
+        for _ in 0..n_batches {
+            finalized.push(
+                member
+                    .finalization_rx
+                    .next()
+                    .await
+                    .expect("the member is running"),
+            );
+        }
+    }
+
+    partitions.partition(vec![
+        (0..4).map(NodeIndex).collect(),
+        (4..7).map(NodeIndex).collect(),
+    ]);
+    // Let whatever was on the way before the partition get finalized.
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    drain_finalized(&mut members, &mut finalized);
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(drain_finalized(&mut members, &mut finalized), 0);
+
+    partitions.heal();
+    for (member, finalized) in members.iter_mut().zip(finalized.iter_mut()) {
+        for _ in 0..n_batches {
+            finalized.push(
+                member
+                    .finalization_rx
+                    .next()
+                    .await
+                    .expect("the member is running"),
+            );
+        }
+    }
+    let common = finalized.iter().map(Vec::len).min().unwrap_or(0);
+    assert!(finalized
+        .windows(2)
+        .all(|pair| pair[0][..common] == pair[1][..common]));
+
+    for HonestMember {
+        exit_tx, handle, ..
+    } in members
+    {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+}
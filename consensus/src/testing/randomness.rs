@@ -0,0 +1,52 @@
+use crate::{
+    backup::decode_units,
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member_with_config, HonestMember,
+    },
+    units::UncheckedSignedUnit,
+    NodeCount, NodeIndex, Randomness, SpawnHandle,
+};
+use aleph_bft_mock::{Data, DataProvider, Hasher64, Router, Signature, Spawner};
+use futures::{future::join, StreamExt};
+use rand::{rngs::StdRng, SeedableRng};
+
+type Unit = UncheckedSignedUnit<Hasher64, Data, Signature>;
+
+async fn created_units(seed: u64, n_units: usize) -> Vec<Unit> {
+    let n_members = NodeCount(1);
+    let node_ix = NodeIndex(0);
+    let spawner = Spawner::new();
+    let (net_hub, mut networks) = Router::new(n_members, 1.0);
+    spawner.spawn("network-hub", net_hub);
+    let (network, _) = networks.pop().expect("there is a single network");
+
+    let config = gen_config(node_ix, n_members, gen_delay_config())
+        .with_randomness(Randomness::from_rng(StdRng::seed_from_u64(seed)));
+    let HonestMember {
+        mut finalization_rx,
+        saved_state,
+        exit_tx,
+        handle,
+    } = spawn_honest_member_with_config(spawner, config, vec![], network, DataProvider::new());
+    // Every unit carries data, so this many units were created and saved.
+    for _ in 0..n_units {
+        finalization_rx.next().await.expect("the member is running");
+    }
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+
+    let mut units: Vec<Unit> = decode_units(&saved_state.lock()).expect("backup is correct");
+    units.truncate(n_units);
+    units
+}
+
+#[tokio::test]
+async fn identically_seeded_members_create_identical_units() {
+    init_log();
+    let n_units = 10;
+    // Without other nodes both members wait out the whole delay of the initial unit collection, so run them together.
+    let (units, other_units) =
+        join(created_units(2137, n_units), created_units(2137, n_units)).await;
+    assert_eq!(units.len(), n_units);
+    assert_eq!(units, other_units);
+}
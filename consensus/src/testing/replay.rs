@@ -0,0 +1,72 @@
+use crate::{
+    testing::{init_log, spawn_honest_member, HonestMember},
+    NodeCount, NodeIndex, RecordingNetwork, ReplaySource, SpawnHandle,
+};
+use aleph_bft_mock::{Loader, Router, Saver, Spawner};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn replay_reproduces_finalized_sequence() {
+    init_log();
+
+    let n_members = NodeCount(4);
+    let recorded_node = NodeIndex(0);
+    let n_batches = 10;
+
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let spawner = Spawner::new();
+    spawner.spawn("network-hub", net_hub);
+
+    let recording = Arc::new(Mutex::new(Vec::new()));
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let member = match ix == recorded_node {
+            true => {
+                let network = RecordingNetwork::new(network, Saver::from(recording.clone()));
+                spawn_honest_member(spawner, ix, n_members, vec![], network)
+            }
+            false => spawn_honest_member(spawner, ix, n_members, vec![], network),
+        };
+        members.push(member);
+    }
+
+    let mut batches = Vec::new();
+    // Some more batches, so that the messages needed for the compared ones surely got recorded.
+    for _ in 0..2 * n_batches {
+        batches.push(
+            members[recorded_node.0]
+                .finalization_rx
+                .next()
+                .await
+                .unwrap(),
+        );
+    }
+    for HonestMember {
+        exit_tx, handle, ..
+    } in members
+    {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+
+    // Starting without a backup, so the whole state has to come from the recording.
+    let recording = recording.lock().clone();
+    let network = ReplaySource::new(Loader::new(recording));
+    let HonestMember {
+        mut finalization_rx,
+        exit_tx,
+        handle,
+        ..
+    } = spawn_honest_member(spawner, recorded_node, n_members, vec![], network);
+    let mut replayed_batches = Vec::new();
+    for _ in 0..n_batches {
+        replayed_batches.push(finalization_rx.next().await.unwrap());
+    }
+    assert_eq!(replayed_batches, batches[..n_batches]);
+
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+}
@@ -0,0 +1,159 @@
+use crate::{
+    testing::{
+        gen_config, gen_delay_config, init_log, spawn_honest_member,
+        spawn_honest_member_with_config, HonestMember,
+    },
+    ConsensusStatus, NodeCount, NodeIndex, SpawnHandle, StatusMonitor,
+};
+use aleph_bft_mock::{DataProvider, Router, Spawner};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn status_reflects_progress_of_member() {
+    init_log();
+
+    let n_members = NodeCount(4);
+    let monitored_node = NodeIndex(0);
+    let n_data = 20;
+
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let spawner = Spawner::new();
+    spawner.spawn("network-hub", net_hub);
+
+    let monitor = StatusMonitor::new();
+    assert_eq!(
+        ConsensusStatus::gather(&monitor),
+        ConsensusStatus::default()
+    );
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let member = match ix == monitored_node {
+            true => {
+                let config = gen_config(ix, n_members, gen_delay_config())
+                    .with_status_monitor(monitor.clone());
+                spawn_honest_member_with_config(
+                    spawner,
+                    config,
+                    vec![],
+                    network,
+                    DataProvider::new(),
+                )
+            }
+            false => spawn_honest_member(spawner, ix, n_members, vec![], network),
+        };
+        members.push(member);
+    }
+
+    for _ in 0..n_data {
+        members[monitored_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    let status = ConsensusStatus::gather(&monitor);
+
+    let created = status.highest_created_round.expect("units were created");
+    let ordered = status.highest_ordered_round.expect("heads were ordered");
+    // Ordering needs units from above the head, so the member must have kept creating.
+    assert!(ordered < created);
+    assert_eq!(status.finalization_lag, Some(created - ordered));
+    assert_eq!(status.known_equivocators, 0);
+    let cache_hit_rate = status.cache_hit_rate.expect("votes were needed");
+    assert!((0.0..=1.0).contains(&cache_hit_rate));
+    assert!(status.rounds_per_second > 0.0);
+    let round_clock = monitor.round_clock().expect("units were added");
+    assert!(round_clock.estimate_time(created).is_some());
+    let parent_diversity = monitor.parent_diversity().expect("the member is running");
+    assert!(parent_diversity
+        .citation_rate(monitored_node, NodeIndex(1))
+        .is_some());
+
+    for HonestMember {
+        exit_tx, handle, ..
+    } in members
+    {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+}
+
+#[tokio::test]
+async fn status_reports_silent_nodes() {
+    init_log();
+
+    let n_members = NodeCount(4);
+    let monitored_node = NodeIndex(0);
+    let stopped_node = NodeIndex(3);
+    let n_data = 10;
+
+    let (net_hub, networks) = Router::new(n_members, 1.0);
+    let spawner = Spawner::new();
+    spawner.spawn("network-hub", net_hub);
+
+    let monitor = StatusMonitor::new();
+    let mut members = Vec::new();
+    for (network, _) in networks {
+        let ix = network.index();
+        let member = match ix == monitored_node {
+            true => {
+                let config = gen_config(ix, n_members, gen_delay_config())
+                    .with_status_monitor(monitor.clone());
+                spawn_honest_member_with_config(
+                    spawner,
+                    config,
+                    vec![],
+                    network,
+                    DataProvider::new(),
+                )
+            }
+            false => spawn_honest_member(spawner, ix, n_members, vec![], network),
+        };
+        members.push(member);
+    }
+
+    for _ in 0..n_data {
+        members[monitored_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+    let HonestMember {
+        exit_tx, handle, ..
+    } = members.remove(stopped_node.0);
+    let _ = exit_tx.send(());
+    let _ = handle.await;
+    for _ in 0..n_data {
+        members[monitored_node.0]
+            .finalization_rx
+            .next()
+            .await
+            .expect("the member is running");
+    }
+
+    let liveness = monitor.liveness().expect("the member creates units");
+    assert!(liveness.silent_since(stopped_node).is_some());
+    let status = ConsensusStatus::gather(&monitor);
+    let stopped_since = status
+        .silent_nodes
+        .iter()
+        .find(|(node_id, _)| *node_id == stopped_node)
+        .map(|(_, round)| *round)
+        .expect("the stopped node is silent");
+    // Running nodes can only appear silent for a moment, when they did not create a unit of the newest round yet.
+    for (node_id, round) in status.silent_nodes {
+        if node_id != stopped_node {
+            assert!(round > stopped_since);
+        }
+    }
+
+    for HonestMember {
+        exit_tx, handle, ..
+    } in members
+    {
+        let _ = exit_tx.send(());
+        let _ = handle.await;
+    }
+}
@@ -12,16 +12,19 @@ mod store;
 #[cfg(test)]
 mod testing;
 mod validator;
+mod wire;
 pub(crate) use store::*;
 #[cfg(test)]
 pub use testing::{
-    create_preunits, creator_set, full_unit_to_unchecked_signed_unit, preunit_to_full_unit,
-    preunit_to_signed_unit, preunit_to_unchecked_signed_unit,
-    random_full_parent_reconstrusted_units_up_to, random_full_parent_units_up_to,
-    random_reconstructed_unit_with_parents, random_unit_with_parents, DagUnit as TestingDagUnit,
-    FullUnit as TestingFullUnit, SignedUnit as TestingSignedUnit, WrappedSignedUnit,
+    create_preunits, creator_set, full_parent_reconstructed_units_with_hasher_up_to,
+    full_unit_to_unchecked_signed_unit, preunit_to_full_unit, preunit_to_signed_unit,
+    preunit_to_unchecked_signed_unit, random_full_parent_reconstrusted_units_up_to,
+    random_full_parent_units_up_to, random_reconstructed_unit_with_parents,
+    random_unit_with_parents, DagUnit as TestingDagUnit, FullUnit as TestingFullUnit, RandomDag,
+    SignedUnit as TestingSignedUnit, WrappedSignedUnit,
 };
 pub use validator::{ValidationError, Validator};
+pub use wire::{decode_wire_unit, encode_wire_unit, WIRE_VERSION};
 
 /// The coordinates of a unit, i.e. creator and round. In the absence of forks this uniquely
 /// determines a unit within a session.
@@ -155,7 +158,7 @@ impl<H: Hasher, D: Data> FullUnit<H, D> {
     pub(crate) fn as_pre_unit(&self) -> &PreUnit<H> {
         &self.pre_unit
     }
-    pub(crate) fn data(&self) -> &Option<D> {
+    pub fn data(&self) -> &Option<D> {
         &self.data
     }
     pub(crate) fn included_data(&self) -> Vec<D> {
@@ -178,7 +181,7 @@ impl<H: Hasher, D: Data> Index for FullUnit<H, D> {
 
 pub(crate) type UncheckedSignedUnit<H, D, S> = UncheckedSigned<FullUnit<H, D>, S>;
 
-pub(crate) type SignedUnit<H, D, K> = Signed<FullUnit<H, D>, K>;
+pub type SignedUnit<H, D, K> = Signed<FullUnit<H, D>, K>;
 
 /// Abstract representation of a unit from the Dag point of view.
 pub trait Unit: 'static + Send + Clone {
@@ -209,6 +212,11 @@ pub trait WrappedUnit<H: Hasher>: Unit<Hasher = H> {
 
 pub trait UnitWithParents: Unit {
     fn parents(&self) -> &NodeMap<HashFor<Self>>;
+
+    /// Whether the control hash of the unit commits to exactly its explicit parents.
+    fn verify_control_hash(&self) -> bool {
+        *self.control_hash() == ControlHash::new(self.parents())
+    }
 }
 
 impl<H: Hasher, D: Data> Unit for FullUnit<H, D> {
@@ -106,6 +106,17 @@ impl<U: Unit> UnitStore<U> {
         self.by_hash.get(hash)
     }
 
+    /// All the units of rounds at least the given one, in order of rounds.
+    pub fn units_since(&self, round: Round) -> Vec<&U> {
+        let mut units: Vec<_> = self
+            .by_hash
+            .values()
+            .filter(|unit| unit.round() >= round)
+            .collect();
+        units.sort_by_key(|unit| unit.round());
+        units
+    }
+
     /// The status summary of this store.
     pub fn status(&self) -> UnitStoreStatus {
         let mut top_row = NodeMap::with_size(self.canonical_units.size());
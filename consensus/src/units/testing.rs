@@ -6,9 +6,10 @@ use crate::{
         SignedUnit as GenericSignedUnit, UncheckedSignedUnit as GenericUncheckedSignedUnit, Unit,
         UnitCoord, WrappedUnit,
     },
-    NodeCount, NodeIndex, NodeMap, Round, SessionId, Signed,
+    Hasher, NodeCount, NodeIndex, NodeMap, Round, SessionId, Signed,
 };
 use aleph_bft_mock::{Data, Hash64, Hasher64, Keychain, Signature};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
 type ControlHash = GenericControlHash<Hasher64>;
 type Creator = GenericCreator<Hasher64>;
@@ -191,3 +192,116 @@ pub fn random_full_parent_reconstrusted_units_up_to(
     }
     result
 }
+
+/// Units without data forming a DAG in which every unit has all the units of the previous round as parents.
+/// Unlike the random variants, this only depends on the arguments and on the hasher.
+pub fn full_parent_reconstructed_units_with_hasher_up_to<H: Hasher>(
+    round: Round,
+    n_members: NodeCount,
+    session_id: SessionId,
+) -> Vec<Vec<ReconstructedUnit<GenericFullUnit<H, Data>>>> {
+    let mut result: Vec<Vec<ReconstructedUnit<GenericFullUnit<H, Data>>>> = vec![n_members
+        .into_iterator()
+        .map(|node_id| {
+            let control_hash = GenericControlHash::new(&NodeMap::with_size(n_members));
+            let preunit = GenericPreUnit::new(node_id, 0, control_hash);
+            ReconstructedUnit::initial(GenericFullUnit::new(preunit, None, session_id))
+        })
+        .collect()];
+    for round in 1..=round {
+        let mut parents = NodeMap::with_size(n_members);
+        for parent in result.last().expect("previous round present") {
+            parents.insert(parent.creator(), parent.hash());
+        }
+        let units = n_members
+            .into_iterator()
+            .map(|node_id| {
+                let control_hash = GenericControlHash::new(&parents);
+                let preunit = GenericPreUnit::new(node_id, round, control_hash);
+                ReconstructedUnit::with_parents(
+                    GenericFullUnit::new(preunit, None, session_id),
+                    parents.clone(),
+                )
+                .expect("correct parents")
+            })
+            .collect();
+        result.push(units);
+    }
+    result
+}
+
+/// A DAG generated deterministically from a seed, in which every unit has a random set of parents
+/// satisfying the constraints. With probability `fork_rate` a creator additionally forks in a round,
+/// and units of the next round pick one of the variants as their parent.
+pub struct RandomDag {
+    rounds: Vec<Vec<DagUnit>>,
+    rng: StdRng,
+}
+
+impl RandomDag {
+    pub fn new(seed: u64, n_members: NodeCount, max_round: Round, fork_rate: f64) -> Self {
+        let session_id = 0;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut rounds: Vec<Vec<DagUnit>> = Vec::new();
+        for round in 0..=max_round {
+            let mut units = Vec::new();
+            for creator in n_members.into_iterator() {
+                let parents = match rounds.last() {
+                    Some(previous) => Self::random_parents(&mut rng, n_members, creator, previous),
+                    None => NodeMap::with_size(n_members),
+                };
+                let n_variants = match rng.gen_bool(fork_rate) {
+                    true => 2,
+                    false => 1,
+                };
+                for _ in 0..n_variants {
+                    let preunit = PreUnit::new(creator, round, ControlHash::new(&parents));
+                    let full_unit = FullUnit::new(preunit, Some(rng.gen()), session_id);
+                    units.push(
+                        ReconstructedUnit::with_parents(full_unit, parents.clone())
+                            .expect("correct parents"),
+                    );
+                }
+            }
+            rounds.push(units);
+        }
+        RandomDag { rounds, rng }
+    }
+
+    // Our own unit and enough random others, picking a random variant of each.
+    fn random_parents(
+        rng: &mut StdRng,
+        n_members: NodeCount,
+        creator: NodeIndex,
+        previous: &[DagUnit],
+    ) -> NodeMap<Hash64> {
+        let mut others: Vec<_> = n_members
+            .into_iterator()
+            .filter(|node_id| *node_id != creator)
+            .collect();
+        others.shuffle(rng);
+        let n_parents = rng.gen_range(n_members.consensus_threshold().0..=n_members.0);
+        let mut parents = NodeMap::with_size(n_members);
+        for parent_creator in std::iter::once(creator).chain(others).take(n_parents) {
+            let variants: Vec<_> = previous
+                .iter()
+                .filter(|unit| unit.creator() == parent_creator)
+                .collect();
+            let parent = variants.choose(rng).expect("every creator has a unit");
+            parents.insert(parent_creator, parent.hash());
+        }
+        parents
+    }
+
+    /// All the units, by round.
+    pub fn rounds(&self) -> &Vec<Vec<DagUnit>> {
+        &self.rounds
+    }
+
+    /// All the units in a random arrival order, different with every call.
+    pub fn shuffled(&mut self) -> Vec<DagUnit> {
+        let mut units: Vec<_> = self.rounds.iter().flatten().cloned().collect();
+        units.shuffle(&mut self.rng);
+        units
+    }
+}
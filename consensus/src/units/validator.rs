@@ -1,7 +1,7 @@
 use crate::{
     units::{ControlHash, FullUnit, PreUnit, SignedUnit, UncheckedSignedUnit, Unit},
-    Data, Hasher, Keychain, NodeCount, NodeIndex, NodeMap, Round, SessionId, Signature,
-    SignatureError,
+    Data, Hasher, Keychain, MultiKeychain, NodeCount, NodeIndex, NodeMap, Round, SessionId,
+    Signature, SignatureError,
 };
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
@@ -11,6 +11,7 @@ use std::{
 /// All that can be wrong with a unit except control hash issues.
 #[derive(Eq, PartialEq, Debug)]
 pub enum ValidationError<H: Hasher, D: Data, S: Signature> {
+    UnknownCreator(UncheckedSignedUnit<H, D, S>),
     WrongSignature(UncheckedSignedUnit<H, D, S>),
     WrongSession(FullUnit<H, D>),
     RoundTooHigh(FullUnit<H, D>),
@@ -25,6 +26,12 @@ impl<H: Hasher, D: Data, S: Signature> Display for ValidationError<H, D, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         use ValidationError::*;
         match self {
+            UnknownCreator(usu) => write!(
+                f,
+                "unit by creator {} from outside the committee: {:?}",
+                usu.as_signable().creator().0,
+                usu
+            ),
             WrongSignature(usu) => write!(f, "wrongly signed unit: {:?}", usu),
             WrongSession(fu) => write!(f, "unit from wrong session: {:?}", fu),
             RoundTooHigh(fu) => write!(f, "unit with too high round {}: {:?}", fu.round(), fu),
@@ -71,6 +78,11 @@ pub struct Validator<K: Keychain> {
 type Result<H, D, K> =
     StdResult<SignedUnit<H, D, K>, ValidationError<H, D, <K as Keychain>::Signature>>;
 
+type UncheckedResult<H, D, K> = StdResult<
+    UncheckedSignedUnit<H, D, <K as Keychain>::Signature>,
+    ValidationError<H, D, <K as Keychain>::Signature>,
+>;
+
 impl<K: Keychain> Validator<K> {
     pub fn new(session_id: SessionId, keychain: K, max_round: Round) -> Self {
         Validator {
@@ -88,11 +100,31 @@ impl<K: Keychain> Validator<K> {
         self.keychain.index()
     }
 
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
     pub fn validate_unit<H: Hasher, D: Data>(
         &self,
         uu: UncheckedSignedUnit<H, D, K::Signature>,
     ) -> Result<H, D, K> {
+        let uu = self.check_creator(uu)?;
         let su = uu.check(&self.keychain)?;
+        self.validate_signed_unit(su)
+    }
+
+    // Everything indexed by nodes would panic for such a creator, so it has to be checked first.
+    fn check_creator<H: Hasher, D: Data>(
+        &self,
+        uu: UncheckedSignedUnit<H, D, K::Signature>,
+    ) -> UncheckedResult<H, D, K> {
+        match uu.as_signable().creator().0 < self.node_count().0 {
+            true => Ok(uu),
+            false => Err(ValidationError::UnknownCreator(uu)),
+        }
+    }
+
+    fn validate_signed_unit<H: Hasher, D: Data>(&self, su: SignedUnit<H, D, K>) -> Result<H, D, K> {
         let full_unit = su.as_signable();
         if full_unit.session_id() != self.session_id {
             // NOTE: this implies malicious behavior as the unit's session_id
@@ -149,17 +181,49 @@ impl<K: Keychain> Validator<K> {
     }
 }
 
+impl<K: MultiKeychain> Validator<K> {
+    /// Like `validate_unit`, but for many units at once, verifying all their signatures in a single batch.
+    /// Returns the results in the order of the units.
+    pub fn validate_units<H: Hasher, D: Data>(
+        &self,
+        uus: Vec<UncheckedSignedUnit<H, D, K::Signature>>,
+    ) -> Vec<Result<H, D, K>> {
+        let mut results = Vec::with_capacity(uus.len());
+        let mut known = Vec::new();
+        for uu in uus {
+            match self.check_creator(uu) {
+                Ok(uu) => {
+                    known.push(uu);
+                    results.push(None);
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+        let mut checked = UncheckedSignedUnit::check_batch(known, &self.keychain)
+            .into_iter()
+            .map(|checked| self.validate_signed_unit(checked?));
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| checked.next().expect("every known unit got checked"))
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{ValidationError::*, Validator as GenericValidator};
     use crate::{
         units::{
             full_unit_to_unchecked_signed_unit, preunit_to_unchecked_signed_unit,
-            random_full_parent_units_up_to, random_unit_with_parents, PreUnit,
+            random_full_parent_units_up_to, random_unit_with_parents, ControlHash, PreUnit,
+            UncheckedSignedUnit, Unit,
         },
-        NodeCount, NodeIndex,
+        NodeCount, NodeIndex, NodeMap,
     };
     use aleph_bft_mock::Keychain;
+    use codec::{Decode, Encode};
 
     type Validator = GenericValidator<Keychain>;
 
@@ -284,4 +348,76 @@ mod tests {
         };
         assert_eq!(full_unit, unchecked_unit.into_signable());
     }
+
+    #[test]
+    fn reports_exactly_the_wrongly_signed_unit_in_batch() {
+        let n_members = NodeCount(7);
+        let session_id = 0;
+        let max_round = 2;
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect();
+        let validator = Validator::new(session_id, keychains[0], max_round);
+        let mut units: Vec<_> = random_full_parent_units_up_to(0, n_members, session_id)[0]
+            .iter()
+            .map(|unit| {
+                full_unit_to_unchecked_signed_unit(unit.clone(), &keychains[unit.creator().0])
+            })
+            .collect();
+        // The unit of node 4 with the signature of node 5.
+        let mut encoded = units[4].as_signable().encode();
+        encoded.extend(units[5].signature().encode());
+        units[4] = UncheckedSignedUnit::decode(&mut encoded.as_slice()).expect("decoding works");
+        let results = validator.validate_units(units.clone());
+        assert_eq!(results.len(), n_members.0);
+        for (position, (result, unit)) in results.into_iter().zip(units).enumerate() {
+            match position {
+                4 => assert_eq!(result, Err(WrongSignature(unit))),
+                _ => assert_eq!(result.expect("unit should validate").into_unchecked(), unit),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unit_by_creator_outside_committee() {
+        let n_members = NodeCount(7);
+        let session_id = 0;
+        let max_round = 2;
+        let validator = Validator::new(
+            session_id,
+            Keychain::new(n_members, NodeIndex(0)),
+            max_round,
+        );
+        let outsider = NodeIndex(n_members.0);
+        let preunit = PreUnit::new(
+            outsider,
+            0,
+            ControlHash::new(&NodeMap::with_size(n_members)),
+        );
+        let unchecked_unit = preunit_to_unchecked_signed_unit(
+            preunit,
+            session_id,
+            &Keychain::new(n_members, outsider),
+        );
+        match validator.validate_unit(unchecked_unit.clone()) {
+            Err(UnknownCreator(unit)) => assert_eq!(unit, unchecked_unit),
+            Err(e) => panic!("Unexpected error from validator: {:?}", e),
+            Ok(_) => panic!("Validated bad unit."),
+        }
+
+        let valid_unit = full_unit_to_unchecked_signed_unit(
+            random_full_parent_units_up_to(0, n_members, session_id)[0][1].clone(),
+            &Keychain::new(n_members, NodeIndex(1)),
+        );
+        let mut results = validator
+            .validate_units(vec![unchecked_unit.clone(), valid_unit.clone()])
+            .into_iter();
+        assert_eq!(results.next(), Some(Err(UnknownCreator(unchecked_unit))));
+        let checked_unit = results
+            .next()
+            .expect("there is a result for every unit")
+            .expect("Unit should validate.");
+        assert_eq!(UncheckedSignedUnit::from(checked_unit), valid_unit);
+    }
 }
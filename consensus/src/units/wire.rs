@@ -0,0 +1,412 @@
+use crate::{
+    units::{ControlHash, FullUnit, PreUnit, UncheckedSignedUnit, Unit},
+    Data, Hasher, NodeCount, NodeIndex, NodeSubset, Round, SessionId, Signature,
+};
+use codec::{Compact, Decode, Encode, Input, Output};
+use thiserror::Error;
+
+/// The version of the wire format used for encoding units.
+/// Version 1 is the plain encoding of unchecked signed units, version 2 encodes numbers compactly.
+pub const WIRE_VERSION: u8 = 2;
+
+/// What can go wrong when decoding a unit from the wire.
+#[derive(Eq, Error, Debug, PartialEq)]
+pub enum UnitDecodeError {
+    #[error("Input ended before the whole unit was decoded.")]
+    TruncatedInput,
+    #[error("Unknown wire format version {0}, the newest supported one is {WIRE_VERSION}.")]
+    InvalidVersion(u8),
+    #[error("Parents of the control hash are not a valid node subset.")]
+    BadControlHash,
+    #[error("Creator {creator:?} is out of range for a committee of {n_members:?}.")]
+    CreatorOutOfRange {
+        creator: NodeIndex,
+        n_members: NodeCount,
+    },
+    #[error("{0} bytes left after decoding the unit.")]
+    TrailingBytes(usize),
+    #[error("Malformed unit: {0}.")]
+    Malformed(String),
+}
+
+/// Bytes being decoded, remembering whether decoding ran out of them, so that truncated input
+/// can be told apart from malformed input.
+struct WireInput<'a> {
+    bytes: &'a [u8],
+    truncated: bool,
+}
+
+impl<'a> WireInput<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        WireInput {
+            bytes,
+            truncated: false,
+        }
+    }
+
+    fn error(&self, e: codec::Error) -> UnitDecodeError {
+        match self.truncated {
+            true => UnitDecodeError::TruncatedInput,
+            false => UnitDecodeError::Malformed(e.to_string()),
+        }
+    }
+
+    fn decode<T: Decode>(&mut self) -> Result<T, UnitDecodeError> {
+        T::decode(self).map_err(|e| self.error(e))
+    }
+
+    fn decode_parents(&mut self) -> Result<NodeSubset, UnitDecodeError> {
+        NodeSubset::decode(self).map_err(|e| match self.error(e) {
+            UnitDecodeError::Malformed(_) => UnitDecodeError::BadControlHash,
+            e => e,
+        })
+    }
+}
+
+impl Input for WireInput<'_> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, codec::Error> {
+        // Unknown, so that collections are read rather than rejected upfront when they claim too many bytes.
+        Ok(None)
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), codec::Error> {
+        if into.len() > self.bytes.len() {
+            self.truncated = true;
+            return Err("Not enough data to fill buffer".into());
+        }
+        let (read, rest) = self.bytes.split_at(into.len());
+        into.copy_from_slice(read);
+        self.bytes = rest;
+        Ok(())
+    }
+}
+
+/// A unit as sent over the wire, in a format prefixed with its version, so that
+/// the format can change without breaking nodes receiving units in older ones.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct WireUnit<H: Hasher, D: Data, S: Signature> {
+    pub creator: NodeIndex,
+    pub round: Round,
+    pub parents: NodeSubset,
+    pub control_hash: H::Hash,
+    pub data: Option<D>,
+    pub session_id: SessionId,
+    pub signature: S,
+}
+
+impl<H: Hasher, D: Data, S: Signature> WireUnit<H, D, S> {
+    /// Encode the unit in the current version of the format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![WIRE_VERSION];
+        Compact(self.round).encode_to(&mut bytes);
+        Compact(self.creator.0 as u64).encode_to(&mut bytes);
+        self.parents.encode_to(&mut bytes);
+        self.control_hash.encode_to(&mut bytes);
+        self.data.encode_to(&mut bytes);
+        Compact(self.session_id).encode_to(&mut bytes);
+        self.signature.encode_to(&mut bytes);
+        bytes
+    }
+
+    /// Decode a unit encoded in any known version of the format.
+    pub fn decode(bytes: &[u8]) -> Result<Self, UnitDecodeError> {
+        let (version, bytes) = bytes.split_first().ok_or(UnitDecodeError::TruncatedInput)?;
+        let mut input = WireInput::new(bytes);
+        let unit = match version {
+            1 => Self::decode_v1(&mut input)?,
+            2 => Self::decode_v2(&mut input)?,
+            version => return Err(UnitDecodeError::InvalidVersion(*version)),
+        };
+        // The parents are a subset of the whole committee, so its size bounds the creators.
+        let n_members = NodeCount(unit.parents.size());
+        if unit.creator.0 >= n_members.0 {
+            return Err(UnitDecodeError::CreatorOutOfRange {
+                creator: unit.creator,
+                n_members,
+            });
+        }
+        match input.bytes.len() {
+            0 => Ok(unit),
+            trailing => Err(UnitDecodeError::TrailingBytes(trailing)),
+        }
+    }
+
+    // The plain encoding of an unchecked signed unit.
+    fn decode_v1(input: &mut WireInput) -> Result<Self, UnitDecodeError> {
+        let round = input.decode::<Round>()?;
+        let creator = input.decode::<NodeIndex>()?;
+        let parents = input.decode_parents()?;
+        let control_hash = input.decode::<H::Hash>()?;
+        let data = input.decode::<Option<D>>()?;
+        let session_id = input.decode::<SessionId>()?;
+        let signature = input.decode::<S>()?;
+        Ok(WireUnit {
+            creator,
+            round,
+            parents,
+            control_hash,
+            data,
+            session_id,
+            signature,
+        })
+    }
+
+    fn decode_v2(input: &mut WireInput) -> Result<Self, UnitDecodeError> {
+        let round = input.decode::<Compact<Round>>()?.0;
+        let creator = NodeIndex(input.decode::<Compact<u64>>()?.0 as usize);
+        let parents = input.decode_parents()?;
+        let control_hash = input.decode::<H::Hash>()?;
+        let data = input.decode::<Option<D>>()?;
+        let session_id = input.decode::<Compact<SessionId>>()?.0;
+        let signature = input.decode::<S>()?;
+        Ok(WireUnit {
+            creator,
+            round,
+            parents,
+            control_hash,
+            data,
+            session_id,
+            signature,
+        })
+    }
+
+    /// The unit this is the wire form of.
+    pub fn into_unchecked(self) -> UncheckedSignedUnit<H, D, S> {
+        let WireUnit {
+            creator,
+            round,
+            parents,
+            control_hash,
+            data,
+            session_id,
+            signature,
+        } = self;
+        let control_hash = ControlHash::<H> {
+            parents_mask: parents,
+            combined_hash: control_hash,
+        };
+        let full_unit = FullUnit::new(PreUnit::new(creator, round, control_hash), data, session_id);
+        // Unchecked signed units are encoded as the unit followed by the signature.
+        UncheckedSignedUnit::decode(&mut &(full_unit, signature).encode()[..])
+            .expect("the encoding of a unit and its signature is a valid unchecked signed unit")
+    }
+}
+
+impl<H: Hasher, D: Data, S: Signature> From<UncheckedSignedUnit<H, D, S>> for WireUnit<H, D, S> {
+    fn from(unit: UncheckedSignedUnit<H, D, S>) -> Self {
+        let signature = unit.signature();
+        let full_unit = unit.into_signable();
+        let control_hash = full_unit.control_hash().clone();
+        WireUnit {
+            creator: full_unit.creator(),
+            round: full_unit.round(),
+            parents: control_hash.parents_mask,
+            control_hash: control_hash.combined_hash,
+            data: full_unit.data().clone(),
+            session_id: full_unit.session_id(),
+            signature,
+        }
+    }
+}
+
+/// Append the unit in the current version of the wire format, prefixed with its length,
+/// so that it can be embedded in other encoded data.
+pub fn encode_wire_unit<H: Hasher, D: Data, S: Signature, T: Output + ?Sized>(
+    unit: &UncheckedSignedUnit<H, D, S>,
+    dest: &mut T,
+) {
+    WireUnit::from(unit.clone()).encode().encode_to(dest)
+}
+
+/// Read a unit embedded with [`encode_wire_unit`].
+pub fn decode_wire_unit<H: Hasher, D: Data, S: Signature, I: Input>(
+    input: &mut I,
+) -> Result<UncheckedSignedUnit<H, D, S>, codec::Error> {
+    let bytes = Vec::<u8>::decode(input)?;
+    match WireUnit::<H, D, S>::decode(&bytes) {
+        Ok(unit) => Ok(unit.into_unchecked()),
+        Err(UnitDecodeError::InvalidVersion(_)) => {
+            Err("Unit in an unknown wire format version.".into())
+        }
+        Err(_) => Err("Malformed wire unit.".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        units::{
+            full_unit_to_unchecked_signed_unit, random_full_parent_units_up_to,
+            wire::{UnitDecodeError, WireUnit, WIRE_VERSION},
+            Unit,
+        },
+        NodeCount, NodeIndex, NodeSubset,
+    };
+    use aleph_bft_mock::{Data, Hasher64, Keychain, Signature};
+    use codec::Encode;
+
+    type TestingWireUnit = WireUnit<Hasher64, Data, Signature>;
+
+    #[test]
+    fn round_trips_units() {
+        let n_members = NodeCount(7);
+        let keychains: Vec<_> = n_members
+            .into_iterator()
+            .map(|node_id| Keychain::new(n_members, node_id))
+            .collect();
+        for unit in random_full_parent_units_up_to(3, n_members, 43)
+            .into_iter()
+            .flatten()
+        {
+            let unchecked =
+                full_unit_to_unchecked_signed_unit(unit.clone(), &keychains[unit.creator().0]);
+            let wire_unit = TestingWireUnit::from(unchecked.clone());
+            let bytes = wire_unit.encode();
+            assert_eq!(bytes[0], WIRE_VERSION);
+            // Compact numbers take less space than the plain encoding.
+            assert!(bytes.len() < unchecked.encode().len() + 1);
+            let decoded = TestingWireUnit::decode(&bytes).expect("just encoded");
+            assert_eq!(decoded, wire_unit);
+            let decoded = decoded.into_unchecked();
+            assert_eq!(decoded, unchecked);
+            assert_eq!(decoded.as_signable().hash(), unit.hash());
+            let mut v1_bytes = vec![1];
+            v1_bytes.extend(unchecked.encode());
+            assert_eq!(TestingWireUnit::decode(&v1_bytes), Ok(wire_unit));
+        }
+    }
+
+    #[test]
+    fn decodes_handcrafted_v1_unit() {
+        let mut bytes = vec![1];
+        // round
+        bytes.extend([3, 0]);
+        // creator
+        bytes.extend([2, 0, 0, 0, 0, 0, 0, 0]);
+        // parents of 4 members, the first, second and fourth ones
+        bytes.extend([4, 0, 0, 0, 4, 0b1101_0000]);
+        // control hash
+        bytes.extend([7; 8]);
+        // data
+        bytes.extend([1, 42, 0, 0, 0]);
+        // session id
+        bytes.extend([43, 0, 0, 0, 0, 0, 0, 0]);
+        // signature, a message and the index of the signer
+        bytes.extend([8, 5, 6]);
+        bytes.extend([2, 0, 0, 0, 0, 0, 0, 0]);
+
+        let unit = TestingWireUnit::decode(&bytes).expect("valid v1 unit");
+        let mut parents = NodeSubset::with_size(NodeCount(4));
+        for parent in [0, 1, 3] {
+            parents.insert(NodeIndex(parent));
+        }
+        assert_eq!(
+            unit,
+            WireUnit {
+                creator: NodeIndex(2),
+                round: 3,
+                parents,
+                control_hash: [7; 8],
+                data: Some(42),
+                session_id: 43,
+                signature: Signature::new(vec![5, 6], NodeIndex(2)),
+            }
+        );
+        let reencoded = TestingWireUnit::decode(&unit.encode()).expect("just encoded");
+        assert_eq!(reencoded, unit);
+    }
+
+    // A version 2 unit in a committee of 4 members, with the given creator and parents byte.
+    fn handcrafted_v2_bytes(creator: u8, parents: u8) -> Vec<u8> {
+        let mut bytes = vec![2];
+        // round and creator, compact
+        bytes.extend([3 << 2, creator << 2]);
+        // parents of 4 members
+        bytes.extend([4, 0, 0, 0, 4, parents]);
+        // control hash
+        bytes.extend([7; 8]);
+        // data
+        bytes.extend([1, 42, 0, 0, 0]);
+        // session id, compact
+        bytes.extend([43 << 2]);
+        // signature, a message and the index of the signer
+        bytes.extend([8, 5, 6]);
+        bytes.extend([2, 0, 0, 0, 0, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn decodes_handcrafted_v2_unit() {
+        let unit =
+            TestingWireUnit::decode(&handcrafted_v2_bytes(2, 0b1101_0000)).expect("valid v2 unit");
+        assert_eq!(unit.creator, NodeIndex(2));
+        assert_eq!(unit.round, 3);
+        assert_eq!(unit.session_id, 43);
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        assert_eq!(
+            TestingWireUnit::decode(&[]),
+            Err(UnitDecodeError::TruncatedInput)
+        );
+        let bytes = handcrafted_v2_bytes(2, 0b1101_0000);
+        for len in 1..bytes.len() {
+            assert_eq!(
+                TestingWireUnit::decode(&bytes[..len]),
+                Err(UnitDecodeError::TruncatedInput)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_version() {
+        let mut bytes = handcrafted_v2_bytes(2, 0b1101_0000);
+        bytes[0] = WIRE_VERSION + 1;
+        assert_eq!(
+            TestingWireUnit::decode(&bytes),
+            Err(UnitDecodeError::InvalidVersion(WIRE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_control_hash() {
+        // A parent beyond the 4 members.
+        assert_eq!(
+            TestingWireUnit::decode(&handcrafted_v2_bytes(2, 0b1101_0001)),
+            Err(UnitDecodeError::BadControlHash)
+        );
+    }
+
+    #[test]
+    fn rejects_creator_out_of_range() {
+        assert_eq!(
+            TestingWireUnit::decode(&handcrafted_v2_bytes(4, 0b1101_0000)),
+            Err(UnitDecodeError::CreatorOutOfRange {
+                creator: NodeIndex(4),
+                n_members: NodeCount(4),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut bytes = handcrafted_v2_bytes(2, 0b1101_0000);
+        bytes.extend([0, 0]);
+        assert_eq!(
+            TestingWireUnit::decode(&bytes),
+            Err(UnitDecodeError::TrailingBytes(2))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let mut bytes = handcrafted_v2_bytes(2, 0b1101_0000);
+        // Neither `None` nor `Some`.
+        bytes[17] = 2;
+        assert!(matches!(
+            TestingWireUnit::decode(&bytes),
+            Err(UnitDecodeError::Malformed(_))
+        ));
+    }
+}
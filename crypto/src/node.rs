@@ -85,7 +85,17 @@ impl NodeCount {
 
     /// If this is the total node count, what number of nodes is required for secure consensus.
     pub fn consensus_threshold(&self) -> NodeCount {
-        (*self * 2) / 3 + NodeCount(1)
+        self.supermajority()
+    }
+
+    /// If this is the total node count, the smallest number of nodes that is more than two thirds of it.
+    pub fn supermajority(self) -> NodeCount {
+        (self * 2) / 3 + NodeCount(1)
+    }
+
+    /// Whether this many nodes are more than two thirds of the total node count.
+    pub fn is_supermajority(self, total: NodeCount) -> bool {
+        self >= total.supermajority()
     }
 }
 
@@ -173,6 +183,40 @@ impl<T> NodeMap<T> {
     pub fn item_count(&self) -> usize {
         self.iter().count()
     }
+
+    /// The items present and equal in both maps. Panics if the maps differ in size.
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone + PartialEq,
+    {
+        assert_eq!(self.size(), other.size(), "node maps differ in size");
+        NodeMap(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(ours, theirs)| match (ours, theirs) {
+                    (Some(ours), Some(theirs)) if ours == theirs => Some(ours.clone()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// The nodes for which the items differ, or are present in only one of the maps.
+    /// Panics if the maps differ in size.
+    pub fn symmetric_difference(&self, other: &Self) -> NodeSubset
+    where
+        T: PartialEq,
+    {
+        assert_eq!(self.size(), other.size(), "node maps differ in size");
+        NodeSubset(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(ours, theirs)| ours != theirs)
+                .collect(),
+        )
+    }
 }
 
 impl<T: 'static> IntoIterator for NodeMap<T> {
@@ -226,6 +270,14 @@ impl NodeSubset {
         self.0.set(i.0, true);
     }
 
+    pub fn remove(&mut self, i: NodeIndex) {
+        self.0.set(i.0, false);
+    }
+
+    pub fn contains(&self, i: NodeIndex) -> bool {
+        self.0.get(i.0).unwrap_or(false)
+    }
+
     pub fn size(&self) -> usize {
         self.0.len()
     }
@@ -244,6 +296,30 @@ impl NodeSubset {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// The nodes present in either of the subsets. Panics if the subsets differ in size.
+    pub fn union(&self, other: &Self) -> Self {
+        assert_eq!(self.size(), other.size(), "node subsets differ in size");
+        let mut result = self.clone();
+        result.0.or(&other.0);
+        result
+    }
+
+    /// The nodes present in both subsets. Panics if the subsets differ in size.
+    pub fn intersection(&self, other: &Self) -> Self {
+        assert_eq!(self.size(), other.size(), "node subsets differ in size");
+        let mut result = self.clone();
+        result.0.and(&other.0);
+        result
+    }
+
+    /// The nodes present in this subset, but not in the other one. Panics if the subsets differ in size.
+    pub fn difference(&self, other: &Self) -> Self {
+        assert_eq!(self.size(), other.size(), "node subsets differ in size");
+        let mut result = self.clone();
+        result.0.difference(&other.0);
+        result
+    }
 }
 
 impl Encode for NodeSubset {
@@ -295,8 +371,27 @@ impl fmt::Display for NodeSubset {
 #[cfg(test)]
 mod tests {
 
-    use crate::node::{NodeIndex, NodeSubset};
+    use crate::node::{NodeCount, NodeIndex, NodeMap, NodeSubset};
     use codec::{Decode, Encode};
+    #[test]
+    fn supermajority_is_more_than_two_thirds() {
+        for n in 1..=100 {
+            let total = NodeCount(n);
+            let supermajority = total.supermajority();
+            // The smallest count k with 3k > 2n, i.e. tolerating f = (n - 1) / 3 faulty nodes.
+            assert!(3 * supermajority.0 > 2 * n);
+            assert!(3 * (supermajority.0 - 1) <= 2 * n);
+            assert_eq!(supermajority.0, n - (n - 1) / 3);
+            assert_eq!(total.consensus_threshold(), supermajority);
+            for count in 0..=n {
+                assert_eq!(
+                    NodeCount(count).is_supermajority(total),
+                    count >= supermajority.0,
+                );
+            }
+        }
+    }
+
     #[test]
     fn decoding_node_index_works() {
         for i in 0..1000 {
@@ -363,4 +458,88 @@ mod tests {
         }
         assert!(bnm.encode().len() < 20);
     }
+
+    fn partially_overlapping_maps() -> (NodeMap<u64>, NodeMap<u64>) {
+        let mut ours = NodeMap::with_size(NodeCount(6));
+        let mut theirs = NodeMap::with_size(NodeCount(6));
+        // Equal in 0 and 1, different in 2, only ours in 3, only theirs in 4, absent in 5.
+        for (node_id, item) in [(0, 10), (1, 11), (2, 12), (3, 13)] {
+            ours.insert(NodeIndex(node_id), item);
+        }
+        for (node_id, item) in [(0, 10), (1, 11), (2, 22), (4, 24)] {
+            theirs.insert(NodeIndex(node_id), item);
+        }
+        (ours, theirs)
+    }
+
+    #[test]
+    fn node_map_intersection_keeps_equal_items() {
+        let (ours, theirs) = partially_overlapping_maps();
+        let intersection = ours.intersection(&theirs);
+        assert_eq!(intersection.size(), NodeCount(6));
+        assert_eq!(
+            intersection.iter().collect::<Vec<_>>(),
+            vec![(NodeIndex(0), &10), (NodeIndex(1), &11)]
+        );
+        assert_eq!(theirs.intersection(&ours), intersection);
+    }
+
+    #[test]
+    fn node_map_symmetric_difference_finds_differing_nodes() {
+        let (ours, theirs) = partially_overlapping_maps();
+        let difference = ours.symmetric_difference(&theirs);
+        assert_eq!(difference.size(), 6);
+        assert_eq!(
+            difference.elements().collect::<Vec<_>>(),
+            vec![NodeIndex(2), NodeIndex(3), NodeIndex(4)]
+        );
+        assert_eq!(theirs.symmetric_difference(&ours), difference);
+        assert!(ours.symmetric_difference(&ours).is_empty());
+    }
+
+    fn subset_of(size: usize, elements: &[usize]) -> NodeSubset {
+        let mut subset = NodeSubset::with_size(NodeCount(size));
+        for i in elements {
+            subset.insert(NodeIndex(*i));
+        }
+        subset
+    }
+
+    #[test]
+    fn node_subset_insert_and_remove() {
+        let mut subset = subset_of(5, &[1, 3]);
+        assert!(subset.contains(NodeIndex(1)));
+        assert!(!subset.contains(NodeIndex(2)));
+        assert!(!subset.contains(NodeIndex(7)));
+        subset.remove(NodeIndex(1));
+        subset.remove(NodeIndex(2));
+        assert_eq!(subset, subset_of(5, &[3]));
+        assert_eq!(subset.len(), 1);
+    }
+
+    #[test]
+    fn node_subset_set_operations() {
+        let ours = subset_of(6, &[0, 1, 2]);
+        let theirs = subset_of(6, &[1, 2, 4]);
+        assert_eq!(ours.union(&theirs), subset_of(6, &[0, 1, 2, 4]));
+        assert_eq!(ours.intersection(&theirs), subset_of(6, &[1, 2]));
+        assert_eq!(ours.difference(&theirs), subset_of(6, &[0]));
+        assert_eq!(theirs.difference(&ours), subset_of(6, &[4]));
+        assert!(ours.difference(&ours).is_empty());
+        assert_eq!(ours.union(&theirs).size(), 6);
+    }
+
+    #[test]
+    fn node_map_converts_to_subset_of_present_nodes() {
+        let (ours, _) = partially_overlapping_maps();
+        let subset = ours.to_subset();
+        assert_eq!(subset.size(), ours.size().0);
+        assert_eq!(
+            subset.elements().collect::<Vec<_>>(),
+            ours.iter().map(|(node_id, _)| node_id).collect::<Vec<_>>()
+        );
+        for node_id in ours.size().into_iterator() {
+            assert_eq!(subset.contains(node_id), ours.get(node_id).is_some());
+        }
+    }
 }
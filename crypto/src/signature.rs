@@ -57,6 +57,43 @@ pub trait MultiKeychain: Keychain {
     ) -> Self::PartialMultisignature;
     /// Checks if enough signatures have beed added.
     fn is_complete(&self, msg: &[u8], partial: &Self::PartialMultisignature) -> bool;
+    /// Combines signatures of `msg` made by the given nodes into a multisignature.
+    /// Returns `None` if the result would not be complete, e.g. there are too few signatures
+    /// or some of them are invalid.
+    fn combine(
+        &self,
+        msg: &[u8],
+        partials: &[(NodeIndex, Self::Signature)],
+    ) -> Option<Self::PartialMultisignature> {
+        let ((first_index, first_signature), rest) = partials.split_first()?;
+        let multisignature = rest.iter().fold(
+            self.bootstrap_multi(first_signature, *first_index),
+            |multisignature, (index, signature)| multisignature.add_signature(signature, *index),
+        );
+        match self.is_complete(msg, &multisignature) {
+            true => Some(multisignature),
+            false => None,
+        }
+    }
+    /// Verifies many signatures at once, each given with the index of the signer and the signed message.
+    /// Returns the positions of the invalid signatures, in ascending order, if there are any.
+    /// By default the signatures are verified one by one, keychains supporting batch verification
+    /// should override this.
+    fn verify_batch(
+        &self,
+        items: &[(NodeIndex, &[u8], &Self::Signature)],
+    ) -> Result<(), Vec<usize>> {
+        let failed: Vec<_> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, (index, msg, sgn))| !self.verify(msg, sgn, *index))
+            .map(|(position, _)| position)
+            .collect();
+        match failed.is_empty() {
+            true => Ok(()),
+            false => Err(failed),
+        }
+    }
 }
 
 /// A set of signatures of a subset of nodes serving as a (partial) multisignature
@@ -140,6 +177,32 @@ impl<T: Signable + Index, S: Signature> UncheckedSigned<T, S> {
     }
 }
 
+impl<T: Signable + Index, S: Signature> UncheckedSigned<T, S> {
+    /// Like `check`, but verifies all the signatures in a single batch, returning the results in the same order.
+    pub fn check_batch<MK: MultiKeychain<Signature = S>>(
+        unchecked: Vec<Self>,
+        keychain: &MK,
+    ) -> Vec<Result<Signed<T, MK>, SignatureError<T, S>>> {
+        let hashes: Vec<_> = unchecked.iter().map(|u| u.signable.hash()).collect();
+        let items: Vec<_> = unchecked
+            .iter()
+            .zip(&hashes)
+            .map(|(u, hash)| (u.signable.index(), hash.as_ref(), &u.signature))
+            .collect();
+        let failed = keychain.verify_batch(&items).err().unwrap_or_default();
+        unchecked
+            .into_iter()
+            .enumerate()
+            .map(
+                |(position, unchecked)| match failed.binary_search(&position) {
+                    Ok(_) => Err(SignatureError { unchecked }),
+                    Err(_) => Ok(Signed { unchecked }),
+                },
+            )
+            .collect()
+    }
+}
+
 impl<T: Signable + Index, S: Signature> Index for UncheckedSigned<T, S> {
     fn index(&self) -> NodeIndex {
         self.signable.index()
@@ -312,6 +375,22 @@ impl<T: Signable, MK: MultiKeychain> Multisigned<T, MK> {
     pub fn into_unchecked(self) -> UncheckedSigned<T, MK::PartialMultisignature> {
         self.unchecked
     }
+
+    /// Combine signatures of the object made by the given nodes into a certificate.
+    /// Returns `None` if the signatures do not form a complete multisignature.
+    pub fn combine(
+        signable: T,
+        partials: &[(NodeIndex, MK::Signature)],
+        keychain: &MK,
+    ) -> Option<Self> {
+        let signature = keychain.combine(signable.hash().as_ref(), partials)?;
+        Some(Multisigned {
+            unchecked: UncheckedSigned {
+                signable,
+                signature,
+            },
+        })
+    }
 }
 
 impl<T: Signable, MK: MultiKeychain> From<Multisigned<T, MK>>
@@ -429,8 +508,8 @@ impl<T: Signable, MK: MultiKeychain> PartiallyMultisigned<T, MK> {
 mod tests {
 
     use crate::{
-        Index, Keychain, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature,
-        PartiallyMultisigned, Signable, SignatureSet, Signed,
+        Index, Keychain, MultiKeychain, Multisigned, NodeCount, NodeIndex, PartialMultisignature,
+        PartiallyMultisigned, Signable, SignatureSet, Signed, UncheckedSigned,
     };
     use codec::{Decode, Encode};
     use std::fmt::Debug;
@@ -602,6 +681,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_batch_reports_invalid_signature() {
+        let node_count: NodeCount = 5.into();
+        let keychains: Vec<TestMultiKeychain> = (0..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let mut unchecked: Vec<_> = keychains
+            .iter()
+            .map(|keychain| Signed::sign_with_index(test_message(), keychain).into_unchecked())
+            .collect();
+        unchecked[3].signature.index = 1.into();
+        let hashes: Vec<_> = unchecked.iter().map(|u| u.signable.hash()).collect();
+        let items: Vec<_> = unchecked
+            .iter()
+            .zip(&hashes)
+            .map(|(u, hash)| (u.index(), hash.as_ref(), &u.signature))
+            .collect();
+        assert_eq!(keychains[0].verify_batch(&items), Err(vec![3]));
+        assert_eq!(keychains[0].verify_batch(&items[..3]), Ok(()));
+
+        let checked = UncheckedSigned::check_batch(unchecked, &keychains[0]);
+        assert_eq!(checked.len(), node_count.0);
+        for (position, result) in checked.into_iter().enumerate() {
+            assert_eq!(result.is_err(), position == 3);
+        }
+    }
+
     #[test]
     fn test_incomplete_multisignature() {
         let msg = test_message();
@@ -636,4 +742,62 @@ mod tests {
             partial
         );
     }
+
+    #[test]
+    fn test_combined_multisignatures() {
+        let msg = test_message();
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let partials: Vec<_> = keychains
+            .iter()
+            .take(5)
+            .map(|keychain| (keychain.index(), keychain.sign(&msg.hash())))
+            .collect();
+        let multisigned = Multisigned::combine(msg.clone(), &partials, &keychains[0])
+            .expect("5 signatures should form a complete signature");
+        for keychain in &keychains {
+            assert!(multisigned
+                .clone()
+                .into_unchecked()
+                .check_multi(keychain)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_combining_too_few_signatures() {
+        let msg = test_message();
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        let partials: Vec<_> = keychains
+            .iter()
+            .take(4)
+            .map(|keychain| (keychain.index(), keychain.sign(&msg.hash())))
+            .collect();
+        assert!(keychains[0].combine(&msg.hash(), &partials).is_none());
+        assert!(keychains[0].combine(&msg.hash(), &[]).is_none());
+    }
+
+    #[test]
+    fn test_combining_invalid_signatures() {
+        let msg = test_message();
+        let node_count: NodeCount = 7.into();
+        let keychains: Vec<TestMultiKeychain> = (0..node_count.0)
+            .map(|i| test_multi_keychain(node_count, i.into()))
+            .collect();
+        // Every signature is attributed to the wrong node.
+        let partials: Vec<_> = keychains
+            .iter()
+            .take(5)
+            .map(|keychain| {
+                let index = NodeIndex((keychain.index().0 + 1) % node_count.0);
+                (index, keychain.sign(&msg.hash()))
+            })
+            .collect();
+        assert!(keychains[0].combine(&msg.hash(), &partials).is_none());
+    }
 }
@@ -1,5 +1,13 @@
 use aleph_bft_types::Hasher;
-use std::{collections::hash_map::DefaultHasher, hash::Hasher as StdHasher};
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use codec::{Decode, Encode, Error, Input};
+use sha2::Sha256;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Debug,
+    hash::{Hash, Hasher as StdHasher},
+    marker::PhantomData,
+};
 
 // A hasher from the standard library that hashes to u64, should be enough to
 // avoid collisions in testing.
@@ -17,3 +25,95 @@ impl Hasher for Hasher64 {
 }
 
 pub type Hash64 = <Hasher64 as Hasher>::Hash;
+
+const MAX_HASH_LENGTH: usize = 64;
+
+/// A hash of any length up to 64 bytes, stored in a buffer of the maximal length.
+/// Hashes are ordered by length first, then by their bytes.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Encode)]
+pub struct RuntimeHash {
+    len: u8,
+    bytes: [u8; MAX_HASH_LENGTH],
+}
+
+impl RuntimeHash {
+    fn new(hash: &[u8]) -> Self {
+        assert!(hash.len() <= MAX_HASH_LENGTH, "hash too long");
+        let mut bytes = [0; MAX_HASH_LENGTH];
+        bytes[..hash.len()].copy_from_slice(hash);
+        RuntimeHash {
+            len: hash.len() as u8,
+            bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for RuntimeHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl Decode for RuntimeHash {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let len = u8::decode(input)?;
+        let bytes = <[u8; MAX_HASH_LENGTH]>::decode(input)?;
+        if len as usize > MAX_HASH_LENGTH {
+            return Err(Error::from("Hash length exceeds the buffer."));
+        }
+        if bytes[len as usize..].iter().any(|byte| *byte != 0) {
+            return Err(Error::from(
+                "Non-canonical encoding. Bytes after the hash should be all 0.",
+            ));
+        }
+        Ok(RuntimeHash { len, bytes })
+    }
+}
+
+/// A hashing algorithm for a `RuntimeHasher`.
+pub trait HashAlgo: Copy + Clone + Eq + PartialEq + Hash + Debug + Send + Sync + 'static {
+    fn digest(x: &[u8]) -> RuntimeHash;
+}
+
+/// SHA-256, with 32-byte hashes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Sha256Algo;
+
+impl HashAlgo for Sha256Algo {
+    fn digest(x: &[u8]) -> RuntimeHash {
+        RuntimeHash::new(&Sha256::digest(x))
+    }
+}
+
+/// BLAKE2b with 32-byte hashes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Blake2b256Algo;
+
+impl HashAlgo for Blake2b256Algo {
+    fn digest(x: &[u8]) -> RuntimeHash {
+        RuntimeHash::new(&Blake2b::<U32>::digest(x))
+    }
+}
+
+/// BLAKE2b with 64-byte hashes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Blake2b512Algo;
+
+impl HashAlgo for Blake2b512Algo {
+    fn digest(x: &[u8]) -> RuntimeHash {
+        RuntimeHash::new(&Blake2b512::digest(x))
+    }
+}
+
+/// A hasher using the algorithm `A`, with hashes of the same type whatever the algorithm,
+/// so switching between algorithms does not change the hash type.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RuntimeHasher<A: HashAlgo>(PhantomData<A>);
+
+impl<A: HashAlgo> Hasher for RuntimeHasher<A> {
+    type Hash = RuntimeHash;
+
+    fn hash(x: &[u8]) -> Self::Hash {
+        A::digest(x)
+    }
+}
@@ -8,8 +8,12 @@ mod spawner;
 
 pub use crypto::{BadSigning, Keychain, PartialMultisignature, Signable, Signature};
 pub use dataio::{Data, DataProvider, FinalizationHandler, Loader, Saver, StalledDataProvider};
-pub use hasher::{Hash64, Hasher64};
+pub use hasher::{
+    Blake2b256Algo, Blake2b512Algo, Hash64, HashAlgo, Hasher64, RuntimeHash, RuntimeHasher,
+    Sha256Algo,
+};
 pub use network::{
-    Network, NetworkHook, NetworkReceiver, NetworkSender, Peer, ReconnectSender, Router,
+    Network, NetworkHook, NetworkReceiver, NetworkSender, PartitionableNetwork, Peer,
+    ReconnectSender, Router,
 };
 pub use spawner::Spawner;
@@ -7,11 +7,13 @@ use futures::{
     Future, StreamExt,
 };
 use log::debug;
+use parking_lot::Mutex;
 use std::{
     cell::RefCell,
     collections::HashMap,
     fmt::{Debug, Formatter},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
@@ -83,6 +85,43 @@ pub trait NetworkHook<D>: Send {
     fn update_state(&mut self, data: &mut D, sender: NodeIndex, recipient: NodeIndex);
 }
 
+/// Controls partitions of the network of a [`Router`], obtained with [`Router::partitionable_network`].
+#[derive(Clone, Debug, Default)]
+pub struct PartitionableNetwork {
+    // The group of every node, empty when the network is not partitioned.
+    groups: Arc<Mutex<HashMap<NodeIndex, usize>>>,
+}
+
+impl PartitionableNetwork {
+    /// Split the network into the groups, from now on messages are only delivered within a group.
+    /// Nodes not present in any of the groups get cut off from everyone.
+    pub fn partition(&mut self, groups: Vec<Vec<NodeIndex>>) {
+        let mut node_groups = self.groups.lock();
+        node_groups.clear();
+        for (group_id, group) in groups.into_iter().enumerate() {
+            for node in group {
+                node_groups.insert(node, group_id);
+            }
+        }
+    }
+
+    /// Reconnect all the nodes, messages dropped during the partition are not delivered.
+    pub fn heal(&mut self) {
+        self.groups.lock().clear();
+    }
+
+    fn separates(&self, sender: NodeIndex, recipient: NodeIndex) -> bool {
+        let groups = self.groups.lock();
+        if groups.is_empty() {
+            return false;
+        }
+        match (groups.get(&sender), groups.get(&recipient)) {
+            (Some(sender_group), Some(recipient_group)) => sender_group != recipient_group,
+            _ => true,
+        }
+    }
+}
+
 type ReconnectReceiver<D> = UnboundedReceiver<(NodeIndex, oneshot::Sender<Network<D>>)>;
 pub type ReconnectSender<D> = UnboundedSender<(NodeIndex, oneshot::Sender<Network<D>>)>;
 
@@ -92,6 +131,7 @@ pub struct Router<D: Debug> {
     hook_list: RefCell<Vec<Box<dyn NetworkHook<D>>>>,
     peer_reconnect_rx: ReconnectReceiver<D>,
     reliability: f64,
+    partitions: PartitionableNetwork,
 }
 
 impl<D: Debug> Debug for Router<D> {
@@ -117,6 +157,7 @@ impl<D: Debug> Router<D> {
             hook_list: RefCell::new(Vec::new()),
             peer_reconnect_rx,
             reliability,
+            partitions: PartitionableNetwork::default(),
         };
         let mut networks = Vec::new();
         for ix in n_members.into_iterator() {
@@ -156,6 +197,11 @@ impl<D: Debug> Router<D> {
     pub fn reliability(&self) -> f64 {
         self.reliability
     }
+
+    /// A handle for partitioning the network, effective also after the router gets spawned.
+    pub fn partitionable_network(&self) -> PartitionableNetwork {
+        self.partitions.clone()
+    }
 }
 
 impl<D: Debug> Future for Router<D> {
@@ -206,6 +252,10 @@ impl<D: Debug> Future for Router<D> {
                 debug!("Simulated network fail.");
                 continue;
             }
+            if this.partitions.separates(sender, recipient) {
+                debug!("Simulated network partition.");
+                continue;
+            }
 
             if let Some(peer) = this.peers.borrow().get(&recipient) {
                 for hook in this.hook_list.borrow_mut().iter_mut() {
@@ -15,6 +15,15 @@ pub trait DataProvider<Data>: Sync + Send + 'static {
     async fn get_data(&mut self) -> Option<Data>;
 }
 
+/// A check that the data is actually available, e.g. fetched from a data availability layer,
+/// before it gets reported as finalized.
+#[async_trait]
+pub trait DataAvailability<Data>: Sync + Send + 'static {
+    /// Whether the data is available. Can take as long as needed to find out,
+    /// data reported as unavailable gets checked again after a while.
+    async fn is_available(&self, data: &Data) -> bool;
+}
+
 /// The source of finalization of the units that consensus produces.
 ///
 /// The [`FinalizationHandler::data_finalized`] method is called whenever a piece of data input to the algorithm
@@ -33,4 +42,8 @@ pub trait FinalizationHandler<Data>: Sync + Send + 'static {
             self.data_finalized(d);
         }
     }
+    /// Nothing more will be finalized in this session, as the head of the last round that can be decided below
+    /// the maximal round of the session has been finalized. Called at most once, and never if that head does not
+    /// get elected.
+    fn session_finished(&mut self) {}
 }